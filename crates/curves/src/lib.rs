@@ -134,6 +134,18 @@ pub trait EllipticCurve: EllipticCurveParameters {
     /// Doubles a point on the curve.
     fn ec_double(p: &AffinePoint<Self>) -> AffinePoint<Self>;
 
+    /// Adds two points on the curve like [`EllipticCurve::ec_add`], or returns `None` if the
+    /// formulas of the curve don't cover them, such as two equal points.
+    fn ec_checked_add(p: &AffinePoint<Self>, q: &AffinePoint<Self>) -> Option<AffinePoint<Self>> {
+        Some(Self::ec_add(p, q))
+    }
+
+    /// Doubles a point on the curve like [`EllipticCurve::ec_double`], or returns `None` if the
+    /// formulas of the curve don't cover it, such as a point of order two.
+    fn ec_checked_double(p: &AffinePoint<Self>) -> Option<AffinePoint<Self>> {
+        Some(Self::ec_double(p))
+    }
+
     /// Returns the generator of the curve group for a curve/subgroup of prime order.
     fn ec_generator() -> AffinePoint<Self>;
 
@@ -148,6 +160,26 @@ pub trait EllipticCurve: EllipticCurveParameters {
     fn nb_scalar_bits() -> usize {
         Self::BaseField::NB_LIMBS * Self::BaseField::NB_BITS_PER_LIMB
     }
+
+    /// Adds two different points given as little-endian words, `x` then `y`, with fixed-width
+    /// arithmetic. Returns `None` if the curve has no fixed-width backend or the points fall
+    /// outside the cases it handles, in which case [`EllipticCurve::ec_add`] is the reference.
+    fn ec_add_words(_p: &[u32], _q: &[u32]) -> Option<Vec<u32>> {
+        None
+    }
+
+    /// Doubles a point given as little-endian words with fixed-width arithmetic, like
+    /// [`EllipticCurve::ec_add_words`].
+    fn ec_double_words(_p: &[u32]) -> Option<Vec<u32>> {
+        None
+    }
+
+    /// Computes the `y` coordinate of the point with the `x` coordinate given as little-endian
+    /// words and the given sign bit with fixed-width arithmetic, like
+    /// [`EllipticCurve::ec_add_words`].
+    fn ec_decompress_words(_x: &[u32], _sign_bit: u32) -> Option<Vec<u32>> {
+        None
+    }
 }
 
 impl<E: EllipticCurve> Add<&AffinePoint<E>> for &AffinePoint<E> {
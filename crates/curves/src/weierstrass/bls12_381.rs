@@ -4,7 +4,11 @@ use num::{BigUint, Num, Zero};
 use serde::{Deserialize, Serialize};
 use typenum::{U48, U94};
 
-use super::{FieldType, FpOpField, SwCurve, WeierstrassParameters};
+use super::{
+    fixed,
+    fp::{Fp, FpElement, FpModulus, Montgomery},
+    FieldType, FpOpField, SwCurve, WeierstrassParameters,
+};
 use crate::{
     params::{FieldParameters, NumLimbs},
     CurveType, EllipticCurveParameters,
@@ -53,6 +57,12 @@ impl FieldParameters for Bls12381BaseField {
 
 impl FpOpField for Bls12381BaseField {
     const FIELD_TYPE: FieldType = FieldType::Bls12381;
+
+    type Fp = Fp<6, Self>;
+}
+
+impl FpModulus<6> for Bls12381BaseField {
+    const MONTGOMERY: Montgomery<6> = Montgomery::new(Self::MODULUS);
 }
 
 impl NumLimbs for Bls12381BaseField {
@@ -109,6 +119,18 @@ impl WeierstrassParameters for Bls12381Parameters {
     fn b_int() -> BigUint {
         BigUint::from(4u32)
     }
+
+    fn add_words(p: &[u32], q: &[u32]) -> Option<Vec<u32>> {
+        fixed::add::<Fp<6, Bls12381BaseField>>(p, q)
+    }
+
+    fn double_words(p: &[u32]) -> Option<Vec<u32>> {
+        fixed::double::<Fp<6, Bls12381BaseField>>(p)
+    }
+
+    fn decompress_words(x: &[u32], sign_bit: u32) -> Option<Vec<u32>> {
+        bls12381_decompress_words(x, sign_bit)
+    }
 }
 
 pub fn bls12381_decompress<E: EllipticCurve>(bytes_be: &[u8], sign_bit: u32) -> AffinePoint<E> {
@@ -130,6 +152,41 @@ pub fn bls12381_decompress<E: EllipticCurve>(bytes_be: &[u8], sign_bit: u32) ->
     AffinePoint::new(x, y)
 }
 
+/// Compute the `y` coordinate of a point like [`bls12381_decompress`], with fixed-width arithmetic.
+/// Returns `None` where the former fails, that is if the top three bits of `x` are set, as they
+/// hold the flags of the compressed form, or if `x` isn't the coordinate of a point.
+pub fn bls12381_decompress_words(x: &[u32], sign_bit: u32) -> Option<Vec<u32>> {
+    type F = Fp<6, Bls12381BaseField>;
+
+    if x[11] >> 29 != 0 {
+        return None;
+    }
+    let x = F::from_canonical_words(x)?;
+    let mut b = [0; 12];
+    b[0] = 4;
+    let y_squared = x.mul(&x).mul(&x).add(&F::from_words(&b));
+
+    // Since p = 3 mod 4, a square root of `y^2` is `y^2^((p + 1) / 4)`.
+    let mut p = [0u64; 6];
+    for (i, byte) in Bls12381BaseField::MODULUS.iter().enumerate() {
+        p[i / 8] |= u64::from(*byte) << (8 * (i % 8));
+    }
+    p[0] += 1;
+    let exponent = std::array::from_fn(|i| (p[i] >> 2) | p.get(i + 1).map_or(0, |next| next << 62));
+    let y = y_squared.pow(&exponent);
+    if y.mul(&y) != y_squared {
+        return None;
+    }
+
+    // The sign bit selects the larger of `y` and `-y`.
+    let mut y_words = vec![0; 12];
+    let mut neg_y_words = vec![0; 12];
+    y.to_words(&mut y_words);
+    F::from_words(&[0; 12]).sub(&y).to_words(&mut neg_y_words);
+    let larger = y_words.iter().rev().cmp(neg_y_words.iter().rev()).is_gt();
+    Some(if larger == (sign_bit == 1) { y_words } else { neg_y_words })
+}
+
 pub fn bls12381_sqrt(a: &BigUint) -> BigUint {
     let a_big = Big::from_bytes(a.to_bytes_be().as_slice());
 
@@ -3,7 +3,11 @@ use num::{BigUint, Num, Zero};
 use serde::{Deserialize, Serialize};
 use typenum::{U32, U62};
 
-use super::{FieldType, FpOpField, SwCurve, WeierstrassParameters};
+use super::{
+    fixed,
+    fp::{Fp, FpModulus, Montgomery},
+    FieldType, FpOpField, SwCurve, WeierstrassParameters,
+};
 use crate::{
     params::{FieldParameters, NumLimbs},
     CurveType, EllipticCurveParameters,
@@ -41,6 +45,12 @@ impl FieldParameters for Bn254BaseField {
 
 impl FpOpField for Bn254BaseField {
     const FIELD_TYPE: FieldType = FieldType::Bn254;
+
+    type Fp = Fp<4, Self>;
+}
+
+impl FpModulus<4> for Bn254BaseField {
+    const MONTGOMERY: Montgomery<4> = Montgomery::new(Self::MODULUS);
 }
 
 impl NumLimbs for Bn254BaseField {
@@ -87,6 +97,14 @@ impl WeierstrassParameters for Bn254Parameters {
     fn b_int() -> BigUint {
         BigUint::from(3u32)
     }
+
+    fn add_words(p: &[u32], q: &[u32]) -> Option<Vec<u32>> {
+        fixed::add::<Fp<4, Bn254BaseField>>(p, q)
+    }
+
+    fn double_words(p: &[u32]) -> Option<Vec<u32>> {
+        fixed::double::<Fp<4, Bn254BaseField>>(p)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,118 @@
+//! Affine point arithmetic with fixed-width field elements on curves `y^2 = x^3 + b`.
+//!
+//! Points are read from and written to little-endian words, `x` then `y`, as the precompiles lay
+//! them out in memory. Each function returns `None` in the cases the chord-and-tangent formulas
+//! don't cover, such as a point at infinity as the result or unreduced coordinates, so that
+//! callers can fall back to the `BigUint` implementation in [`super::SwCurve`].
+
+use super::fp::FpElement;
+
+/// Add two points with distinct `x` coordinates.
+pub fn add<F: FpElement>(p: &[u32], q: &[u32]) -> Option<Vec<u32>> {
+    let (px, py) = read::<F>(p)?;
+    let (qx, qy) = read::<F>(q)?;
+
+    let slope = qy.sub(&py).mul(&qx.sub(&px).inv()?);
+    let x = slope.mul(&slope).sub(&px).sub(&qx);
+    let y = slope.mul(&px.sub(&x)).sub(&py);
+    Some(write(p.len(), &x, &y))
+}
+
+/// Double a point whose `y` coordinate isn't zero, on a curve with `a = 0`.
+pub fn double<F: FpElement>(p: &[u32]) -> Option<Vec<u32>> {
+    let (px, py) = read::<F>(p)?;
+
+    let square = px.mul(&px);
+    let slope = square.add(&square).add(&square).mul(&py.add(&py).inv()?);
+    let x = slope.mul(&slope).sub(&px).sub(&px);
+    let y = slope.mul(&px.sub(&x)).sub(&py);
+    Some(write(p.len(), &x, &y))
+}
+
+/// Read the coordinates of a point, or `None` if they aren't reduced.
+fn read<F: FpElement>(point: &[u32]) -> Option<(F, F)> {
+    let (x, y) = point.split_at(point.len() / 2);
+    Some((F::from_canonical_words(x)?, F::from_canonical_words(y)?))
+}
+
+/// Write the coordinates of a point to `len` words.
+fn write<F: FpElement>(len: usize, x: &F, y: &F) -> Vec<u32> {
+    let mut words = vec![0; len];
+    let (x_words, y_words) = words.split_at_mut(len / 2);
+    x.to_words(x_words);
+    y.to_words(y_words);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use num::BigUint;
+
+    use crate::{
+        params::FieldParameters,
+        weierstrass::{
+            bls12_381::{bls12381_decompress, Bls12381Parameters},
+            bn254::Bn254Parameters,
+            secp256k1::{secp256k1_decompress, Secp256k1Parameters},
+            SwCurve, WeierstrassParameters,
+        },
+        AffinePoint, CurveType, EllipticCurve,
+    };
+
+    /// Cross-check the fixed-width arithmetic against the `BigUint` reference on multiples of the
+    /// generator.
+    fn check<E: WeierstrassParameters>() {
+        let modulus = E::BaseField::modulus();
+        let mut p = SwCurve::<E>::generator();
+        let mut q = p.sw_double();
+        for _ in 0..20 {
+            let (p_words, q_words) = (p.to_words_le(), q.to_words_le());
+            let sum = p.sw_add(&q);
+            assert_eq!(SwCurve::<E>::ec_add_words(&p_words, &q_words), Some(sum.to_words_le()));
+            assert_eq!(SwCurve::<E>::ec_double_words(&p_words), Some(p.sw_double().to_words_le()));
+
+            // Compressed points carry the low words of `x`, in big-endian bytes for the reference.
+            let half = p_words.len() / 2;
+            let x_bytes_be = {
+                let mut bytes = p.x.to_bytes_le();
+                bytes.resize(4 * half, 0);
+                bytes.reverse();
+                bytes
+            };
+            for sign_bit in [0, 1] {
+                let expected = match E::CURVE_TYPE {
+                    CurveType::Secp256k1 => {
+                        secp256k1_decompress::<SwCurve<E>>(&x_bytes_be, sign_bit)
+                    }
+                    CurveType::Bls12381 => bls12381_decompress::<SwCurve<E>>(&x_bytes_be, sign_bit),
+                    _ => continue,
+                };
+                let words = SwCurve::<E>::ec_decompress_words(&p_words[..half], sign_bit);
+                assert_eq!(words.as_deref(), Some(&expected.to_words_le()[half..]));
+            }
+
+            // Cases left to the reference, which doesn't cover the equal and opposite points
+            // either.
+            assert_eq!(SwCurve::<E>::ec_add_words(&p_words, &p_words), None);
+            assert!(p.sw_checked_add(&p).is_none());
+            let neg_p = AffinePoint::<SwCurve<E>>::new(p.x.clone(), &modulus - &p.y);
+            assert_eq!(SwCurve::<E>::ec_add_words(&p_words, &neg_p.to_words_le()), None);
+            assert!(p.sw_checked_add(&neg_p).is_none());
+            let unreduced = AffinePoint::<SwCurve<E>>::new(modulus.clone(), p.y.clone());
+            assert_eq!(SwCurve::<E>::ec_double_words(&unreduced.to_words_le()), None);
+
+            p = sum;
+            q = q.sw_double();
+        }
+        let zero_y = AffinePoint::<SwCurve<E>>::new(p.x.clone(), BigUint::from(0u32));
+        assert_eq!(SwCurve::<E>::ec_double_words(&zero_y.to_words_le()), None);
+        assert!(zero_y.sw_checked_double().is_none());
+    }
+
+    #[test]
+    fn test_fixed_width_points() {
+        check::<Secp256k1Parameters>();
+        check::<Bn254Parameters>();
+        check::<Bls12381Parameters>();
+    }
+}
@@ -0,0 +1,403 @@
+//! Fixed-width arithmetic in the base fields of the `Fp` precompiles.
+//!
+//! Elements are stored in `N` 64-bit limbs and multiplied with Montgomery multiplication, so that
+//! no operation allocates, unlike with `BigUint`.
+
+use std::marker::PhantomData;
+
+/// The constants of Montgomery arithmetic modulo an odd `p < R`, where `R = 2^(64 N)`.
+#[derive(Debug, Clone, Copy)]
+pub struct Montgomery<const N: usize> {
+    /// The limbs of `p`, least significant first.
+    p: [u64; N],
+    /// `R^2 mod p`.
+    r2: [u64; N],
+    /// `-p^-1 mod 2^64`.
+    inv: u64,
+}
+
+impl<const N: usize> Montgomery<N> {
+    /// Compute the constants for the modulus with the given little-endian bytes.
+    pub const fn new(modulus: &[u8]) -> Self {
+        assert!(modulus.len() <= 8 * N);
+        let mut p = [0; N];
+        let mut i = 0;
+        while i < modulus.len() {
+            p[i / 8] |= (modulus[i] as u64) << (8 * (i % 8));
+            i += 1;
+        }
+        assert!(p[0] % 2 == 1);
+
+        // Each Newton iteration doubles the number of correct low bits of `p^-1`.
+        let mut inv = 1u64;
+        let mut i = 0;
+        while i < 6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(p[0].wrapping_mul(inv)));
+            i += 1;
+        }
+
+        let mut r2 = [0; N];
+        r2[0] = 1;
+        let mut i = 0;
+        while i < 128 * N {
+            let (double, carry) = add(&r2, &r2);
+            r2 = if carry || !less(&double, &p) { sub(&double, &p).0 } else { double };
+            i += 1;
+        }
+
+        Self { p, r2, inv: inv.wrapping_neg() }
+    }
+
+    /// Reduce `x` modulo `p`.
+    #[must_use]
+    pub fn reduce(&self, mut x: [u64; N]) -> [u64; N] {
+        while !less(&x, &self.p) {
+            x = sub(&x, &self.p).0;
+        }
+        x
+    }
+
+    /// Compute `a + b mod p`, where `a, b < p`.
+    #[must_use]
+    pub fn add(&self, a: &[u64; N], b: &[u64; N]) -> [u64; N] {
+        let (sum, carry) = add(a, b);
+        if carry || !less(&sum, &self.p) {
+            sub(&sum, &self.p).0
+        } else {
+            sum
+        }
+    }
+
+    /// Compute `a - b mod p`, where `a, b < p`.
+    #[must_use]
+    pub fn sub(&self, a: &[u64; N], b: &[u64; N]) -> [u64; N] {
+        let (difference, borrow) = sub(a, b);
+        if borrow {
+            add(&difference, &self.p).0
+        } else {
+            difference
+        }
+    }
+
+    /// Compute `a * b mod p`, where `a < p` and `b < R`.
+    #[must_use]
+    pub fn mul(&self, a: &[u64; N], b: &[u64; N]) -> [u64; N] {
+        // `a R mod p` times `b` in Montgomery form is `a b mod p`.
+        self.montgomery_mul(&self.montgomery_mul(a, &self.r2), b)
+    }
+
+    /// Compute `a^exponent mod p`, where `a < p`.
+    #[must_use]
+    pub fn pow(&self, a: &[u64; N], exponent: &[u64; N]) -> [u64; N] {
+        // Square and multiply in Montgomery form, starting from `R mod p`, the form of one.
+        let a = self.montgomery_mul(a, &self.r2);
+        let mut one = [0; N];
+        one[0] = 1;
+        let mut result = self.montgomery_mul(&one, &self.r2);
+        for limb in exponent.iter().rev() {
+            for bit in (0..64).rev() {
+                result = self.montgomery_mul(&result, &result);
+                if limb >> bit & 1 == 1 {
+                    result = self.montgomery_mul(&result, &a);
+                }
+            }
+        }
+        self.montgomery_mul(&result, &one)
+    }
+
+    /// Compute `a^-1 mod p`, where `a < p`, with the binary extended Euclidean algorithm, or `None`
+    /// if `a` is zero.
+    #[must_use]
+    pub fn inv(&self, a: &[u64; N]) -> Option<[u64; N]> {
+        let mut one = [0; N];
+        one[0] = 1;
+        if *a == [0; N] {
+            return None;
+        }
+
+        // Keep `u = x1 a` and `v = x2 a` modulo `p` while reducing `u` and `v` to their gcd, one.
+        let (mut u, mut v) = (*a, self.p);
+        let (mut x1, mut x2) = (one, [0; N]);
+        while u != one && v != one {
+            while u[0].is_multiple_of(2) {
+                u = shr(&u, false);
+                x1 = self.halve(&x1);
+            }
+            while v[0].is_multiple_of(2) {
+                v = shr(&v, false);
+                x2 = self.halve(&x2);
+            }
+            if less(&u, &v) {
+                v = sub(&v, &u).0;
+                x2 = self.sub(&x2, &x1);
+            } else {
+                u = sub(&u, &v).0;
+                x1 = self.sub(&x1, &x2);
+            }
+        }
+        Some(if u == one { x1 } else { x2 })
+    }
+
+    /// Compute `a / 2 mod p`, where `a < p`.
+    fn halve(&self, a: &[u64; N]) -> [u64; N] {
+        if a[0].is_multiple_of(2) {
+            shr(a, false)
+        } else {
+            let (sum, carry) = add(a, &self.p);
+            shr(&sum, carry)
+        }
+    }
+
+    /// Compute `a * b / R mod p`, where `a * b < R p`, with the CIOS method.
+    fn montgomery_mul(&self, a: &[u64; N], b: &[u64; N]) -> [u64; N] {
+        let mut t = [0; N];
+        let mut t_high = 0u64;
+        for &b in b {
+            // t += a * b
+            let mut carry = 0;
+            for j in 0..N {
+                (t[j], carry) = mul_add(a[j], b, t[j], carry);
+            }
+            let (high, overflow) = t_high.overflowing_add(carry);
+            t_high = high;
+
+            // t = (t + m * p) / 2^64, where m makes the low limb vanish.
+            let m = t[0].wrapping_mul(self.inv);
+            let (_, mut carry) = mul_add(m, self.p[0], t[0], 0);
+            for j in 1..N {
+                (t[j - 1], carry) = mul_add(m, self.p[j], t[j], carry);
+            }
+            let (low, carry) = t_high.overflowing_add(carry);
+            t[N - 1] = low;
+            t_high = u64::from(overflow) + u64::from(carry);
+        }
+
+        // t < 2p
+        if t_high != 0 || !less(&t, &self.p) {
+            sub(&t, &self.p).0
+        } else {
+            t
+        }
+    }
+}
+
+/// The modulus of a field with `N`-limb [`Fp`] elements.
+pub trait FpModulus<const N: usize> {
+    /// The constants of Montgomery arithmetic modulo the field.
+    const MONTGOMERY: Montgomery<N>;
+}
+
+/// An element of a prime field, stored in `N` 64-bit limbs.
+#[derive(Debug)]
+pub struct Fp<const N: usize, P> {
+    limbs: [u64; N],
+    _marker: PhantomData<P>,
+}
+
+impl<const N: usize, P> Clone for Fp<N, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<const N: usize, P> Copy for Fp<N, P> {}
+
+impl<const N: usize, P> PartialEq for Fp<N, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.limbs == other.limbs
+    }
+}
+
+impl<const N: usize, P> Eq for Fp<N, P> {}
+
+impl<const N: usize, P: FpModulus<N>> Fp<N, P> {
+    /// Raise the element to a power given by its little-endian limbs.
+    #[must_use]
+    pub fn pow(&self, exponent: &[u64; N]) -> Self {
+        Self { limbs: P::MONTGOMERY.pow(&self.limbs, exponent), _marker: PhantomData }
+    }
+}
+
+/// An element of a prime field with fixed-width arithmetic, read from and written to
+/// little-endian words.
+pub trait FpElement: Copy {
+    /// Read an element from words, reducing it modulo the field.
+    fn from_words(words: &[u32]) -> Self;
+
+    /// Read an element from words, or `None` if they aren't reduced modulo the field.
+    fn from_canonical_words(words: &[u32]) -> Option<Self>;
+
+    /// Write the element to words.
+    fn to_words(&self, words: &mut [u32]);
+
+    /// Add two elements.
+    #[must_use]
+    fn add(&self, other: &Self) -> Self;
+
+    /// Subtract two elements.
+    #[must_use]
+    fn sub(&self, other: &Self) -> Self;
+
+    /// Multiply two elements.
+    #[must_use]
+    fn mul(&self, other: &Self) -> Self;
+
+    /// Invert the element, or return `None` if it is zero.
+    fn inv(&self) -> Option<Self>;
+}
+
+impl<const N: usize, P: FpModulus<N>> FpElement for Fp<N, P> {
+    fn from_words(words: &[u32]) -> Self {
+        Self { limbs: P::MONTGOMERY.reduce(words_to_limbs(words)), _marker: PhantomData }
+    }
+
+    fn from_canonical_words(words: &[u32]) -> Option<Self> {
+        let limbs = words_to_limbs(words);
+        less(&limbs, &P::MONTGOMERY.p).then_some(Self { limbs, _marker: PhantomData })
+    }
+
+    fn to_words(&self, words: &mut [u32]) {
+        assert_eq!(words.len(), 2 * N);
+        for (i, limb) in self.limbs.iter().enumerate() {
+            words[2 * i] = *limb as u32;
+            words[2 * i + 1] = (*limb >> 32) as u32;
+        }
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Self { limbs: P::MONTGOMERY.add(&self.limbs, &other.limbs), _marker: PhantomData }
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        Self { limbs: P::MONTGOMERY.sub(&self.limbs, &other.limbs), _marker: PhantomData }
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        Self { limbs: P::MONTGOMERY.mul(&self.limbs, &other.limbs), _marker: PhantomData }
+    }
+
+    fn inv(&self) -> Option<Self> {
+        P::MONTGOMERY.inv(&self.limbs).map(|limbs| Self { limbs, _marker: PhantomData })
+    }
+}
+
+/// Read `N` limbs from `2 N` little-endian words.
+fn words_to_limbs<const N: usize>(words: &[u32]) -> [u64; N] {
+    assert_eq!(words.len(), 2 * N);
+    std::array::from_fn(|i| u64::from(words[2 * i]) | (u64::from(words[2 * i + 1]) << 32))
+}
+
+/// Compute `a * b + c + d` as a low and a high limb.
+#[inline]
+const fn mul_add(a: u64, b: u64, c: u64, d: u64) -> (u64, u64) {
+    let result = a as u128 * b as u128 + c as u128 + d as u128;
+    (result as u64, (result >> 64) as u64)
+}
+
+/// Compute `a + b`, along with whether it overflows.
+const fn add<const N: usize>(a: &[u64; N], b: &[u64; N]) -> ([u64; N], bool) {
+    let mut sum = [0; N];
+    let mut carry = false;
+    let mut i = 0;
+    while i < N {
+        let (limb, carry1) = a[i].overflowing_add(b[i]);
+        let (limb, carry2) = limb.overflowing_add(carry as u64);
+        sum[i] = limb;
+        carry = carry1 || carry2;
+        i += 1;
+    }
+    (sum, carry)
+}
+
+/// Compute `a - b`, along with whether it underflows.
+const fn sub<const N: usize>(a: &[u64; N], b: &[u64; N]) -> ([u64; N], bool) {
+    let mut difference = [0; N];
+    let mut borrow = false;
+    let mut i = 0;
+    while i < N {
+        let (limb, borrow1) = a[i].overflowing_sub(b[i]);
+        let (limb, borrow2) = limb.overflowing_sub(borrow as u64);
+        difference[i] = limb;
+        borrow = borrow1 || borrow2;
+        i += 1;
+    }
+    (difference, borrow)
+}
+
+/// Compute `(a + carry 2^(64 N)) / 2`, rounding down.
+fn shr<const N: usize>(a: &[u64; N], carry: bool) -> [u64; N] {
+    std::array::from_fn(|i| {
+        let high = if i + 1 < N { a[i + 1] } else { u64::from(carry) };
+        (a[i] >> 1) | (high << 63)
+    })
+}
+
+/// Whether `a < b`.
+const fn less<const N: usize>(a: &[u64; N], b: &[u64; N]) -> bool {
+    let mut i = N;
+    while i > 0 {
+        i -= 1;
+        if a[i] != b[i] {
+            return a[i] < b[i];
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use num::{bigint::RandBigInt, BigUint};
+    use rand::thread_rng;
+
+    use super::{Fp, FpElement, FpModulus};
+    use crate::{
+        params::FieldParameters,
+        weierstrass::{bls12_381::Bls12381BaseField, bn254::Bn254BaseField},
+    };
+
+    fn check<const N: usize, P: FpModulus<N> + FieldParameters>() {
+        let modulus = P::modulus();
+        let to_words = |x: &BigUint| {
+            let mut words = x.to_u32_digits();
+            words.resize(2 * N, 0);
+            words
+        };
+        let from_fp = |x: Fp<N, P>| {
+            let mut words = vec![0; 2 * N];
+            x.to_words(&mut words);
+            BigUint::from_slice(&words)
+        };
+
+        let mut rng = thread_rng();
+        let max = (BigUint::from(1u32) << (64 * N)) - 1u32;
+        let mut values = vec![BigUint::from(0u32), &modulus - 1u32, modulus.clone(), max];
+        values.extend((0..100).map(|_| rng.gen_biguint(64 * N as u64)));
+        for x in &values {
+            for y in &values {
+                let (a, b) = (Fp::<N, P>::from_words(&to_words(x)), Fp::from_words(&to_words(y)));
+                let (x, y) = (x % &modulus, y % &modulus);
+                assert_eq!(from_fp(a.add(&b)), (&x + &y) % &modulus);
+                assert_eq!(from_fp(a.sub(&b)), (&x + &modulus - &y) % &modulus);
+                assert_eq!(from_fp(a.mul(&b)), (&x * &y) % &modulus);
+            }
+            let a = Fp::<N, P>::from_words(&to_words(x));
+            let x = x % &modulus;
+            let inverse = a.inv().map(from_fp);
+            let expected =
+                (x != BigUint::from(0u32)).then(|| x.modpow(&(&modulus - 2u32), &modulus));
+            assert_eq!(inverse, expected);
+            let exponent = rng.gen_biguint(64 * N as u64);
+            let exponent_limbs =
+                std::array::from_fn(|i| exponent.iter_u64_digits().nth(i).unwrap_or(0));
+            assert_eq!(from_fp(a.pow(&exponent_limbs)), x.modpow(&exponent, &modulus));
+        }
+        assert!(Fp::<N, P>::from_canonical_words(&to_words(&modulus)).is_none());
+        assert!(Fp::<N, P>::from_canonical_words(&to_words(&(&modulus - 1u32))).is_some());
+    }
+
+    #[test]
+    fn test_fp_arithmetic() {
+        check::<4, Bn254BaseField>();
+        check::<6, Bls12381BaseField>();
+    }
+}
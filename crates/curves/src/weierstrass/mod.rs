@@ -9,12 +9,15 @@ use crate::{
     AffinePoint, EllipticCurve, EllipticCurveParameters,
 };
 use alloy_primitives::{Uint, U256, U512};
+use fp::FpElement;
 
 #[cfg(feature = "bigint-rug")]
 use crate::utils::{biguint_to_rug, rug_to_biguint};
 
 pub mod bls12_381;
 pub mod bn254;
+pub mod fixed;
+pub mod fp;
 pub mod secp256k1;
 
 /// Parameters that specify a short Weierstrass curve : y^2 = x^3 + ax + b.
@@ -45,6 +48,24 @@ pub trait WeierstrassParameters: EllipticCurveParameters {
     fn nb_scalar_bits() -> usize {
         Self::BaseField::NB_LIMBS * 16
     }
+
+    /// Add two points given as words with fixed-width arithmetic, as in
+    /// [`EllipticCurve::ec_add_words`].
+    fn add_words(_p: &[u32], _q: &[u32]) -> Option<Vec<u32>> {
+        None
+    }
+
+    /// Double a point given as words with fixed-width arithmetic, as in
+    /// [`EllipticCurve::ec_double_words`].
+    fn double_words(_p: &[u32]) -> Option<Vec<u32>> {
+        None
+    }
+
+    /// Decompress a point given as words with fixed-width arithmetic, as in
+    /// [`EllipticCurve::ec_decompress_words`].
+    fn decompress_words(_x: &[u32], _sign_bit: u32) -> Option<Vec<u32>> {
+        None
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -73,6 +94,18 @@ impl<E: WeierstrassParameters> WeierstrassParameters for SwCurve<E> {
     fn prime_group_order() -> BigUint {
         E::prime_group_order()
     }
+
+    fn add_words(p: &[u32], q: &[u32]) -> Option<Vec<u32>> {
+        E::add_words(p, q)
+    }
+
+    fn double_words(p: &[u32]) -> Option<Vec<u32>> {
+        E::double_words(p)
+    }
+
+    fn decompress_words(x: &[u32], sign_bit: u32) -> Option<Vec<u32>> {
+        E::decompress_words(x, sign_bit)
+    }
 }
 
 impl<E: WeierstrassParameters> EllipticCurveParameters for SwCurve<E> {
@@ -93,6 +126,14 @@ impl<E: WeierstrassParameters> EllipticCurve for SwCurve<E> {
         p.sw_double()
     }
 
+    fn ec_checked_add(p: &AffinePoint<Self>, q: &AffinePoint<Self>) -> Option<AffinePoint<Self>> {
+        p.sw_checked_add(q)
+    }
+
+    fn ec_checked_double(p: &AffinePoint<Self>) -> Option<AffinePoint<Self>> {
+        p.sw_checked_double()
+    }
+
     fn ec_generator() -> AffinePoint<Self> {
         let (x, y) = E::generator();
         AffinePoint::new(x, y)
@@ -106,6 +147,18 @@ impl<E: WeierstrassParameters> EllipticCurve for SwCurve<E> {
         let modulus = E::BaseField::modulus();
         AffinePoint::new(p.x.clone(), modulus - &p.y)
     }
+
+    fn ec_add_words(p: &[u32], q: &[u32]) -> Option<Vec<u32>> {
+        E::add_words(p, q)
+    }
+
+    fn ec_double_words(p: &[u32]) -> Option<Vec<u32>> {
+        E::double_words(p)
+    }
+
+    fn ec_decompress_words(x: &[u32], sign_bit: u32) -> Option<Vec<u32>> {
+        E::decompress_words(x, sign_bit)
+    }
 }
 
 impl<E: WeierstrassParameters> SwCurve<E> {
@@ -215,82 +268,81 @@ pub fn dashu_modpow(
 
 impl<E: WeierstrassParameters> AffinePoint<SwCurve<E>> {
     pub fn sw_add(&self, other: &AffinePoint<SwCurve<E>>) -> AffinePoint<SwCurve<E>> {
-        if self.x == other.x && self.y == other.y {
-            panic!("Error: Points are the same. Use sw_double instead.");
-        }
+        self.sw_checked_add(other)
+            .expect("Error: Points have the same x coordinate. Use sw_double instead.")
+    }
 
+    /// Adds two points, or returns `None` if they have the same `x` coordinate, in which case they
+    /// are equal or opposite.
+    pub fn sw_checked_add(
+        &self,
+        other: &AffinePoint<SwCurve<E>>,
+    ) -> Option<AffinePoint<SwCurve<E>>> {
         cfg_if::cfg_if! {
             if #[cfg(feature = "bigint-rug")] {
                 self.sw_add_rug(other)
             } else {
+                if E::BaseField::MODULUS.len() == 32 {
+                    self.sw_add_generic::<256, 4>(other)
+                } else {
+                    assert!(E::BaseField::MODULUS.len() <= 64);
+                    self.sw_add_generic::<512, 8>(other)
+                }
+            }
+        }
+    }
 
-                let p = U256::from_le_slice(E::BaseField::MODULUS);
-                let self_x = U256::from_le_slice(self.x.to_bytes_le().as_slice());
-                let self_y = U256::from_le_slice(self.y.to_bytes_le().as_slice());
-                let other_x = U256::from_le_slice(other.x.to_bytes_le().as_slice());
-                let other_y = U256::from_le_slice(other.y.to_bytes_le().as_slice());
-
-
-                let slope_num = other_y.add_mod(p - self_y, p);
-                let slope_denom = other_x.add_mod(p - self_x, p);
-                let slope = slope_num.mul_mod(slope_denom.inv_mod(p).unwrap(), p);
-
-                let x_3n = slope.mul_mod(slope, p).add_mod(p - self_x, p).add_mod(p - other_x, p);
-                let y_3n = self_x.add_mod(p - x_3n, p).mul_mod(slope, p).add_mod(p - self_y, p);
-
-                return AffinePoint::new(
-                    BigUint::from_bytes_le(x_3n.as_le_slice()),
-                    BigUint::from_bytes_le(y_3n.as_le_slice())
-                );
-
-
-
-
-                let p = biguint_to_dashu(&E::BaseField::modulus());
-                let self_x = biguint_to_dashu(&self.x);
-                let self_y = biguint_to_dashu(&self.y);
-
-                let other_x = biguint_to_dashu(&other.x);
-                let other_y = biguint_to_dashu(&other.y);
+    fn sw_add_generic<const BITS: usize, const LIMBS: usize>(
+        &self,
+        other: &AffinePoint<SwCurve<E>>,
+    ) -> Option<AffinePoint<SwCurve<E>>> {
+        let p = Uint::<BITS, LIMBS>::from_le_slice(E::BaseField::MODULUS);
+        let self_x = Uint::<BITS, LIMBS>::from_le_slice(self.x.to_bytes_le().as_slice());
+        let self_y = Uint::<BITS, LIMBS>::from_le_slice(self.y.to_bytes_le().as_slice());
+        let other_x = Uint::<BITS, LIMBS>::from_le_slice(other.x.to_bytes_le().as_slice());
+        let other_y = Uint::<BITS, LIMBS>::from_le_slice(other.y.to_bytes_le().as_slice());
 
-                let slope_numerator = (&p + &other_y - &self_y) % &p;
-                let slope_denominator = (&p + &other_x - &self_x) % &p;
-                let slope_denom_inverse =
-                    dashu_modpow(&slope_denominator, &(&p - &dashu::integer::UBig::from(2u32)), &p);
-                let slope = (slope_numerator * &slope_denom_inverse) % &p;
+        let slope_num = other_y.add_mod(p - self_y, p);
+        let slope_denom = other_x.add_mod(p - self_x, p);
+        let slope = slope_num.mul_mod(slope_denom.inv_mod(p)?, p);
 
-                let x_3n = (&slope * &slope + &p + &p - &self_x - &other_x) % &p;
-                let y_3n = (&slope * &(&p + &self_x - &x_3n) + &p - &self_y) % &p;
+        let x_3n = slope.mul_mod(slope, p).add_mod(p - self_x, p).add_mod(p - other_x, p);
+        let y_3n = self_x.add_mod(p - x_3n, p).mul_mod(slope, p).add_mod(p - self_y, p);
 
-                AffinePoint::new(dashu_to_biguint(&x_3n), dashu_to_biguint(&y_3n))
-            }
-        }
+        Some(AffinePoint::new(
+            BigUint::from_bytes_le(x_3n.as_le_slice()),
+            BigUint::from_bytes_le(y_3n.as_le_slice()),
+        ))
     }
 
-    fn sw_double_generic<const BITS: usize, const LIMBS: usize>(&self) -> AffinePoint<SwCurve<E>> {
+    fn sw_double_generic<const BITS: usize, const LIMBS: usize>(
+        &self,
+    ) -> Option<AffinePoint<SwCurve<E>>> {
         let p = Uint::<BITS, LIMBS>::from_le_slice(E::BaseField::MODULUS);
         let a = Uint::<BITS, LIMBS>::from_le_slice(E::a_int().to_bytes_le().as_slice());
         let x = Uint::<BITS, LIMBS>::from_le_slice(self.x.to_bytes_le().as_slice());
         let y = Uint::<BITS, LIMBS>::from_le_slice(self.y.to_bytes_le().as_slice());
 
-        let slope_num = x
-            .mul_mod(x, p)
-            .mul_mod(Uint::<BITS, LIMBS>::from(3), p)
-            .add_mod(a, p);
+        let slope_num = x.mul_mod(x, p).mul_mod(Uint::<BITS, LIMBS>::from(3), p).add_mod(a, p);
         let slope_denom = y.mul_mod(Uint::<BITS, LIMBS>::from(2), p);
-        let slop_denom_inv = slope_denom.inv_mod(p).unwrap();
+        let slop_denom_inv = slope_denom.inv_mod(p)?;
         let slope = slope_num.mul_mod(slop_denom_inv, p);
 
         let x_3n = slope.mul_mod(slope, p).add_mod(p - x, p).add_mod(p - x, p);
         let y_3n = x.add_mod(p - x_3n, p).mul_mod(slope, p).add_mod(p - y, p);
 
-        return AffinePoint::new(
+        return Some(AffinePoint::new(
             BigUint::from_bytes_le(x_3n.as_le_slice()),
             BigUint::from_bytes_le(y_3n.as_le_slice()),
-        );
+        ));
     }
 
     pub fn sw_double(&self) -> AffinePoint<SwCurve<E>> {
+        self.sw_checked_double().expect("Error: Point has a zero y coordinate.")
+    }
+
+    /// Doubles a point, or returns `None` if its `y` coordinate is zero.
+    pub fn sw_checked_double(&self) -> Option<AffinePoint<SwCurve<E>>> {
         cfg_if::cfg_if! {
             if #[cfg(feature = "bigint-rug")] {
                 self.sw_double_rug()
@@ -306,7 +358,7 @@ impl<E: WeierstrassParameters> AffinePoint<SwCurve<E>> {
     }
 
     #[cfg(feature = "bigint-rug")]
-    pub fn sw_add_rug(&self, other: &AffinePoint<SwCurve<E>>) -> AffinePoint<SwCurve<E>> {
+    pub fn sw_add_rug(&self, other: &AffinePoint<SwCurve<E>>) -> Option<AffinePoint<SwCurve<E>>> {
         use rug::Complete;
         let p = biguint_to_rug(&E::BaseField::modulus());
         let self_x = biguint_to_rug(&self.x);
@@ -316,6 +368,9 @@ impl<E: WeierstrassParameters> AffinePoint<SwCurve<E>> {
 
         let slope_numerator = ((&p + &other_y).complete() - &self_y) % &p;
         let slope_denominator = ((&p + &other_x).complete() - &self_x) % &p;
+        if slope_denominator == 0 {
+            return None;
+        }
         let slope_denom_inverse = slope_denominator
             .pow_mod_ref(&(&p - &rug::Integer::from(2u32)).complete(), &p)
             .unwrap()
@@ -325,11 +380,11 @@ impl<E: WeierstrassParameters> AffinePoint<SwCurve<E>> {
         let x_3n = ((&slope * &slope + &p).complete() + &p - &self_x - &other_x) % &p;
         let y_3n = ((&slope * &((&p + &self_x).complete() - &x_3n) + &p).complete() - &self_y) % &p;
 
-        AffinePoint::new(rug_to_biguint(&x_3n), rug_to_biguint(&y_3n))
+        Some(AffinePoint::new(rug_to_biguint(&x_3n), rug_to_biguint(&y_3n)))
     }
 
     #[cfg(feature = "bigint-rug")]
-    pub fn sw_double_rug(&self) -> AffinePoint<SwCurve<E>> {
+    pub fn sw_double_rug(&self) -> Option<AffinePoint<SwCurve<E>>> {
         use rug::Complete;
         let p = biguint_to_rug(&E::BaseField::modulus());
         let a = biguint_to_rug(&E::a_int());
@@ -340,6 +395,9 @@ impl<E: WeierstrassParameters> AffinePoint<SwCurve<E>> {
         let slope_numerator = (&a + &(&self_x * &self_x).complete() * 3u32).complete() % &p;
 
         let slope_denominator = (&self_y * 2u32).complete() % &p;
+        if slope_denominator == 0 {
+            return None;
+        }
         let slope_denom_inverse = slope_denominator
             .pow_mod_ref(&(&p - &rug::Integer::from(2u32)).complete(), &p)
             .unwrap()
@@ -351,7 +409,7 @@ impl<E: WeierstrassParameters> AffinePoint<SwCurve<E>> {
 
         let y_3n = ((&slope * &((&p + &self_x).complete() - &x_3n) + &p).complete() - &self_y) % &p;
 
-        AffinePoint::new(rug_to_biguint(&x_3n), rug_to_biguint(&y_3n))
+        Some(AffinePoint::new(rug_to_biguint(&x_3n), rug_to_biguint(&y_3n)))
     }
 }
 
@@ -363,6 +421,9 @@ pub enum FieldType {
 
 pub trait FpOpField: FieldParameters + NumWords {
     const FIELD_TYPE: FieldType;
+
+    /// The elements of the field, with fixed-width arithmetic.
+    type Fp: FpElement;
 }
 
 #[cfg(test)]
@@ -13,7 +13,7 @@ use num::{
 use serde::{Deserialize, Serialize};
 use typenum::{U32, U62};
 
-use super::{SwCurve, WeierstrassParameters};
+use super::{fixed, fp::FpElement, SwCurve, WeierstrassParameters};
 use crate::{
     params::{FieldParameters, NumLimbs},
     AffinePoint, CurveType, EllipticCurve, EllipticCurveParameters,
@@ -90,6 +90,81 @@ impl WeierstrassParameters for Secp256k1Parameters {
     fn b_int() -> BigUint {
         BigUint::from(7u32)
     }
+
+    fn add_words(p: &[u32], q: &[u32]) -> Option<Vec<u32>> {
+        fixed::add::<Secp256k1Fp>(p, q)
+    }
+
+    fn double_words(p: &[u32]) -> Option<Vec<u32>> {
+        fixed::double::<Secp256k1Fp>(p)
+    }
+
+    fn decompress_words(x: &[u32], sign_bit: u32) -> Option<Vec<u32>> {
+        let x = Secp256k1Fp::from_canonical_words(x)?;
+        let point = k256::AffinePoint::decompress(&x.0.to_bytes(), Choice::from(sign_bit as u8));
+        let point = Option::<k256::AffinePoint>::from(point)?.to_encoded_point(false);
+        let y = FieldElement::from_bytes(point.y().unwrap()).unwrap();
+        let mut words = vec![0; 8];
+        Secp256k1Fp(y).to_words(&mut words);
+        Some(words)
+    }
+}
+
+/// An element of the base field of secp256k1, with the fixed-width arithmetic of `k256`.
+///
+/// Every operation leaves the element with magnitude one, as `k256` expects of the operands of
+/// negation and multiplication.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Secp256k1Fp(FieldElement);
+
+impl FpElement for Secp256k1Fp {
+    fn from_words(words: &[u32]) -> Self {
+        Self::from_canonical_words(words).unwrap_or_else(|| {
+            // The words are at least p and less than 2^256, so subtracting p amounts to adding
+            // 2^256 - p and dropping the carry.
+            let mut reduced = [0; 8];
+            let mut carry = 0x1_0000_03d1;
+            for (word, reduced) in words.iter().zip(&mut reduced) {
+                let sum = u64::from(*word) + carry;
+                *reduced = sum as u32;
+                carry = sum >> 32;
+            }
+            Self::from_canonical_words(&reduced).unwrap()
+        })
+    }
+
+    fn from_canonical_words(words: &[u32]) -> Option<Self> {
+        assert_eq!(words.len(), 8);
+        let mut bytes = [0u8; 32];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(words.iter().rev()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        Option::from(FieldElement::from_bytes(&bytes.into())).map(Self)
+    }
+
+    fn to_words(&self, words: &mut [u32]) {
+        assert_eq!(words.len(), 8);
+        let bytes = self.0.to_bytes();
+        for (word, chunk) in words.iter_mut().rev().zip(bytes.chunks_exact(4)) {
+            *word = u32::from_be_bytes(chunk.try_into().unwrap());
+        }
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Self((self.0 + other.0).normalize_weak())
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        Self((self.0 - other.0).normalize_weak())
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        Self(self.0 * other.0)
+    }
+
+    fn inv(&self) -> Option<Self> {
+        Option::from(self.0.invert()).map(Self)
+    }
 }
 
 pub fn secp256k1_decompress<E: EllipticCurve>(bytes_be: &[u8], sign: u32) -> AffinePoint<E> {
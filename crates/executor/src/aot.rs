@@ -0,0 +1,310 @@
+//! An ahead-of-time compiler of programs to Rust, used in [`ExecutorMode::Simple`].
+//!
+//! [`compile_to_rust`] emits a Rust module with a function for each basic block of a program and
+//! a dispatcher on the program counter. Once compiled along with this crate and registered with
+//! [`Executor::set_aot_blocks`], the blocks run on the register file of the executor without
+//! decoding their instructions. Memory accesses go through the executor and `ECALL` is left to the
+//! interpreter, so that syscalls still go through the [`SyscallContext`] and the blocks behave
+//! exactly like the interpreter.
+//!
+//! [`ExecutorMode::Simple`]: crate::ExecutorMode::Simple
+//! [`SyscallContext`]: crate::syscalls::SyscallContext
+
+use std::{collections::BTreeSet, fmt::Write};
+
+use crate::{
+    executor::SIMPLE,
+    ops::{alu, Op},
+    ExecutionError, Executor, Opcode, Program, Register,
+};
+
+/// A block compiled ahead of time by [`compile_to_rust`].
+#[derive(Clone, Copy)]
+pub struct AotBlock {
+    /// The number of instructions in the block.
+    pub len: u32,
+    /// Execute the block, returning the program counter after it, or the program counter of the
+    /// instruction that didn't complete along with the reason.
+    pub execute: fn(&mut Executor<'_>) -> Result<u32, (u32, ExecutionError)>,
+}
+
+/// The dispatcher of a module generated by [`compile_to_rust`], which gets the compiled block
+/// starting at a program counter.
+pub type AotBlocks = fn(u32) -> Option<AotBlock>;
+
+/// Compile a program to the source of a Rust module.
+///
+/// The module exports the digest of the program as `DIGEST` and its dispatcher as `block`, to be
+/// registered with `executor.set_aot_blocks(DIGEST, block)`. It has no inner attributes, so it can
+/// be a module file of its own or be `include!`d in a module. Blocks start at the entry point,
+/// after each branch, jump and `ECALL`, and at the targets of branches and direct jumps. They end
+/// with their basic block, or before its first `ECALL`, `EBREAK` or unimplemented instruction.
+#[must_use]
+pub fn compile_to_rust(program: &Program) -> String {
+    let ops = program
+        .instructions
+        .iter()
+        .map(Op::lower)
+        .collect::<Vec<_>>();
+    let ends = program.basic_block_ends();
+    let pc = |index: usize| program.pc_base + 4 * index as u32;
+    let index = |pc: u32| {
+        let offset = pc.wrapping_sub(program.pc_base);
+        (offset.is_multiple_of(4) && offset / 4 < ops.len() as u32).then_some((offset / 4) as usize)
+    };
+
+    let mut starts = BTreeSet::new();
+    starts.extend(index(program.pc_start));
+    for (i, op) in ops.iter().enumerate() {
+        if i == 0 || ends[i - 1] as usize == i {
+            starts.insert(i);
+        }
+        if let Op::Branch { imm, .. } | Op::Jal { imm, .. } | Op::JalX0 { imm } = *op {
+            starts.extend(index(pc(i).wrapping_add(imm)));
+        }
+    }
+    let blocks = starts
+        .into_iter()
+        .filter_map(|start| {
+            let len = ops[start..ends[start] as usize]
+                .iter()
+                .take_while(|op| !matches!(op, Op::Ecall | Op::Ebreak | Op::Unimp))
+                .count();
+            (len > 0).then_some((start, len))
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// The program with digest `{}`, compiled ahead of time by `sp1-aot`.",
+        hex::encode(program.digest())
+    )
+    .unwrap();
+    out.push_str(concat!(
+        "//\n",
+        "// Generated by `sp1_core_executor::compile_to_rust`. Do not edit.\n",
+        "\n",
+        "use sp1_core_executor::{alu, AotBlock, ExecutionError, Executor, Opcode};\n",
+        "\n",
+        "/// The digest of the program.\n",
+    ));
+    writeln!(out, "pub const DIGEST: [u8; 32] = {:?};", program.digest()).unwrap();
+    out.push_str(concat!(
+        "\n",
+        "/// Get the compiled block starting at `pc`.\n",
+        "#[must_use]\n",
+        "#[allow(clippy::all, clippy::pedantic)]\n",
+        "pub fn block(pc: u32) -> Option<AotBlock> {\n",
+        "    Some(match pc {\n",
+    ));
+    for &(start, len) in &blocks {
+        writeln!(
+            out,
+            "        {:#010x} => AotBlock {{ len: {len}, execute: block_{:08x} }},",
+            pc(start),
+            pc(start)
+        )
+        .unwrap();
+    }
+    out.push_str("        _ => return None,\n    })\n}\n");
+
+    for &(start, len) in &blocks {
+        compile_block(&mut out, program, &ops[start..start + len], pc(start));
+    }
+    out
+}
+
+/// Compile the block of `ops` starting at `pc` to a function of the module.
+fn compile_block(out: &mut String, program: &Program, ops: &[Op], pc: u32) {
+    let first = ((pc - program.pc_base) / 4) as usize;
+    let mut body = String::new();
+    for (i, &op) in ops.iter().enumerate() {
+        let op_pc = pc + 4 * i as u32;
+        let instruction = format!("{:?}", program.instructions[first + i]);
+        writeln!(body, "    // {op_pc:#010x}: {}", instruction.trim_end()).unwrap();
+        compile_op(&mut body, op, op_pc);
+    }
+    // Blocks of jumps and writes to `x0` only never touch the executor.
+    let uses_rt = body
+        .lines()
+        .any(|line| !line.trim_start().starts_with("//") && line.contains("rt."));
+    let rt = if uses_rt { "rt" } else { "_rt" };
+    out.push_str("\n#[allow(clippy::all, clippy::pedantic)]\n");
+    writeln!(
+        out,
+        "fn block_{pc:08x}({rt}: &mut Executor<'_>) -> Result<u32, (u32, ExecutionError)> {{"
+    )
+    .unwrap();
+    out.push_str(&body);
+    if !matches!(
+        ops[ops.len() - 1],
+        Op::Branch { .. } | Op::Jal { .. } | Op::JalX0 { .. } | Op::Jalr { .. } | Op::JalrX0 { .. }
+    ) {
+        writeln!(out, "    Ok({:#010x})", pc + 4 * ops.len() as u32).unwrap();
+    }
+    out.push_str("}\n");
+}
+
+/// Compile the instruction at `pc` to statements of a block. Branches and jumps end with the
+/// program counter after the block.
+fn compile_op(out: &mut String, op: Op, pc: u32) {
+    let fault = format!(".map_err(|error| ({pc:#010x}, error))?");
+    let line = match op {
+        Op::AluRegisters {
+            opcode,
+            rd,
+            rs1,
+            rs2,
+        } => format!(
+            "{} = alu(Opcode::{opcode:?}, {}, {});",
+            register(rd),
+            register(rs1),
+            register(rs2)
+        ),
+        Op::AluImmediate {
+            opcode,
+            rd,
+            rs1,
+            imm,
+        } => format!(
+            "{} = alu(Opcode::{opcode:?}, {}, {imm:#x});",
+            register(rd),
+            register(rs1)
+        ),
+        Op::AluImmediates { opcode, rd, b, c } if rd != Register::X0 => {
+            format!("{} = {:#x};", register(rd), alu(opcode, b, c))
+        }
+        Op::AluRegistersX0 { .. } | Op::AluImmediateX0 { .. } | Op::AluImmediates { .. } => {
+            return;
+        }
+
+        Op::Load {
+            opcode,
+            rd,
+            rs1,
+            imm,
+        } => {
+            let load = format!(
+                "rt.aot_load(Opcode::{opcode:?}, {}.wrapping_add({imm:#x})){fault}",
+                register(rs1)
+            );
+            if rd == Register::X0 {
+                format!("{load};")
+            } else {
+                format!("{} = {load};", register(rd))
+            }
+        }
+        Op::Store {
+            opcode,
+            rs1,
+            rs2,
+            imm,
+        } => format!(
+            "rt.aot_store(Opcode::{opcode:?}, {}.wrapping_add({imm:#x}), {}){fault};",
+            register(rs2),
+            register(rs1)
+        ),
+
+        Op::Branch {
+            opcode,
+            rs1,
+            rs2,
+            imm,
+        } => {
+            let (a, b) = (register(rs1), register(rs2));
+            let condition = match opcode {
+                Opcode::BEQ => format!("{a} == {b}"),
+                Opcode::BNE => format!("{a} != {b}"),
+                Opcode::BLT => format!("({a} as i32) < ({b} as i32)"),
+                Opcode::BGE => format!("({a} as i32) >= ({b} as i32)"),
+                Opcode::BLTU => format!("{a} < {b}"),
+                Opcode::BGEU => format!("{a} >= {b}"),
+                _ => unreachable!(),
+            };
+            format!(
+                "Ok(if {condition} {{ {:#010x} }} else {{ {:#010x} }})",
+                pc.wrapping_add(imm),
+                pc.wrapping_add(4)
+            )
+        }
+        Op::Jal { rd, imm } => format!(
+            "{} = {:#010x};\n    Ok({:#010x})",
+            register(rd),
+            pc.wrapping_add(4),
+            pc.wrapping_add(imm)
+        ),
+        Op::JalX0 { imm } => format!("Ok({:#010x})", pc.wrapping_add(imm)),
+        // The target is computed before `rd` is written, since it may be `rs1`.
+        Op::Jalr { rd, rs1, imm } => format!(
+            "let target = {}.wrapping_add({imm:#x});\n    {} = {:#010x};\n    Ok(target)",
+            register(rs1),
+            register(rd),
+            pc.wrapping_add(4)
+        ),
+        Op::JalrX0 { rs1, imm } => format!("Ok({}.wrapping_add({imm:#x}))", register(rs1)),
+
+        Op::Auipc { rd, imm } if rd != Register::X0 => {
+            format!("{} = {:#010x};", register(rd), pc.wrapping_add(imm))
+        }
+        Op::Auipc { .. } => return,
+
+        Op::Ecall | Op::Ebreak | Op::Unimp | Op::Fused(_) => {
+            unreachable!("{op:?} is not compiled")
+        }
+    };
+    writeln!(out, "    {line}").unwrap();
+}
+
+/// The expression of a register of the executor in a block.
+fn register(register: Register) -> String {
+    format!("rt.state.registers[{}]", register as u8)
+}
+
+impl Executor<'_> {
+    /// Execute the program with the blocks of the module generated for it by [`compile_to_rust`],
+    /// given its `DIGEST` and its `block` dispatcher, whenever it runs in simple mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the blocks were compiled from another program.
+    pub fn set_aot_blocks(&mut self, digest: [u8; 32], blocks: AotBlocks) {
+        assert_eq!(
+            digest,
+            self.program.digest(),
+            "the blocks were compiled from another program"
+        );
+        self.aot_blocks = Some(blocks);
+    }
+
+    /// Execute a load from `addr` for a block compiled ahead of time, returning the loaded value.
+    pub fn aot_load(&mut self, opcode: Opcode, addr: u32) -> Result<u32, ExecutionError> {
+        self.execute_load::<SIMPLE, false>(opcode, addr)
+            .map(|(value, _)| value)
+    }
+
+    /// Execute a store of `a` to `addr` for a block compiled ahead of time.
+    pub fn aot_store(&mut self, opcode: Opcode, addr: u32, a: u32) -> Result<(), ExecutionError> {
+        self.execute_store::<SIMPLE, false>(opcode, addr, a)
+            .map(|_| ())
+    }
+
+    /// Execute a block compiled ahead of time starting at the instruction `start`.
+    pub(crate) fn execute_aot_block(
+        &mut self,
+        start: usize,
+        block: AotBlock,
+    ) -> Result<(), ExecutionError> {
+        match (block.execute)(self) {
+            Ok(next_pc) => {
+                self.finish_compiled_block(start, block.len as usize, false, next_pc);
+                Ok(())
+            }
+            Err((pc, error)) => {
+                let executed = ((pc - self.state.pc) / 4) as usize;
+                self.finish_compiled_block(start, executed, true, pc);
+                Err(error)
+            }
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Compile a RISC-V ELF ahead of time to a Rust module of its blocks, to be executed with
+//! [`Executor::set_aot_blocks`](sp1_core_executor::Executor::set_aot_blocks).
+//!
+//! Usage: `sp1-aot <elf> <output.rs>`
+
+use std::fs;
+
+use sp1_core_executor::{compile_to_rust, Program};
+
+fn main() -> eyre::Result<()> {
+    let args = std::env::args().collect::<Vec<_>>();
+    let [_, elf, output] = args.as_slice() else {
+        eyre::bail!("usage: sp1-aot <elf> <output.rs>");
+    };
+    let program = Program::from(&fs::read(elf)?)?;
+    fs::write(output, compile_to_rust(&program))?;
+    Ok(())
+}
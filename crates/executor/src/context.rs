@@ -1,8 +1,12 @@
 use core::mem::take;
+use std::sync::Arc;
 
 use hashbrown::HashMap;
 
-use crate::hook::{hookify, BoxedHook, HookEnv, HookRegistry};
+use crate::{
+    hook::{hookify, BoxedHook, HookEnv, HookRegistry},
+    syscalls::{default_syscall_map, Syscall, SyscallCode},
+};
 
 /// Context to run a program inside SP1.
 #[derive(Clone, Default)]
@@ -12,8 +16,30 @@ pub struct SP1Context<'a> {
     /// Note: `None` denotes the default list of hooks.
     pub hook_registry: Option<HookRegistry<'a>>,
 
+    /// The implementations of the syscalls that [`SyscallCode`] lists.
+    ///
+    /// Note: `None` denotes the default syscalls.
+    pub syscall_map: Option<HashMap<SyscallCode, Arc<dyn Syscall>>>,
+
+    /// The implementations of syscalls that [`SyscallCode`] doesn't list, by number.
+    pub custom_syscall_map: HashMap<u32, Arc<dyn Syscall<u32>>>,
+
     /// The maximum number of cpu cycles to use for execution.
     pub max_cycles: Option<u64>,
+
+    /// The number of cpu cycles in a shard.
+    ///
+    /// Note: `None` denotes the default shard size.
+    pub shard_size: Option<u64>,
+
+    /// The number of shards to execute before returning from
+    /// [`Executor::execute`](crate::Executor::execute).
+    ///
+    /// Note: `None` denotes the default shard batch size.
+    pub shard_batch_size: Option<u32>,
+
+    /// Whether to execute common pairs of instructions as one in simple mode.
+    pub fuse_instructions: bool,
 }
 
 /// A builder for [`SP1Context`].
@@ -21,7 +47,13 @@ pub struct SP1Context<'a> {
 pub struct SP1ContextBuilder<'a> {
     no_default_hooks: bool,
     hook_registry_entries: Vec<(u32, BoxedHook<'a>)>,
+    no_default_syscalls: bool,
+    syscall_entries: Vec<(SyscallCode, Arc<dyn Syscall>)>,
+    custom_syscall_entries: Vec<(u32, Arc<dyn Syscall<u32>>)>,
     max_cycles: Option<u64>,
+    shard_size: Option<u64>,
+    shard_batch_size: Option<u32>,
+    fuse_instructions: bool,
 }
 
 impl<'a> SP1Context<'a> {
@@ -58,10 +90,30 @@ impl<'a> SP1ContextBuilder<'a> {
                 table.extend(take(&mut self.hook_registry_entries));
                 HookRegistry { table }
             });
+        let syscall_map =
+            (!self.syscall_entries.is_empty() || self.no_default_syscalls).then(|| {
+                let mut syscall_map = if take(&mut self.no_default_syscalls) {
+                    HashMap::default()
+                } else {
+                    default_syscall_map()
+                };
+                // Allows overwriting default syscalls.
+                syscall_map.extend(take(&mut self.syscall_entries));
+                syscall_map
+            });
+        let custom_syscall_map = take(&mut self.custom_syscall_entries).into_iter().collect();
         let cycle_limit = take(&mut self.max_cycles);
+        let shard_size = take(&mut self.shard_size);
+        let shard_batch_size = take(&mut self.shard_batch_size);
+        let fuse_instructions = take(&mut self.fuse_instructions);
         SP1Context {
             hook_registry,
+            syscall_map,
+            custom_syscall_map,
             max_cycles: cycle_limit,
+            shard_size,
+            shard_batch_size,
+            fuse_instructions,
         }
     }
 
@@ -88,9 +140,79 @@ impl<'a> SP1ContextBuilder<'a> {
         self
     }
 
+    /// Register a [`Syscall`] for `code` in the runtime.
+    ///
+    /// This replaces the default implementation of the syscall, if there is one, which can be
+    /// used to stub out an expensive precompile for example.
+    pub fn syscall(&mut self, code: SyscallCode, syscall: Arc<dyn Syscall>) -> &mut Self {
+        self.syscall_entries.push((code, syscall));
+        self
+    }
+
+    /// Register a [`Syscall`] for a `number` that [`SyscallCode`] doesn't list yet, such as the
+    /// number of a new precompile being prototyped.
+    ///
+    /// The number has the layout described in [`SyscallCode`]: a syscall event is emitted if its
+    /// byte 1 is set. The syscall isn't counted in the
+    /// [`ExecutionReport`](crate::ExecutionReport), and it may not be called in unconstrained
+    /// mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`SyscallCode`] lists `number`, in which case [`Self::syscall`] should be used.
+    pub fn custom_syscall(&mut self, number: u32, syscall: Arc<dyn Syscall<u32>>) -> &mut Self {
+        assert!(
+            SyscallCode::from_u32(number).is_none(),
+            "syscall {number:#x} is listed in `SyscallCode`"
+        );
+        self.custom_syscall_entries.push((number, syscall));
+        self
+    }
+
+    /// Avoid registering the default syscalls in the runtime.
+    ///
+    /// Only the syscalls registered with [`Self::syscall`] are available then. It is not necessary
+    /// to call this to override syscalls --- instead, simply register a syscall with the same
+    /// code.
+    pub fn without_default_syscalls(&mut self) -> &mut Self {
+        self.no_default_syscalls = true;
+        self
+    }
+
     /// Set the maximum number of cpu cycles to use for execution.
     pub fn max_cycles(&mut self, max_cycles: u64) -> &mut Self {
         self.max_cycles = Some(max_cycles);
         self
     }
+
+    /// Set the number of cpu cycles in a shard.
+    ///
+    /// Every time a batch of shards fills up, [`Executor::execute`](crate::Executor::execute)
+    /// returns so the caller can process it before continuing.
+    ///
+    /// The size must be less than `2^30` cycles, and more than a quarter of the extra cycles of
+    /// any syscall, or [`Executor::with_context`](crate::Executor::with_context) returns an error.
+    pub fn shard_size(&mut self, shard_size: u64) -> &mut Self {
+        self.shard_size = Some(shard_size);
+        self
+    }
+
+    /// Set the number of shards executed per call to
+    /// [`Executor::execute`](crate::Executor::execute).
+    ///
+    /// This is also the number of shards covered by each checkpoint returned from
+    /// [`Executor::execute_checkpoint`](crate::Executor::execute_checkpoint).
+    pub fn shard_batch_size(&mut self, shard_batch_size: u32) -> &mut Self {
+        self.shard_batch_size = Some(shard_batch_size);
+        self
+    }
+
+    /// Execute common pairs of consecutive instructions, like `lui` followed by `addi` or
+    /// `auipc` followed by `jalr`, as one in simple mode.
+    ///
+    /// The cycles and the state of the program are the same as without fusion.
+    pub fn fuse_instructions(&mut self) -> &mut Self {
+        self.fuse_instructions = true;
+        self
+    }
 }
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Opcode;
+
+use super::LookupId;
+
+/// Arithmetic Logic Unit (ALU) Event.
+///
+/// This object encapsulated the information needed to prove an ALU operation. This includes its
+/// shard, opcode, operands, and other relevant information.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AluEvent {
+    /// The lookup identifer.
+    pub lookup_id: LookupId,
+    /// The shard number.
+    pub shard: u32,
+    /// The clock cycle.
+    pub clk: u32,
+    /// The opcode.
+    pub opcode: Opcode,
+    /// The first operand.
+    pub a: u32,
+    /// The second operand.
+    pub b: u32,
+    /// The third operand.
+    pub c: u32,
+    /// The lookup ids of the operations this one depends on.
+    pub sub_lookups: [LookupId; 6],
+}
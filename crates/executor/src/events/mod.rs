@@ -1,10 +1,14 @@
 //! Type definitions for the events emitted by the [`crate::Executor`] during execution.
 
+mod alu;
+mod cpu;
 mod memory;
 mod precompiles;
 mod syscall;
 mod utils;
 
+pub use alu::*;
+pub use cpu::*;
 pub use memory::*;
 pub use precompiles::*;
 pub use syscall::*;
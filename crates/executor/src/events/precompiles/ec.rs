@@ -13,7 +13,8 @@ use crate::{
         memory::{MemoryReadRecord, MemoryWriteRecord},
         LookupId, MemoryLocalEvent,
     },
-    syscalls::SyscallContext,
+    syscalls::{SyscallCode, SyscallContext},
+    ExecutionError,
 };
 
 /// Elliptic Curve Add Event.
@@ -95,22 +96,21 @@ pub struct EllipticCurveDecompressEvent {
 /// The generic parameter `N` is the number of u32 words in the point representation. For example,
 /// for the secp256k1 curve, `N` would be 16 (64 bytes) because the x and y coordinates are 32 bytes
 /// each.
+///
+/// Returns `None` if the events of the syscall aren't kept, and an error if a pointer is invalid or
+/// the curve can't add the points, such as two equal points on a Weierstrass curve.
 pub fn create_ec_add_event<E: EllipticCurve>(
     rt: &mut SyscallContext,
+    syscall_code: SyscallCode,
     arg1: u32,
     arg2: u32,
-) -> EllipticCurveAddEvent {
+) -> Result<Option<EllipticCurveAddEvent>, ExecutionError> {
     let start_clk = rt.clk;
+    let num_words = <E::BaseField as NumWords>::WordsCurvePoint::USIZE;
     let p_ptr = arg1;
-    if p_ptr % 4 != 0 {
-        panic!();
-    }
+    rt.check_words(syscall_code as u32, p_ptr, num_words)?;
     let q_ptr = arg2;
-    if q_ptr % 4 != 0 {
-        panic!();
-    }
-
-    let num_words = <E::BaseField as NumWords>::WordsCurvePoint::USIZE;
+    rt.check_words(syscall_code as u32, q_ptr, num_words)?;
 
     let p = rt.slice_unsafe(p_ptr, num_words);
 
@@ -119,15 +119,24 @@ pub fn create_ec_add_event<E: EllipticCurve>(
     // When we write to p, we want the clk to be incremented because p and q could be the same.
     rt.clk += 1;
 
-    let p_affine = AffinePoint::<E>::from_words_le(&p);
-    let q_affine = AffinePoint::<E>::from_words_le(&q);
-    let result_affine = p_affine + q_affine;
-
-    let result_words = result_affine.to_words_le();
+    // Use the fixed-width arithmetic of the curve when it has one, and `BigUint` otherwise.
+    let result_words = E::ec_add_words(&p, &q)
+        .or_else(|| {
+            let p_affine = AffinePoint::<E>::from_words_le(&p);
+            let q_affine = AffinePoint::<E>::from_words_le(&q);
+            E::ec_checked_add(&p_affine, &q_affine).map(|point| point.to_words_le())
+        })
+        .ok_or(ExecutionError::InvalidSyscallArgument(
+            syscall_code as u32,
+            q_ptr,
+        ))?;
 
     let p_memory_records = rt.mw_slice(p_ptr, &result_words);
 
-    EllipticCurveAddEvent {
+    if !rt.emits_events() {
+        return Ok(None);
+    }
+    Ok(Some(EllipticCurveAddEvent {
         lookup_id: rt.syscall_lookup_id,
         shard: rt.current_shard(),
         clk: start_clk,
@@ -138,37 +147,44 @@ pub fn create_ec_add_event<E: EllipticCurve>(
         p_memory_records,
         q_memory_records,
         local_mem_access: rt.postprocess(),
-    }
+    }))
 }
 
 /// Create an elliptic curve double event.
 ///
 /// It takes a pointer to a memory location, reads the point from memory, doubles it, and writes the
-/// result back to the memory location.
+/// result back to the memory location. Returns `None` if the events of the syscall aren't kept,
+/// and an error if the pointer is invalid or the curve can't double the point, such as a point with
+/// a zero `y` coordinate on a Weierstrass curve.
 pub fn create_ec_double_event<E: EllipticCurve>(
     rt: &mut SyscallContext,
+    syscall_code: SyscallCode,
     arg1: u32,
     _: u32,
-) -> EllipticCurveDoubleEvent {
+) -> Result<Option<EllipticCurveDoubleEvent>, ExecutionError> {
     let start_clk = rt.clk;
-    let p_ptr = arg1;
-    if p_ptr % 4 != 0 {
-        panic!();
-    }
-
     let num_words = <E::BaseField as NumWords>::WordsCurvePoint::USIZE;
+    let p_ptr = arg1;
+    rt.check_words(syscall_code as u32, p_ptr, num_words)?;
 
     let p = rt.slice_unsafe(p_ptr, num_words);
 
-    let p_affine = AffinePoint::<E>::from_words_le(&p);
-
-    let result_affine = E::ec_double(&p_affine);
-
-    let result_words = result_affine.to_words_le();
+    let result_words = E::ec_double_words(&p)
+        .or_else(|| {
+            E::ec_checked_double(&AffinePoint::<E>::from_words_le(&p))
+                .map(|point| point.to_words_le())
+        })
+        .ok_or(ExecutionError::InvalidSyscallArgument(
+            syscall_code as u32,
+            p_ptr,
+        ))?;
 
     let p_memory_records = rt.mw_slice(p_ptr, &result_words);
 
-    EllipticCurveDoubleEvent {
+    if !rt.emits_events() {
+        return Ok(None);
+    }
+    Ok(Some(EllipticCurveDoubleEvent {
         lookup_id: rt.syscall_lookup_id,
         shard: rt.current_shard(),
         clk: start_clk,
@@ -176,47 +192,61 @@ pub fn create_ec_double_event<E: EllipticCurve>(
         p,
         p_memory_records,
         local_mem_access: rt.postprocess(),
-    }
+    }))
 }
 
 /// Create an elliptic curve decompress event.
 ///
 /// It takes a pointer to a memory location, reads the point from memory, decompresses it, and
-/// writes the result back to the memory location.
+/// writes the result back to the memory location. Returns `None` if the events of the syscall
+/// aren't kept, and an error if the pointer is invalid or the sign bit isn't 0 or 1.
 pub fn create_ec_decompress_event<E: EllipticCurve>(
     rt: &mut SyscallContext,
+    syscall_code: SyscallCode,
     slice_ptr: u32,
     sign_bit: u32,
-) -> EllipticCurveDecompressEvent {
+) -> Result<Option<EllipticCurveDecompressEvent>, ExecutionError> {
     let start_clk = rt.clk;
-    assert!(slice_ptr % 4 == 0, "slice_ptr must be 4-byte aligned");
-    assert!(sign_bit <= 1, "is_odd must be 0 or 1");
-
     let num_limbs = <E::BaseField as NumLimbs>::Limbs::USIZE;
     let num_words_field_element = num_limbs / 4;
+    // The `y` coordinate is followed by the `x` coordinate.
+    rt.check_words(syscall_code as u32, slice_ptr, 2 * num_words_field_element)?;
+    if sign_bit > 1 {
+        return Err(ExecutionError::InvalidSyscallArgument(
+            syscall_code as u32,
+            sign_bit,
+        ));
+    }
 
     let (x_memory_records, x_vec) =
         rt.mr_slice(slice_ptr + (num_limbs as u32), num_words_field_element);
 
     let x_bytes = words_to_bytes_le_vec(&x_vec);
-    let mut x_bytes_be = x_bytes.clone();
-    x_bytes_be.reverse();
 
-    let decompress_fn = match E::CURVE_TYPE {
-        CurveType::Secp256k1 => secp256k1_decompress::<E>,
-        CurveType::Bls12381 => bls12381_decompress::<E>,
-        _ => panic!("Unsupported curve"),
-    };
+    let y_words = E::ec_decompress_words(&x_vec, sign_bit).unwrap_or_else(|| {
+        let mut x_bytes_be = x_bytes.clone();
+        x_bytes_be.reverse();
+
+        let decompress_fn = match E::CURVE_TYPE {
+            CurveType::Secp256k1 => secp256k1_decompress::<E>,
+            CurveType::Bls12381 => bls12381_decompress::<E>,
+            _ => panic!("Unsupported curve"),
+        };
 
-    let computed_point: AffinePoint<E> = decompress_fn(&x_bytes_be, sign_bit);
+        let computed_point: AffinePoint<E> = decompress_fn(&x_bytes_be, sign_bit);
 
-    let mut decompressed_y_bytes = computed_point.y.to_bytes_le();
-    decompressed_y_bytes.resize(num_limbs, 0u8);
-    let y_words = bytes_to_words_le_vec(&decompressed_y_bytes);
+        let mut decompressed_y_bytes = computed_point.y.to_bytes_le();
+        decompressed_y_bytes.resize(num_limbs, 0u8);
+        bytes_to_words_le_vec(&decompressed_y_bytes)
+    });
+    let decompressed_y_bytes = words_to_bytes_le_vec(&y_words);
 
     let y_memory_records = rt.mw_slice(slice_ptr, &y_words);
 
-    EllipticCurveDecompressEvent {
+    if !rt.emits_events() {
+        return Ok(None);
+    }
+    Ok(Some(EllipticCurveDecompressEvent {
         lookup_id: rt.syscall_lookup_id,
         shard: rt.current_shard(),
         clk: start_clk,
@@ -227,5 +257,5 @@ pub fn create_ec_decompress_event<E: EllipticCurve>(
         x_memory_records,
         y_memory_records,
         local_mem_access: rt.postprocess(),
-    }
+    }))
 }
@@ -147,6 +147,22 @@ impl Default for PrecompileEvents {
 }
 
 impl PrecompileEvents {
+    /// Add a precompile event for a given syscall code.
+    ///
+    /// Events are grouped by [`SyscallCode::count_map`], since syscalls that share a table also
+    /// share its rows. The nonce of the syscall event is set to its row in that table.
+    #[inline]
+    pub(crate) fn add_event(
+        &mut self,
+        syscall_code: SyscallCode,
+        mut syscall_event: SyscallEvent,
+        event: PrecompileEvent,
+    ) {
+        let events = self.events.entry(syscall_code.count_map()).or_default();
+        syscall_event.nonce = events.len() as u32;
+        events.push((syscall_event, event));
+    }
+
     /// Checks if the precompile events are empty.
     #[inline]
     #[must_use]
@@ -160,13 +176,15 @@ impl PrecompileEvents {
     }
 
     /// Get all the precompile events for a given syscall code.
+    ///
+    /// Syscalls which share a table with another one (see [`SyscallCode::count_map`]) are looked
+    /// up under the code of that table.
     #[inline]
     #[must_use]
     pub fn get_events(
         &self,
         syscall_code: SyscallCode,
     ) -> Option<&Vec<(SyscallEvent, PrecompileEvent)>> {
-        assert!(syscall_code.should_send() == 1);
-        self.events.get(&syscall_code)
+        self.events.get(&syscall_code.count_map())
     }
 }
@@ -23,6 +23,47 @@ pub struct LookupId {
     pub d: u32,
 }
 
+/// A deterministic source of [``LookupId``]s.
+///
+/// Ids are a keyed hash of the shard, the clock and the index of the lookup within the cycle, so
+/// executing the same program twice, or re-executing a shard from a checkpoint, yields the same
+/// ids.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct LookupIdGenerator {
+    /// The key of the hash.
+    pub seed: u64,
+}
+
+impl LookupIdGenerator {
+    /// Creates a new [``LookupIdGenerator``] with the given seed.
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Returns the id of the `index`-th lookup of the cycle at `clk` in `shard`.
+    #[must_use]
+    pub const fn lookup_id(&self, shard: u32, clk: u32, index: u32) -> LookupId {
+        let cycle = ((shard as u64) << 32) | clk as u64;
+        let hi = splitmix64(self.seed ^ splitmix64(cycle) ^ index as u64);
+        let lo = splitmix64(hi);
+        LookupId {
+            a: (hi >> 32) as u32,
+            b: hi as u32,
+            c: (lo >> 32) as u32,
+            d: lo as u32,
+        }
+    }
+}
+
+/// The `SplitMix64` output function, a bijective mixer of 64-bit values.
+const fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
 /// Creates a new ALU lookup id with ``LookupId``
 #[must_use]
 pub fn create_alu_lookup_id(rng: &mut impl Rng) -> LookupId {
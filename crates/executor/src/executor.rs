@@ -1,26 +1,36 @@
 use std::{
     fs::File,
-    io::{BufWriter, Write},
+    io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    num::NonZeroUsize,
     sync::Arc,
 };
 
 use hashbrown::HashMap;
+use p3_maybe_rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
+    aot::AotBlocks,
     context::SP1Context,
     events::{
-        LookupId, MemoryAccessPosition, MemoryLocalEvent, MemoryReadRecord, MemoryRecord,
-        MemoryWriteRecord,
+        AluEvent, CpuEvent, LookupId, LookupIdGenerator, MemoryAccessPosition,
+        MemoryInitializeFinalizeEvent, MemoryLocalEvent, MemoryReadRecord, MemoryRecord,
+        MemoryRecordEnum, MemoryWriteRecord, SyscallEvent,
     },
     hook::{HookEnv, HookRegistry},
-    memory_map::{MemEntry as Entry, MemoryMap},
-    state::{ExecutionState, ForkState},
+    memory_map::{page_runs, AlreadyInitialized, MemEntry as Entry, MemoryMap},
+    ops::{alu, fuse, Fused, Op},
+    record::ExecutionRecord,
+    report::ExecutionReport,
+    state::{CheckpointError, ExecutionState, ForkState, MemoryTimestamp},
     syscalls::{default_syscall_map, LocalMemAccessMap, Syscall, SyscallCode, SyscallContext},
     Instruction, Opcode, Program, Register,
 };
 
+#[cfg(feature = "jit")]
+use crate::jit::Jit;
+
 /// An executor for the SP1 RISC-V zkVM.
 ///
 /// The exeuctor is responsible for executing a user program and tracing important events which
@@ -43,26 +53,52 @@ pub struct Executor<'a> {
     /// Whether we should write to the report.
     pub print_report: bool,
 
+    /// Report of the program execution.
+    pub report: ExecutionReport,
+
     /// The maximum number of cycles for a syscall.
     pub max_syscall_cycles: u32,
 
     /// The mapping between syscall codes and their implementations.
     pub syscall_map: HashMap<SyscallCode, Arc<dyn Syscall>>,
 
+    /// The mapping between the numbers of syscalls that [`SyscallCode`] doesn't list and their
+    /// implementations.
+    pub custom_syscall_map: HashMap<u32, Arc<dyn Syscall<u32>>>,
+
     /// Memory addresses that were touched in this batch of shards. Used to minimize the size of
     /// checkpoints.
     pub memory_checkpoint: HashMap<u32, Option<MemoryRecord>>,
 
     /// Memory addresses that were initialized in this batch of shards. Used to minimize the size of
-    /// checkpoints. The value stored is whether or not it had a value at the beginning of the batch.
+    /// checkpoints. The value stored is whether or not it was initialized with a non-zero value.
     pub uninitialized_memory_checkpoint: HashMap<u32, bool>,
 
+    /// The addresses of the regions of uninitialized memory that were hinted in this batch of
+    /// shards, which re-executing the batch hints again.
+    pub hint_checkpoint: Vec<u32>,
+
     /// The maximum number of cpu cycles to use for execution.
     pub max_cycles: Option<u64>,
 
+    /// The maximum size of each shard, in units of `clk`.
+    pub shard_size: u32,
+
+    /// The number of shards to execute in each call to [`Executor::execute`].
+    pub shard_batch_size: u32,
+
     /// The state of the execution.
     pub state: ExecutionState,
 
+    /// The current trace of the execution that is being collected.
+    pub record: ExecutionRecord,
+
+    /// The collected records of the shards that have finished executing.
+    pub records: Vec<ExecutionRecord>,
+
+    /// Memory accesses of the instruction currently being executed.
+    pub memory_accesses: MemoryAccessRecord,
+
     /// Local memory access events.
     pub local_memory_access: LocalMemAccessMap,
 
@@ -83,6 +119,36 @@ pub struct Executor<'a> {
 
     /// The maximal shapes for the program.
     pub maximal_shapes: Option<Vec<HashMap<String, usize>>>,
+
+    /// The generator of the lookup ids of the events.
+    pub lookup_id_generator: LookupIdGenerator,
+
+    /// For each instruction of the program, the index one past the end of the basic block that
+    /// starts at it.
+    pub basic_block_ends: Vec<u32>,
+
+    /// The instructions of the program, lowered for execution.
+    pub(crate) ops: Vec<Op>,
+
+    /// The blocks of the program compiled ahead of time for simple mode, if any.
+    pub(crate) aot_blocks: Option<AotBlocks>,
+
+    /// The blocks of the program compiled for simple mode.
+    #[cfg(feature = "jit")]
+    pub(crate) jit: Jit,
+}
+
+/// The memory accesses made by a single instruction.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct MemoryAccessRecord {
+    /// The memory access of the `a` register.
+    pub a: Option<MemoryRecordEnum>,
+    /// The memory access of the `b` register.
+    pub b: Option<MemoryRecordEnum>,
+    /// The memory access of the `c` register.
+    pub c: Option<MemoryRecordEnum>,
+    /// The memory access of the `memory` register.
+    pub memory: Option<MemoryRecordEnum>,
 }
 
 /// The different modes the executor can run in.
@@ -130,8 +196,46 @@ pub enum ExecutionError {
     /// The program ended in unconstrained mode.
     #[error("program ended in unconstrained mode")]
     EndInUnconstrained(),
+
+    /// The execution failed because a syscall was given a pointer that isn't aligned to 4 bytes.
+    #[error("syscall {0:#x} got misaligned pointer {1:#x}")]
+    MisalignedSyscallPointer(u32, u32),
+
+    /// The execution failed because a syscall was given an invalid argument.
+    #[error("syscall {0:#x} got invalid argument {1:#x}")]
+    InvalidSyscallArgument(u32, u32),
+
+    /// The execution failed because the program read more inputs than were written to stdin.
+    #[error(
+        "failed reading stdin due to insufficient input data: input_stream_ptr={0}, input_stream_len={1}"
+    )]
+    StdinExhausted(usize, usize),
+
+    /// The execution failed because the program read a hint with a length other than its own.
+    #[error("hint input stream read length mismatch: read {0} bytes of a hint of {1} bytes")]
+    HintLengthMismatch(u32, usize),
+
+    /// The execution failed because a hint was read into an address that was already hinted.
+    #[error("hint read address {0:#x} is initialized already")]
+    DoubleInitialization(u32),
+
+    /// The executor could not be created because its shard size, in cycles, is out of range.
+    #[error("shard size of {0} cycles is out of range")]
+    InvalidShardSize(u64),
+}
+
+impl From<AlreadyInitialized> for ExecutionError {
+    fn from(AlreadyInitialized(addr): AlreadyInitialized) -> Self {
+        Self::DoubleInitialization(addr)
+    }
 }
 
+/// The default number of cpu cycles in a shard.
+pub const DEFAULT_SHARD_SIZE: u64 = 1 << 21;
+
+/// The default number of shards executed in each call to [`Executor::execute`].
+pub const DEFAULT_SHARD_BATCH_SIZE: u32 = 1;
+
 macro_rules! assert_valid_memory_access {
     ($addr:expr, $position:expr) => {
         #[cfg(not(debug_assertions))]
@@ -139,20 +243,50 @@ macro_rules! assert_valid_memory_access {
     };
 }
 
+/// [`ExecutorMode::Simple`] as the `MODE` parameter of the mode-specialized methods.
+pub(crate) const SIMPLE: u8 = ExecutorMode::Simple as u8;
+
+/// [`ExecutorMode::Checkpoint`] as the `MODE` parameter of the mode-specialized methods.
+const CHECKPOINT: u8 = ExecutorMode::Checkpoint as u8;
+
+/// [`ExecutorMode::Trace`] as the `MODE` parameter of the mode-specialized methods.
+const TRACE: u8 = ExecutorMode::Trace as u8;
+
+/// Calls the version of a mode-specialized method for the current mode of the executor.
+macro_rules! dispatch_mode {
+    ($executor:ident.$method:ident($($arg:expr),* $(,)?)) => {
+        match ($executor.executor_mode, $executor.unconstrained) {
+            // Unconstrained blocks always run in simple mode.
+            (_, true) => $executor.$method::<SIMPLE, true>($($arg),*),
+            (ExecutorMode::Simple, false) => $executor.$method::<SIMPLE, false>($($arg),*),
+            (ExecutorMode::Checkpoint, false) => {
+                $executor.$method::<CHECKPOINT, false>($($arg),*)
+            }
+            (ExecutorMode::Trace, false) => $executor.$method::<TRACE, false>($($arg),*),
+        }
+    };
+}
+
 impl<'a> Executor<'a> {
     /// Create a new [``Executor``] from a program and options.
     #[must_use]
     pub fn new(program: Program) -> Self {
         Self::with_context(program, SP1Context::default())
+            .expect("the default shard size fits the default syscalls")
     }
 
     /// Create a new runtime from a program, options, and a context.
     ///
+    /// # Errors
+    ///
+    /// Returns [`ExecutionError::InvalidShardSize`] if the shard size of the context doesn't fit
+    /// in the clock of a shard, or if a shard is too small for the syscall with the most extra
+    /// cycles.
+    ///
     /// # Panics
     ///
     /// This function may panic if it fails to create the trace file if `TRACE_FILE` is set.
-    #[must_use]
-    pub fn with_context(program: Program, context: SP1Context<'a>) -> Self {
+    pub fn with_context(program: Program, context: SP1Context<'a>) -> Result<Self, ExecutionError> {
         // Create a shared reference to the program.
         let program = Arc::new(program);
 
@@ -165,17 +299,43 @@ impl<'a> Executor<'a> {
         };
 
         // Determine the maximum number of cycles for any syscall.
-        let syscall_map = default_syscall_map();
+        let syscall_map = context.syscall_map.unwrap_or_else(default_syscall_map);
+        let custom_syscall_map = context.custom_syscall_map;
         let max_syscall_cycles = syscall_map
             .values()
             .map(|syscall| syscall.num_extra_cycles())
+            .chain(
+                custom_syscall_map
+                    .values()
+                    .map(|syscall| syscall.num_extra_cycles()),
+            )
             .max()
             .unwrap_or(0);
 
+        // The clock advances by 4 every cycle, and a shard must have room for any syscall.
+        let cycles = context.shard_size.unwrap_or(DEFAULT_SHARD_SIZE);
+        let shard_size = cycles
+            .checked_mul(4)
+            .and_then(|shard_size| u32::try_from(shard_size).ok())
+            .filter(|&shard_size| shard_size > max_syscall_cycles)
+            .ok_or(ExecutionError::InvalidShardSize(cycles))?;
+
         let hook_registry = context.hook_registry.unwrap_or_default();
+        let basic_block_ends = program.basic_block_ends();
+        let mut ops = program
+            .instructions
+            .iter()
+            .map(Op::lower)
+            .collect::<Vec<_>>();
+        if context.fuse_instructions {
+            fuse(&mut ops, program.pc_base);
+        }
 
-        Self {
+        Ok(Self {
             state: ExecutionState::new(program.pc_start),
+            record: ExecutionRecord::new(program.clone()),
+            records: Vec::new(),
+            memory_accesses: MemoryAccessRecord::default(),
             program,
             cycle_tracker: HashMap::new(),
             io_buf: HashMap::new(),
@@ -183,16 +343,27 @@ impl<'a> Executor<'a> {
             unconstrained: false,
             unconstrained_state: ForkState::default(),
             syscall_map,
+            custom_syscall_map,
             executor_mode: ExecutorMode::Trace,
             max_syscall_cycles,
             print_report: false,
+            report: ExecutionReport::default(),
             hook_registry,
             max_cycles: context.max_cycles,
+            shard_size,
+            shard_batch_size: context.shard_batch_size.unwrap_or(DEFAULT_SHARD_BATCH_SIZE),
             memory_checkpoint: HashMap::new(),
             uninitialized_memory_checkpoint: HashMap::new(),
+            hint_checkpoint: Vec::new(),
             local_memory_access: LocalMemAccessMap::new(),
             maximal_shapes: None,
-        }
+            lookup_id_generator: LookupIdGenerator::default(),
+            basic_block_ends,
+            ops,
+            aot_blocks: None,
+            #[cfg(feature = "jit")]
+            jit: Jit::new(),
+        })
     }
 
     /// Invokes a hook with the given file descriptor `fd` with the data `buf`.
@@ -223,87 +394,92 @@ impl<'a> Executor<'a> {
         runtime
     }
 
-    /// Get the current values of the registers.
-    #[allow(clippy::single_match_else)]
-    #[must_use]
-    pub fn registers(&mut self) -> [u32; 32] {
-        let mut registers = [0; 32];
-        for i in 0..32 {
-            let addr = Register::from_u32(i as u32) as u32;
-            let record = self.state.memory.get(&addr);
-
-            // Only add the previous memory state to checkpoint map if we're in checkpoint mode,
-            // or if we're in unconstrained mode. In unconstrained mode, the mode is always
-            // Simple.
-            if self.executor_mode == ExecutorMode::Checkpoint || self.unconstrained {
-                match record {
-                    Some(record) => {
-                        self.memory_checkpoint
-                            .entry(addr)
-                            .or_insert_with(|| Some(*record));
-                    }
-                    None => {
-                        self.memory_checkpoint.entry(addr).or_insert(None);
-                    }
-                }
-            }
+    /// Save the executor to a checkpoint file, from which it can be resumed with
+    /// [`Executor::resume_from_file`].
+    ///
+    /// Besides the [`ExecutionState`], this saves the memory checkpoint of the current batch, the
+    /// IO buffers, the open cycle tracker spans and the report. It should be called between
+    /// batches, since the record of the current shard is not saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the file fails.
+    pub fn save_to_file(&self, file: &mut File) -> Result<(), CheckpointError> {
+        let mut writer = BufWriter::new(file);
+        self.state.write_checkpoint(&self.program, &mut writer)?;
+        bincode::serialize_into(
+            &mut writer,
+            &(
+                &self.memory_checkpoint,
+                &self.uninitialized_memory_checkpoint,
+                &self.hint_checkpoint,
+                &self.io_buf,
+                &self.cycle_tracker,
+                &self.report,
+            ),
+        )?;
+        writer.flush()?;
+        writer.seek(SeekFrom::Start(0))?;
+        Ok(())
+    }
 
-            registers[i] = match record {
-                Some(record) => record.value,
-                None => 0,
-            };
+    /// Resume an executor of `program` from a checkpoint file.
+    ///
+    /// The file may also have been written by [`ExecutionState::save`], in which case only the
+    /// state is restored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading the file fails, if it isn't a checkpoint of `program`, or if
+    /// `context` is invalid.
+    pub fn resume_from_file(
+        program: Program,
+        context: SP1Context<'a>,
+        file: &mut File,
+    ) -> Result<Self, CheckpointError> {
+        let mut reader = BufReader::new(file);
+        let state = ExecutionState::read_checkpoint(&program, &mut reader)?;
+        let mut runtime = Self::with_context(program, context)?;
+        runtime.state = state;
+        if !reader.fill_buf()?.is_empty() {
+            (
+                runtime.memory_checkpoint,
+                runtime.uninitialized_memory_checkpoint,
+                runtime.hint_checkpoint,
+                runtime.io_buf,
+                runtime.cycle_tracker,
+                runtime.report,
+            ) = bincode::deserialize_from(reader)?;
         }
-        registers
+        Ok(runtime)
     }
 
-    /// Get the current value of a register.
+    /// Get the current values of the registers.
     #[must_use]
-    pub fn register(&mut self, register: Register) -> u32 {
-        let addr = register as u32;
-        let record = self.state.memory.get(&addr);
-
-        if self.executor_mode == ExecutorMode::Checkpoint || self.unconstrained {
-            match record {
-                Some(record) => {
-                    self.memory_checkpoint
-                        .entry(addr)
-                        .or_insert_with(|| Some(*record));
-                }
-                None => {
-                    self.memory_checkpoint.entry(addr).or_insert(None);
-                }
-            }
-        }
+    pub fn registers(&self) -> [u32; 32] {
+        self.state.registers
+    }
 
-        match record {
-            Some(record) => record.value,
-            None => 0,
-        }
+    /// Get the current value of a register.
+    #[must_use]
+    pub fn register(&self, register: Register) -> u32 {
+        self.state.registers[register as usize]
     }
 
     /// Get the current value of a word.
     #[must_use]
     pub fn word(&mut self, addr: u32) -> u32 {
-        #[allow(clippy::single_match_else)]
-        let record = self.state.memory.get(&addr);
-
-        if self.executor_mode == ExecutorMode::Checkpoint || self.unconstrained {
-            match record {
-                Some(record) => {
-                    self.memory_checkpoint
-                        .entry(addr)
-                        .or_insert_with(|| Some(*record));
-                }
-                None => {
-                    self.memory_checkpoint.entry(addr).or_insert(None);
-                }
-            }
-        }
+        dispatch_mode!(self.word_in_mode(addr))
+    }
 
-        match record {
-            Some(record) => record.value,
-            None => 0,
+    /// Get the current value of a word in the given mode.
+    #[inline]
+    fn word_in_mode<const MODE: u8, const UNCONSTRAINED: bool>(&mut self, addr: u32) -> u32 {
+        if addr < 32 {
+            return self.state.registers[addr as usize];
         }
+        self.checkpoint_memory::<MODE, UNCONSTRAINED>(addr);
+        self.state.memory.get(&addr).copied().unwrap_or(0)
     }
 
     /// Get the current value of a byte.
@@ -326,183 +502,361 @@ impl<'a> Executor<'a> {
         self.state.current_shard
     }
 
-    /// Read a word from memory and create an access record.
-    pub fn mr(
-        &mut self,
-        addr: u32,
-        shard: u32,
-        timestamp: u32,
-        local_memory_access: Option<&mut LocalMemAccessMap>,
-    ) -> MemoryReadRecord {
-        // Get the memory record entry.
-        let entry = self.state.memory.entry(addr);
-        if self.executor_mode == ExecutorMode::Checkpoint || self.unconstrained {
-            match entry {
-                Entry::Occupied(ref entry) => {
-                    let record = entry.get();
-                    self.memory_checkpoint
-                        .entry(addr)
-                        .or_insert_with(|| Some(*record));
-                }
-                Entry::Vacant(_) => {
-                    self.memory_checkpoint.entry(addr).or_insert(None);
-                }
-            }
+    /// Save the record of an address before it is first accessed in this batch of shards or in
+    /// this unconstrained block, so that it can be restored.
+    ///
+    /// Registers are not saved here, since they are saved all at once at the start of the batch
+    /// or the block.
+    #[inline]
+    fn checkpoint_memory<const MODE: u8, const UNCONSTRAINED: bool>(&mut self, addr: u32) {
+        // Only add the previous memory state to checkpoint map if we're in checkpoint mode,
+        // or if we're in unconstrained mode. In unconstrained mode, the mode is always
+        // Simple.
+        if MODE == CHECKPOINT || UNCONSTRAINED {
+            let state = &self.state;
+            self.memory_checkpoint
+                .entry(addr)
+                .or_insert_with(|| state.memory_record(addr));
         }
 
         // If we're in unconstrained mode, we don't want to modify state, so we'll save the
         // original state if it's the first time modifying it.
-        if self.unconstrained {
-            let record = match entry {
-                Entry::Occupied(ref entry) => Some(entry.get()),
-                Entry::Vacant(_) => None,
-            };
+        if UNCONSTRAINED {
+            let state = &self.state;
             self.unconstrained_state
                 .memory_diff
                 .entry(addr)
-                .or_insert(record.copied());
+                .or_insert_with(|| state.memory_record(addr));
         }
+    }
 
-        // If it's the first time accessing this address, initialize previous values.
-        let record: &mut MemoryRecord = match entry {
-            Entry::Occupied(entry) => entry.into_mut(),
+    /// Get the value of an address, initializing it if it's the first time the address is
+    /// accessed. Also returns whether it was.
+    #[inline]
+    fn memory_value<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        addr: u32,
+    ) -> (&mut u32, bool) {
+        match self.state.memory.entry(addr) {
+            Entry::Occupied(entry) => (entry.into_mut(), false),
             Entry::Vacant(entry) => {
                 // If addr has a specific value to be initialized with, use that, otherwise 0.
-                let value = self.state.uninitialized_memory.get(&addr).unwrap_or(&0);
-                self.uninitialized_memory_checkpoint
-                    .entry(addr)
-                    .or_insert_with(|| *value != 0);
-                entry.insert(MemoryRecord::new(shard, timestamp, *value))
+                let value = self.state.uninitialized_memory.get(addr).unwrap_or(0);
+                if MODE == CHECKPOINT || UNCONSTRAINED {
+                    self.uninitialized_memory_checkpoint
+                        .entry(addr)
+                        .or_insert(value != 0);
+                }
+                (entry.insert(value), true)
             }
+        }
+    }
+
+    /// Set the last access of an address, returning the previous one. If the address has no
+    /// previous access, `initial` is returned if given, and shard 0 and timestamp 0 otherwise.
+    #[inline]
+    fn update_memory_timestamp(
+        &mut self,
+        addr: u32,
+        last: MemoryTimestamp,
+        initial: Option<MemoryTimestamp>,
+    ) -> MemoryTimestamp {
+        self.state
+            .memory_timestamps
+            .insert(addr, last)
+            .or(initial)
+            .unwrap_or_default()
+    }
+
+    /// Record a local memory access event for the address, i.e. its first and last access in the
+    /// current shard.
+    fn emit_local_memory_access(
+        &mut self,
+        addr: u32,
+        prev_record: MemoryRecord,
+        record: MemoryRecord,
+        local_memory_access: Option<&mut LocalMemAccessMap>,
+    ) {
+        let local_memory_access = if let Some(local_memory_access) = local_memory_access {
+            local_memory_access
+        } else {
+            &mut self.local_memory_access
         };
 
-        let prev_record = *record;
-        record.set_shard(shard);
-        record.timestamp = timestamp;
+        local_memory_access
+            .entry(addr)
+            .and_modify(|e| {
+                e.final_mem_access = record;
+            })
+            .or_insert(MemoryLocalEvent {
+                addr,
+                initial_mem_access: prev_record,
+                final_mem_access: record,
+            });
+    }
 
-        if !self.unconstrained {
-            let local_memory_access = if let Some(local_memory_access) = local_memory_access {
-                local_memory_access
-            } else {
-                &mut self.local_memory_access
-            };
+    /// Read a word from memory and create an access record.
+    ///
+    /// In [`ExecutorMode::Simple`], only the value is read and the previous shard and timestamp of
+    /// the record are 0.
+    pub fn mr(
+        &mut self,
+        addr: u32,
+        shard: u32,
+        timestamp: u32,
+        local_memory_access: Option<&mut LocalMemAccessMap>,
+    ) -> MemoryReadRecord {
+        dispatch_mode!(self.mr_in_mode(addr, shard, timestamp, local_memory_access))
+    }
 
-            local_memory_access
-                .entry(addr)
-                .and_modify(|e| {
-                    e.final_mem_access = *record;
-                })
-                .or_insert(MemoryLocalEvent {
-                    addr,
-                    initial_mem_access: prev_record,
-                    final_mem_access: *record,
-                });
+    /// Read a word from memory and create an access record in the given mode.
+    #[inline]
+    fn mr_in_mode<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        addr: u32,
+        shard: u32,
+        timestamp: u32,
+        local_memory_access: Option<&mut LocalMemAccessMap>,
+    ) -> MemoryReadRecord {
+        if addr < 32 {
+            // If it's the first time accessing this register, it was last accessed right now.
+            let first = MemoryTimestamp::new(shard, timestamp);
+            return self.register_read::<MODE, UNCONSTRAINED>(
+                addr as usize,
+                shard,
+                timestamp,
+                first,
+                local_memory_access,
+            );
+        }
+
+        self.checkpoint_memory::<MODE, UNCONSTRAINED>(addr);
+        let (value, first_access) = self.memory_value::<MODE, UNCONSTRAINED>(addr);
+        let value = *value;
+        if MODE == SIMPLE {
+            return MemoryReadRecord::new(value, shard, timestamp, 0, 0);
+        }
+
+        // If it's the first time accessing this address, it was last accessed right now.
+        let last = MemoryTimestamp::new(shard, timestamp);
+        let prev = self.update_memory_timestamp(addr, last, first_access.then_some(last));
+
+        if !UNCONSTRAINED && MODE == TRACE {
+            self.emit_local_memory_access(
+                addr,
+                MemoryRecord::new(prev.shard, prev.timestamp, value),
+                MemoryRecord::new(shard, timestamp, value),
+                local_memory_access,
+            );
         }
 
         // Construct the memory read record.
-        MemoryReadRecord::new(
-            record.value,
-            record.shard(),
-            record.timestamp,
-            prev_record.shard(),
-            prev_record.timestamp,
-        )
+        MemoryReadRecord::new(value, shard, timestamp, prev.shard, prev.timestamp)
     }
 
     /// Write a word to memory and create an access record.
+    ///
+    /// In [`ExecutorMode::Simple`], only the value is written and the previous shard and timestamp
+    /// of the record are 0.
     pub fn mw(
         &mut self,
         addr: u32,
         value: u32,
         shard: u32,
         timestamp: u32,
-        local_memory_access: Option<&mut HashMap<u32, MemoryLocalEvent>>,
+        local_memory_access: Option<&mut LocalMemAccessMap>,
     ) -> MemoryWriteRecord {
-        // Get the memory record entry.
-        let entry = self.state.memory.entry(addr);
-        if self.executor_mode == ExecutorMode::Checkpoint || self.unconstrained {
-            match entry {
-                Entry::Occupied(ref entry) => {
-                    let record = entry.get();
-                    self.memory_checkpoint
-                        .entry(addr)
-                        .or_insert_with(|| Some(*record));
-                }
-                Entry::Vacant(_) => {
-                    self.memory_checkpoint.entry(addr).or_insert(None);
-                }
-            }
+        dispatch_mode!(self.mw_in_mode(addr, value, shard, timestamp, local_memory_access))
+    }
+
+    /// Write a word to memory and create an access record in the given mode.
+    #[inline]
+    fn mw_in_mode<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        addr: u32,
+        value: u32,
+        shard: u32,
+        timestamp: u32,
+        local_memory_access: Option<&mut LocalMemAccessMap>,
+    ) -> MemoryWriteRecord {
+        if addr < 32 {
+            // If it's the first time accessing this register, it was last accessed at the start
+            // of the shard.
+            let first = MemoryTimestamp::new(shard, 0);
+            return self.register_write::<MODE, UNCONSTRAINED>(
+                addr as usize,
+                value,
+                shard,
+                timestamp,
+                first,
+                local_memory_access,
+            );
         }
 
-        // If we're in unconstrained mode, we don't want to modify state, so we'll save the
-        // original state if it's the first time modifying it.
-        if self.unconstrained {
-            let record = match entry {
-                Entry::Occupied(ref entry) => Some(entry.get()),
-                Entry::Vacant(_) => None,
-            };
-            self.unconstrained_state
-                .memory_diff
-                .entry(addr)
-                .or_insert(record.copied());
+        self.checkpoint_memory::<MODE, UNCONSTRAINED>(addr);
+        let (slot, first_access) = self.memory_value::<MODE, UNCONSTRAINED>(addr);
+        let prev_value = std::mem::replace(slot, value);
+        if MODE == SIMPLE {
+            return MemoryWriteRecord::new(value, shard, timestamp, prev_value, 0, 0);
         }
 
-        // If it's the first time accessing this address, initialize previous values.
-        let record: &mut MemoryRecord = match entry {
-            Entry::Occupied(entry) => entry.into_mut(),
-            Entry::Vacant(entry) => {
-                // If addr has a specific value to be initialized with, use that, otherwise 0.
-                let value = self.state.uninitialized_memory.get(&addr).unwrap_or(&0);
-                self.uninitialized_memory_checkpoint
-                    .entry(addr)
-                    .or_insert_with(|| *value != 0);
+        // If it's the first time accessing this address, it was last accessed at the start of
+        // the shard.
+        let initial = MemoryTimestamp::new(shard, 0);
+        let prev = self.update_memory_timestamp(
+            addr,
+            MemoryTimestamp::new(shard, timestamp),
+            first_access.then_some(initial),
+        );
+
+        if !UNCONSTRAINED && MODE == TRACE {
+            self.emit_local_memory_access(
+                addr,
+                MemoryRecord::new(prev.shard, prev.timestamp, prev_value),
+                MemoryRecord::new(shard, timestamp, value),
+                local_memory_access,
+            );
+        }
+
+        // Construct the memory write record.
+        MemoryWriteRecord::new(
+            value,
+            shard,
+            timestamp,
+            prev_value,
+            prev.shard,
+            prev.timestamp,
+        )
+    }
 
-                entry.insert(MemoryRecord::new(shard, 0, *value))
+    /// Read consecutive words from memory into `records`, like [`Executor::mr`] on each of them.
+    ///
+    /// In [`ExecutorMode::Simple`], the words are resolved with one lookup per page.
+    pub fn mr_words(
+        &mut self,
+        addr: u32,
+        shard: u32,
+        timestamp: u32,
+        mut local_memory_access: Option<&mut LocalMemAccessMap>,
+        records: &mut [MemoryReadRecord],
+    ) {
+        if self.executor_mode != ExecutorMode::Simple || self.unconstrained || addr < 32 {
+            for (i, record) in records.iter_mut().enumerate() {
+                *record = self.mr(
+                    addr + 4 * i as u32,
+                    shard,
+                    timestamp,
+                    local_memory_access.as_deref_mut(),
+                );
             }
-        };
+            return;
+        }
 
-        let prev_record = *record;
-        record.value = value;
-        record.set_shard(shard);
-        record.timestamp = timestamp;
+        let state = &mut self.state;
+        for (run_addr, run) in page_runs(addr, records.len()) {
+            let slots = state.memory.run_mut(run_addr, run.len());
+            for (i, slot) in run.zip(slots) {
+                // As in `memory_value`, a word is initialized when it is first accessed.
+                let value = *slot.get_or_insert_with(|| {
+                    let addr = addr + 4 * i as u32;
+                    state.uninitialized_memory.get(addr).unwrap_or(0)
+                });
+                records[i] = MemoryReadRecord::new(value, shard, timestamp, 0, 0);
+            }
+        }
+    }
 
-        if !self.unconstrained {
-            let local_memory_access = if let Some(local_memory_access) = local_memory_access {
-                local_memory_access
-            } else {
-                &mut self.local_memory_access
-            };
+    /// Write consecutive words to memory, with their records in `records`, like [`Executor::mw`]
+    /// on each of them.
+    ///
+    /// In [`ExecutorMode::Simple`], the words are resolved with one lookup per page.
+    pub fn mw_words(
+        &mut self,
+        addr: u32,
+        values: &[u32],
+        shard: u32,
+        timestamp: u32,
+        mut local_memory_access: Option<&mut LocalMemAccessMap>,
+        records: &mut [MemoryWriteRecord],
+    ) {
+        assert_eq!(values.len(), records.len());
+        if self.executor_mode != ExecutorMode::Simple || self.unconstrained || addr < 32 {
+            for (i, record) in records.iter_mut().enumerate() {
+                *record = self.mw(
+                    addr + 4 * i as u32,
+                    values[i],
+                    shard,
+                    timestamp,
+                    local_memory_access.as_deref_mut(),
+                );
+            }
+            return;
+        }
 
-            local_memory_access
-                .entry(addr)
-                .and_modify(|e| {
-                    e.final_mem_access = *record;
-                })
-                .or_insert(MemoryLocalEvent {
-                    addr,
-                    initial_mem_access: prev_record,
-                    final_mem_access: *record,
+        let state = &mut self.state;
+        for (run_addr, run) in page_runs(addr, values.len()) {
+            let slots = state.memory.run_mut(run_addr, run.len());
+            for (i, slot) in run.zip(slots) {
+                let prev_value = slot.replace(values[i]).unwrap_or_else(|| {
+                    let addr = addr + 4 * i as u32;
+                    state.uninitialized_memory.get(addr).unwrap_or(0)
                 });
+                records[i] = MemoryWriteRecord::new(values[i], shard, timestamp, prev_value, 0, 0);
+            }
         }
+    }
 
-        // Construct the memory write record.
-        MemoryWriteRecord::new(
-            record.value,
-            record.shard(),
-            record.timestamp,
-            prev_record.value,
-            prev_record.shard(),
-            prev_record.timestamp,
-        )
+    /// Get the current values of consecutive words into `values`, like [`Executor::word`] on each
+    /// of them.
+    ///
+    /// In [`ExecutorMode::Simple`], the words are resolved with one lookup per page.
+    pub fn words(&mut self, addr: u32, values: &mut [u32]) {
+        if self.executor_mode != ExecutorMode::Simple || self.unconstrained || addr < 32 {
+            for (i, value) in values.iter_mut().enumerate() {
+                *value = self.word(addr + 4 * i as u32);
+            }
+            return;
+        }
+
+        for (run_addr, run) in page_runs(addr, values.len()) {
+            let values = &mut values[run.clone()];
+            match self.state.memory.get_run(run_addr, run.len()) {
+                Some(slots) => {
+                    for (value, slot) in values.iter_mut().zip(slots) {
+                        *value = slot.unwrap_or(0);
+                    }
+                }
+                None => values.fill(0),
+            }
+        }
     }
 
     /// Read from memory, assuming that all addresses are aligned.
     pub fn mr_cpu(&mut self, addr: u32, position: MemoryAccessPosition) -> u32 {
+        dispatch_mode!(self.mr_cpu_in_mode(addr, position))
+    }
+
+    /// Read from memory in the given mode, assuming that all addresses are aligned.
+    fn mr_cpu_in_mode<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        addr: u32,
+        position: MemoryAccessPosition,
+    ) -> u32 {
         // Assert that the address is aligned.
         assert_valid_memory_access!(addr, position);
 
         // Read the address from memory and create a memory read record.
-        let record = self.mr(addr, self.shard(), self.timestamp(&position), None);
+        let record = self.mr_in_mode::<MODE, UNCONSTRAINED>(
+            addr,
+            self.shard(),
+            self.timestamp(&position),
+            None,
+        );
+
+        // If we're not in unconstrained mode, record the access for the current cycle.
+        if MODE == TRACE {
+            self.set_memory_access(position, record.into());
+        }
 
         record.value
     }
@@ -514,229 +868,310 @@ impl<'a> Executor<'a> {
     /// This function will panic if the address is not aligned or if the memory accesses are already
     /// initialized.
     pub fn mw_cpu(&mut self, addr: u32, value: u32, position: MemoryAccessPosition) {
+        dispatch_mode!(self.mw_cpu_in_mode(addr, value, position));
+    }
+
+    /// Write to memory in the given mode, like [`Self::mw_cpu`].
+    fn mw_cpu_in_mode<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        addr: u32,
+        value: u32,
+        position: MemoryAccessPosition,
+    ) {
         // Assert that the address is aligned.
         assert_valid_memory_access!(addr, position);
 
         // Read the address from memory and create a memory read record.
-        self.mw(addr, value, self.shard(), self.timestamp(&position), None);
+        let record = self.mw_in_mode::<MODE, UNCONSTRAINED>(
+            addr,
+            value,
+            self.shard(),
+            self.timestamp(&position),
+            None,
+        );
+
+        // If we're not in unconstrained mode, record the access for the current cycle.
+        if MODE == TRACE {
+            self.set_memory_access(position, record.into());
+        }
     }
 
-    /// Read from a register.
-    pub fn rr(&mut self, register: Register, position: MemoryAccessPosition) -> u32 {
-        let addr = register as u32;
-        assert_valid_memory_access!(addr, position);
-
-        let shard = self.shard();
-        let timestamp = self.timestamp(&position);
-
-        if self.executor_mode == ExecutorMode::Checkpoint || self.unconstrained {
-            match &self.state.memory.registers[register as usize] {
-                Some(value) => {
-                    self.memory_checkpoint
-                        .entry(addr)
-                        .or_insert_with(|| Some(*value));
-                }
-                None => {
-                    self.memory_checkpoint.entry(addr).or_insert(None);
-                }
-            }
+    /// Record a memory access of the current instruction at the given position.
+    fn set_memory_access(&mut self, position: MemoryAccessPosition, record: MemoryRecordEnum) {
+        match position {
+            MemoryAccessPosition::A => self.memory_accesses.a = Some(record),
+            MemoryAccessPosition::B => self.memory_accesses.b = Some(record),
+            MemoryAccessPosition::C => self.memory_accesses.c = Some(record),
+            MemoryAccessPosition::Memory => self.memory_accesses.memory = Some(record),
         }
+    }
 
-        // If we're in unconstrained mode, we don't want to modify state, so we'll save the
-        // original state if it's the first time modifying it.
-        if self.unconstrained {
-            self.unconstrained_state
-                .memory_diff
-                .entry(addr)
-                .or_insert(self.state.memory.registers[register as usize].clone());
+    /// Read a register and create an access record. If it's the first time the register is
+    /// accessed, it was last accessed at `first`.
+    #[inline]
+    fn register_read<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        register: usize,
+        shard: u32,
+        timestamp: u32,
+        first: MemoryTimestamp,
+        local_memory_access: Option<&mut LocalMemAccessMap>,
+    ) -> MemoryReadRecord {
+        let value = self.state.registers[register];
+        if MODE == SIMPLE {
+            return MemoryReadRecord::new(value, shard, timestamp, 0, 0);
         }
 
-        // If it's the first time accessing this address, initialize previous values.
-        let record: &mut MemoryRecord =
-            match self.state.memory.registers[register as usize].as_mut() {
-                Some(record) => record,
-                None => {
-                    // If addr has a specific value to be initialized with, use that, otherwise 0.
-                    let value = self.state.uninitialized_memory.get(&addr).unwrap_or(&0);
-                    self.uninitialized_memory_checkpoint
-                        .entry(addr)
-                        .or_insert_with(|| *value != 0);
-                    (&mut self.state.memory.registers[register as usize])
-                        .insert(MemoryRecord::new(0, 0, *value))
-                }
-            };
+        let prev = self.state.register_timestamps[register]
+            .replace(MemoryTimestamp::new(shard, timestamp))
+            .unwrap_or(first);
 
-        let prev_record = *record;
-        record.set_shard(shard);
-        record.timestamp = timestamp;
+        if !UNCONSTRAINED && MODE == TRACE {
+            self.emit_local_memory_access(
+                register as u32,
+                MemoryRecord::new(prev.shard, prev.timestamp, value),
+                MemoryRecord::new(shard, timestamp, value),
+                local_memory_access,
+            );
+        }
 
-        if !self.unconstrained {
-            self.local_memory_access
-                .entry(addr)
-                .and_modify(|e| {
-                    e.final_mem_access = *record;
-                })
-                .or_insert(MemoryLocalEvent {
-                    addr,
-                    initial_mem_access: prev_record,
-                    final_mem_access: *record,
-                });
+        MemoryReadRecord::new(value, shard, timestamp, prev.shard, prev.timestamp)
+    }
+
+    /// Write a register and create an access record. If it's the first time the register is
+    /// accessed, it was last accessed at `first`.
+    #[inline]
+    fn register_write<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        register: usize,
+        value: u32,
+        shard: u32,
+        timestamp: u32,
+        first: MemoryTimestamp,
+        local_memory_access: Option<&mut LocalMemAccessMap>,
+    ) -> MemoryWriteRecord {
+        let prev_value = std::mem::replace(&mut self.state.registers[register], value);
+        if MODE == SIMPLE {
+            return MemoryWriteRecord::new(value, shard, timestamp, prev_value, 0, 0);
         }
 
-        // Construct the memory read record.
+        let prev = self.state.register_timestamps[register]
+            .replace(MemoryTimestamp::new(shard, timestamp))
+            .unwrap_or(first);
+
+        if !UNCONSTRAINED && MODE == TRACE {
+            self.emit_local_memory_access(
+                register as u32,
+                MemoryRecord::new(prev.shard, prev.timestamp, prev_value),
+                MemoryRecord::new(shard, timestamp, value),
+                local_memory_access,
+            );
+        }
+
+        MemoryWriteRecord::new(
+            value,
+            shard,
+            timestamp,
+            prev_value,
+            prev.shard,
+            prev.timestamp,
+        )
+    }
+
+    /// Read from a register.
+    pub fn rr(&mut self, register: Register, position: MemoryAccessPosition) -> u32 {
+        dispatch_mode!(self.rr_in_mode(register, position))
+    }
+
+    /// Read from a register in the given mode.
+    fn rr_in_mode<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        register: Register,
+        position: MemoryAccessPosition,
+    ) -> u32 {
+        // When accesses aren't recorded, reading a register is a single load.
+        if MODE == SIMPLE {
+            return self.state.registers[register as usize];
+        }
+
+        let record = self.register_read::<MODE, UNCONSTRAINED>(
+            register as usize,
+            self.shard(),
+            self.timestamp(&position),
+            MemoryTimestamp::default(),
+            None,
+        );
+        if MODE == TRACE {
+            self.set_memory_access(position, record.into());
+        }
         record.value
     }
 
     /// Write to a register.
     pub fn rw(&mut self, register: Register, value: u32) {
+        dispatch_mode!(self.rw_in_mode(register, value));
+    }
+
+    /// Write to a register in the given mode.
+    fn rw_in_mode<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        register: Register,
+        value: u32,
+    ) {
         // The only time we are writing to a register is when it is in operand A.
         // Register %x0 should always be 0. See 2.6 Load and Store Instruction on
         // P.18 of the RISC-V spec. We always write 0 to %x0.
-        if register == Register::X0 {
-            self.mw_cpu(register as u32, 0, MemoryAccessPosition::A);
-        } else {
-            self.mw_cpu(register as u32, value, MemoryAccessPosition::A);
+        let value = if register == Register::X0 { 0 } else { value };
+        if MODE == SIMPLE {
+            self.state.registers[register as usize] = value;
+            return;
         }
-    }
 
-    /// Fetch the destination register and input operand values for an ALU instruction.
-    fn alu_rr(&mut self, instruction: &Instruction) -> (Register, u32, u32) {
-        if !instruction.imm_c {
-            let (rd, rs1, rs2) = instruction.r_type();
-            let c = self.rr(rs2, MemoryAccessPosition::C);
-            let b = self.rr(rs1, MemoryAccessPosition::B);
-            (rd, b, c)
-        } else if !instruction.imm_b && instruction.imm_c {
-            let (rd, rs1, imm) = instruction.i_type();
-            let (rd, b, c) = (rd, self.rr(rs1, MemoryAccessPosition::B), imm);
-            (rd, b, c)
-        } else {
-            assert!(instruction.imm_b && instruction.imm_c);
-            let (rd, b, c) = (
-                Register::from_u32(instruction.op_a),
-                instruction.op_b,
-                instruction.op_c,
-            );
-            (rd, b, c)
+        // If it's the first time accessing this register, it was last accessed at the start of
+        // the shard.
+        let shard = self.shard();
+        let record = self.register_write::<MODE, UNCONSTRAINED>(
+            register as usize,
+            value,
+            shard,
+            self.timestamp(&MemoryAccessPosition::A),
+            MemoryTimestamp::new(shard, 0),
+            None,
+        );
+        if MODE == TRACE {
+            self.set_memory_access(MemoryAccessPosition::A, record.into());
         }
     }
 
     /// Set the destination register with the result and emit an ALU event.
-    fn alu_rw(&mut self, _: &Instruction, rd: Register, a: u32, _: u32, _: u32, _: LookupId) {
-        self.rw(rd, a);
+    fn alu_rw<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        opcode: Opcode,
+        rd: Register,
+        a: u32,
+        b: u32,
+        c: u32,
+        lookup_id: LookupId,
+    ) {
+        self.rw_in_mode::<MODE, UNCONSTRAINED>(rd, a);
+        if MODE == TRACE {
+            self.emit_alu(self.state.clk, opcode, a, b, c, lookup_id);
+        }
     }
 
-    /// Fetch the input operand values for a load instruction.
-    fn load_rr(&mut self, instruction: &Instruction) -> (Register, u32, u32, u32, u32) {
-        let (rd, rs1, imm) = instruction.i_type();
-        let (b, c) = (self.rr(rs1, MemoryAccessPosition::B), imm);
-        let addr = b.wrapping_add(c);
-        let memory_value = self.mr_cpu(align(addr), MemoryAccessPosition::Memory);
-        (rd, b, c, addr, memory_value)
+    /// Emit a CPU event.
+    #[allow(clippy::too_many_arguments)]
+    fn emit_cpu(
+        &mut self,
+        shard: u32,
+        clk: u32,
+        pc: u32,
+        next_pc: u32,
+        instruction: Instruction,
+        a: u32,
+        b: u32,
+        c: u32,
+        memory_store_value: Option<u32>,
+        record: MemoryAccessRecord,
+        exit_code: u32,
+        lookup_id: LookupId,
+        syscall_lookup_id: LookupId,
+    ) {
+        self.record.cpu_events.push(CpuEvent {
+            shard,
+            clk,
+            pc,
+            next_pc,
+            instruction,
+            a,
+            a_record: record.a,
+            b,
+            b_record: record.b,
+            c,
+            c_record: record.c,
+            memory: memory_store_value,
+            memory_record: record.memory,
+            exit_code,
+            alu_lookup_id: lookup_id,
+            syscall_lookup_id,
+        });
     }
 
-    /// Fetch the input operand values for a store instruction.
-    fn store_rr(&mut self, instruction: &Instruction) -> (u32, u32, u32, u32, u32) {
-        let (rs1, rs2, imm) = instruction.s_type();
-        let c = imm;
-        let b = self.rr(rs2, MemoryAccessPosition::B);
-        let a = self.rr(rs1, MemoryAccessPosition::A);
-        let addr = b.wrapping_add(c);
-        let memory_value = self.word(align(addr));
-        (a, b, c, addr, memory_value)
+    /// Create a syscall event.
+    ///
+    /// The nonce of the event is assigned when it is added to its table.
+    pub(crate) fn syscall_event(
+        &self,
+        clk: u32,
+        syscall_id: u32,
+        arg1: u32,
+        arg2: u32,
+        lookup_id: LookupId,
+    ) -> SyscallEvent {
+        SyscallEvent {
+            shard: self.shard(),
+            clk,
+            lookup_id,
+            syscall_id,
+            arg1,
+            arg2,
+            nonce: 0,
+        }
     }
 
-    /// Fetch the input operand values for a branch instruction.
-    fn branch_rr(&mut self, instruction: &Instruction) -> (u32, u32, u32) {
-        let (rs1, rs2, imm) = instruction.b_type();
-        let c = imm;
-        let b = self.rr(rs2, MemoryAccessPosition::B);
-        let a = self.rr(rs1, MemoryAccessPosition::A);
-        (a, b, c)
+    /// Emit a syscall event.
+    fn emit_syscall(
+        &mut self,
+        clk: u32,
+        syscall_id: u32,
+        arg1: u32,
+        arg2: u32,
+        lookup_id: LookupId,
+    ) {
+        let mut syscall_event = self.syscall_event(clk, syscall_id, arg1, arg2, lookup_id);
+        syscall_event.nonce = self.record.syscall_events.len() as u32;
+        self.record.syscall_events.push(syscall_event);
     }
 
-    /// Fetch the instruction at the current program counter.
+    /// Get the `index`-th lookup id of the cycle at `clk` in the current shard.
+    ///
+    /// Index 0 is the ALU lookup of the cycle, index 1 the syscall lookup and indices 2 to 7 the
+    /// sub-lookups of its ALU event.
     #[inline]
-    fn fetch(&self) -> Instruction {
-        let idx = ((self.state.pc - self.program.pc_base) / 4) as usize;
-        self.program.instructions[idx]
+    fn lookup_id(&self, clk: u32, index: u32) -> LookupId {
+        self.lookup_id_generator.lookup_id(self.shard(), clk, index)
     }
 
-    /// Execute the given instruction over the current state of the runtime.
-    #[allow(clippy::too_many_lines)]
-    fn execute_instruction(&mut self, instruction: &Instruction) -> Result<(), ExecutionError> {
-        let mut next_pc = self.state.pc.wrapping_add(4);
-
-        let rd: Register;
-        let (a, b, c): (u32, u32, u32);
-        let (addr, memory_read_value): (u32, u32);
-
-        let lookup_id = LookupId::default();
-        let syscall_lookup_id = LookupId::default();
-
-        match instruction.opcode {
-            // Arithmetic instructions.
-            Opcode::ADD => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = b.wrapping_add(c);
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::SUB => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = b.wrapping_sub(c);
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::XOR => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = b ^ c;
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::OR => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = b | c;
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::AND => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = b & c;
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::SLL => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = b.wrapping_shl(c);
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::SRL => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = b.wrapping_shr(c);
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::SRA => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = (b as i32).wrapping_shr(c) as u32;
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::SLT => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = if (b as i32) < (c as i32) { 1 } else { 0 };
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::SLTU => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = if b < c { 1 } else { 0 };
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
+    /// Emit an ALU event.
+    fn emit_alu(&mut self, clk: u32, opcode: Opcode, a: u32, b: u32, c: u32, lookup_id: LookupId) {
+        let event = AluEvent {
+            lookup_id,
+            shard: self.shard(),
+            clk,
+            opcode,
+            a,
+            b,
+            c,
+            sub_lookups: std::array::from_fn(|i| self.lookup_id(clk, 2 + i as u32)),
+        };
+        self.record.add_alu_event(event);
+    }
 
-            // Load instructions.
+    /// Execute a load from `addr`, returning the loaded value and the word it was loaded from.
+    pub(crate) fn execute_load<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        opcode: Opcode,
+        addr: u32,
+    ) -> Result<(u32, u32), ExecutionError> {
+        let memory_read_value =
+            self.mr_cpu_in_mode::<MODE, UNCONSTRAINED>(align(addr), MemoryAccessPosition::Memory);
+        let value = match opcode {
             Opcode::LB => {
-                (rd, _, _, addr, memory_read_value) = self.load_rr(instruction);
-                let value = (memory_read_value).to_le_bytes()[(addr % 4) as usize];
-                a = ((value as i8) as i32) as u32;
-                self.rw(rd, a);
+                let value = memory_read_value.to_le_bytes()[(addr % 4) as usize];
+                ((value as i8) as i32) as u32
             }
             Opcode::LH => {
-                (rd, _, _, addr, memory_read_value) = self.load_rr(instruction);
-                if addr % 2 != 0 {
+                if !addr.is_multiple_of(2) {
                     return Err(ExecutionError::InvalidMemoryAccess(Opcode::LH, addr));
                 }
                 let value = match (addr >> 1) % 2 {
@@ -744,26 +1179,20 @@ impl<'a> Executor<'a> {
                     1 => (memory_read_value & 0xFFFF_0000) >> 16,
                     _ => unreachable!(),
                 };
-                a = ((value as i16) as i32) as u32;
-                self.rw(rd, a);
+                ((value as i16) as i32) as u32
             }
             Opcode::LW => {
-                (rd, _, _, addr, memory_read_value) = self.load_rr(instruction);
-                if addr % 4 != 0 {
+                if !addr.is_multiple_of(4) {
                     return Err(ExecutionError::InvalidMemoryAccess(Opcode::LW, addr));
                 }
-                a = memory_read_value;
-                self.rw(rd, a);
+                memory_read_value
             }
             Opcode::LBU => {
-                (rd, _, _, addr, memory_read_value) = self.load_rr(instruction);
-                let value = (memory_read_value).to_le_bytes()[(addr % 4) as usize];
-                a = value as u32;
-                self.rw(rd, a);
+                let value = memory_read_value.to_le_bytes()[(addr % 4) as usize];
+                value as u32
             }
             Opcode::LHU => {
-                (rd, _, _, addr, memory_read_value) = self.load_rr(instruction);
-                if addr % 2 != 0 {
+                if !addr.is_multiple_of(2) {
                     return Err(ExecutionError::InvalidMemoryAccess(Opcode::LHU, addr));
                 }
                 let value = match (addr >> 1) % 2 {
@@ -771,243 +1200,390 @@ impl<'a> Executor<'a> {
                     1 => (memory_read_value & 0xFFFF_0000) >> 16,
                     _ => unreachable!(),
                 };
-                a = (value as u16) as u32;
-                self.rw(rd, a);
+                (value as u16) as u32
             }
+            _ => unreachable!(),
+        };
+        Ok((value, memory_read_value))
+    }
 
-            // Store instructions.
-            Opcode::SB => {
-                (a, _, _, addr, memory_read_value) = self.store_rr(instruction);
-                let value = match addr % 4 {
-                    0 => (a & 0x0000_00FF) + (memory_read_value & 0xFFFF_FF00),
-                    1 => ((a & 0x0000_00FF) << 8) + (memory_read_value & 0xFFFF_00FF),
-                    2 => ((a & 0x0000_00FF) << 16) + (memory_read_value & 0xFF00_FFFF),
-                    3 => ((a & 0x0000_00FF) << 24) + (memory_read_value & 0x00FF_FFFF),
-                    _ => unreachable!(),
-                };
-                self.mw_cpu(align(addr), value, MemoryAccessPosition::Memory);
-            }
+    /// Execute a store of `a` to `addr`, returning the word that was written.
+    pub(crate) fn execute_store<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        opcode: Opcode,
+        addr: u32,
+        a: u32,
+    ) -> Result<u32, ExecutionError> {
+        let memory_read_value = self.word_in_mode::<MODE, UNCONSTRAINED>(align(addr));
+        let value = match opcode {
+            Opcode::SB => match addr % 4 {
+                0 => (a & 0x0000_00FF) + (memory_read_value & 0xFFFF_FF00),
+                1 => ((a & 0x0000_00FF) << 8) + (memory_read_value & 0xFFFF_00FF),
+                2 => ((a & 0x0000_00FF) << 16) + (memory_read_value & 0xFF00_FFFF),
+                3 => ((a & 0x0000_00FF) << 24) + (memory_read_value & 0x00FF_FFFF),
+                _ => unreachable!(),
+            },
             Opcode::SH => {
-                (a, _, _, addr, memory_read_value) = self.store_rr(instruction);
-                if addr % 2 != 0 {
+                if !addr.is_multiple_of(2) {
                     return Err(ExecutionError::InvalidMemoryAccess(Opcode::SH, addr));
                 }
-                let value = match (addr >> 1) % 2 {
+                match (addr >> 1) % 2 {
                     0 => (a & 0x0000_FFFF) + (memory_read_value & 0xFFFF_0000),
                     1 => ((a & 0x0000_FFFF) << 16) + (memory_read_value & 0x0000_FFFF),
                     _ => unreachable!(),
-                };
-                self.mw_cpu(align(addr), value, MemoryAccessPosition::Memory);
+                }
             }
             Opcode::SW => {
-                (a, _, _, addr, _) = self.store_rr(instruction);
-                if addr % 4 != 0 {
+                if !addr.is_multiple_of(4) {
                     return Err(ExecutionError::InvalidMemoryAccess(Opcode::SW, addr));
                 }
-                let value = a;
-                self.mw_cpu(align(addr), value, MemoryAccessPosition::Memory);
+                a
             }
+            _ => unreachable!(),
+        };
+        self.mw_cpu_in_mode::<MODE, UNCONSTRAINED>(
+            align(addr),
+            value,
+            MemoryAccessPosition::Memory,
+        );
+        Ok(value)
+    }
 
-            // B-type instructions.
-            Opcode::BEQ => {
-                (a, b, c) = self.branch_rr(instruction);
-                if a == b {
-                    next_pc = self.state.pc.wrapping_add(c);
-                }
+    /// Execute the instruction at the given index of the program over the current state of the
+    /// runtime.
+    #[allow(clippy::too_many_lines)]
+    pub(crate) fn execute_instruction<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        index: usize,
+    ) -> Result<(), ExecutionError> {
+        let mut op = self.ops[index];
+        if let Op::Fused(_) = op {
+            // Fused instructions are executed one at a time outside of simple mode, and at the end
+            // of a block that was cut short.
+            op = Op::lower(&self.program.instructions[index]);
+        }
+        let mut next_pc = self.state.pc.wrapping_add(4);
+
+        let (a, b, c): (u32, u32, u32);
+        let mut memory_store_value: Option<u32> = None;
+        let mut exit_code = 0u32;
+
+        // Capture the state of the cycle before it is modified by the instruction.
+        let shard = self.shard();
+        let clk = self.state.clk;
+        let pc = self.state.pc;
+
+        if self.print_report && !UNCONSTRAINED {
+            self.report.opcode_counts[self.program.instructions[index].opcode] += 1;
+        }
+
+        let (lookup_id, syscall_lookup_id) = if MODE == TRACE {
+            self.memory_accesses = MemoryAccessRecord::default();
+            (self.lookup_id(clk, 0), self.lookup_id(clk, 1))
+        } else {
+            (LookupId::default(), LookupId::default())
+        };
+
+        match op {
+            // Arithmetic instructions.
+            Op::AluRegisters {
+                opcode,
+                rd,
+                rs1,
+                rs2,
+            } => {
+                c = self.rr_in_mode::<MODE, UNCONSTRAINED>(rs2, MemoryAccessPosition::C);
+                b = self.rr_in_mode::<MODE, UNCONSTRAINED>(rs1, MemoryAccessPosition::B);
+                a = alu(opcode, b, c);
+                self.alu_rw::<MODE, UNCONSTRAINED>(opcode, rd, a, b, c, lookup_id);
             }
-            Opcode::BNE => {
-                (a, b, c) = self.branch_rr(instruction);
-                if a != b {
-                    next_pc = self.state.pc.wrapping_add(c);
-                }
+            Op::AluImmediate {
+                opcode,
+                rd,
+                rs1,
+                imm,
+            } => {
+                (b, c) = (
+                    self.rr_in_mode::<MODE, UNCONSTRAINED>(rs1, MemoryAccessPosition::B),
+                    imm,
+                );
+                a = alu(opcode, b, c);
+                self.alu_rw::<MODE, UNCONSTRAINED>(opcode, rd, a, b, c, lookup_id);
             }
-            Opcode::BLT => {
-                (a, b, c) = self.branch_rr(instruction);
-                if (a as i32) < (b as i32) {
-                    next_pc = self.state.pc.wrapping_add(c);
-                }
+            Op::AluImmediates {
+                opcode,
+                rd,
+                b: imm_b,
+                c: imm_c,
+            } => {
+                (b, c) = (imm_b, imm_c);
+                a = alu(opcode, b, c);
+                self.alu_rw::<MODE, UNCONSTRAINED>(opcode, rd, a, b, c, lookup_id);
             }
-            Opcode::BGE => {
-                (a, b, c) = self.branch_rr(instruction);
-                if (a as i32) >= (b as i32) {
-                    next_pc = self.state.pc.wrapping_add(c);
-                }
+
+            // Arithmetic instructions whose result is discarded, which are only executed when
+            // their accesses have to be traced or checkpointed.
+            Op::AluRegistersX0 { .. } | Op::AluImmediateX0 { .. } if MODE == SIMPLE => {
+                (a, b, c) = (0, 0, 0);
             }
-            Opcode::BLTU => {
-                (a, b, c) = self.branch_rr(instruction);
-                if a < b {
-                    next_pc = self.state.pc.wrapping_add(c);
-                }
+            Op::AluRegistersX0 { opcode, rs1, rs2 } => {
+                c = self.rr_in_mode::<MODE, UNCONSTRAINED>(rs2, MemoryAccessPosition::C);
+                b = self.rr_in_mode::<MODE, UNCONSTRAINED>(rs1, MemoryAccessPosition::B);
+                a = alu(opcode, b, c);
+                self.alu_rw::<MODE, UNCONSTRAINED>(opcode, Register::X0, a, b, c, lookup_id);
+            }
+            Op::AluImmediateX0 { opcode, rs1, imm } => {
+                (b, c) = (
+                    self.rr_in_mode::<MODE, UNCONSTRAINED>(rs1, MemoryAccessPosition::B),
+                    imm,
+                );
+                a = alu(opcode, b, c);
+                self.alu_rw::<MODE, UNCONSTRAINED>(opcode, Register::X0, a, b, c, lookup_id);
+            }
+
+            // Load instructions.
+            Op::Load {
+                opcode,
+                rd,
+                rs1,
+                imm,
+            } => {
+                (b, c) = (
+                    self.rr_in_mode::<MODE, UNCONSTRAINED>(rs1, MemoryAccessPosition::B),
+                    imm,
+                );
+                let memory_read_value;
+                (a, memory_read_value) =
+                    self.execute_load::<MODE, UNCONSTRAINED>(opcode, b.wrapping_add(c))?;
+                memory_store_value = Some(memory_read_value);
+                self.rw_in_mode::<MODE, UNCONSTRAINED>(rd, a);
+            }
+
+            // Store instructions.
+            Op::Store {
+                opcode,
+                rs1,
+                rs2,
+                imm,
+            } => {
+                c = imm;
+                b = self.rr_in_mode::<MODE, UNCONSTRAINED>(rs2, MemoryAccessPosition::B);
+                a = self.rr_in_mode::<MODE, UNCONSTRAINED>(rs1, MemoryAccessPosition::A);
+                memory_store_value = Some(self.execute_store::<MODE, UNCONSTRAINED>(
+                    opcode,
+                    b.wrapping_add(c),
+                    a,
+                )?);
             }
-            Opcode::BGEU => {
-                (a, b, c) = self.branch_rr(instruction);
-                if a >= b {
+
+            // B-type instructions.
+            Op::Branch {
+                opcode,
+                rs1,
+                rs2,
+                imm,
+            } => {
+                c = imm;
+                b = self.rr_in_mode::<MODE, UNCONSTRAINED>(rs2, MemoryAccessPosition::B);
+                a = self.rr_in_mode::<MODE, UNCONSTRAINED>(rs1, MemoryAccessPosition::A);
+                let taken = match opcode {
+                    Opcode::BEQ => a == b,
+                    Opcode::BNE => a != b,
+                    Opcode::BLT => (a as i32) < (b as i32),
+                    Opcode::BGE => (a as i32) >= (b as i32),
+                    Opcode::BLTU => a < b,
+                    Opcode::BGEU => a >= b,
+                    _ => unreachable!(),
+                };
+                if taken {
                     next_pc = self.state.pc.wrapping_add(c);
                 }
             }
 
             // Jump instructions.
-            Opcode::JAL => {
-                let (rd, imm) = instruction.j_type();
+            Op::Jal { rd, imm } => {
+                (b, c) = (imm, 0);
+                a = self.state.pc + 4;
+                self.rw_in_mode::<MODE, UNCONSTRAINED>(rd, a);
+                next_pc = self.state.pc.wrapping_add(imm);
+            }
+            Op::JalX0 { imm } => {
+                (b, c) = (imm, 0);
                 a = self.state.pc + 4;
-                self.rw(rd, a);
+                if MODE != SIMPLE {
+                    self.rw_in_mode::<MODE, UNCONSTRAINED>(Register::X0, a);
+                }
                 next_pc = self.state.pc.wrapping_add(imm);
             }
-            Opcode::JALR => {
-                let (rd, rs1, imm) = instruction.i_type();
-                (b, c) = (self.rr(rs1, MemoryAccessPosition::B), imm);
+            Op::Jalr { rd, rs1, imm } => {
+                (b, c) = (
+                    self.rr_in_mode::<MODE, UNCONSTRAINED>(rs1, MemoryAccessPosition::B),
+                    imm,
+                );
+                a = self.state.pc + 4;
+                self.rw_in_mode::<MODE, UNCONSTRAINED>(rd, a);
+                next_pc = b.wrapping_add(c);
+            }
+            Op::JalrX0 { rs1, imm } => {
+                (b, c) = (
+                    self.rr_in_mode::<MODE, UNCONSTRAINED>(rs1, MemoryAccessPosition::B),
+                    imm,
+                );
                 a = self.state.pc + 4;
-                self.rw(rd, a);
+                if MODE != SIMPLE {
+                    self.rw_in_mode::<MODE, UNCONSTRAINED>(Register::X0, a);
+                }
                 next_pc = b.wrapping_add(c);
             }
 
             // Upper immediate instructions.
-            Opcode::AUIPC => {
-                let (rd, imm) = instruction.u_type();
-                (b, _) = (imm, imm);
+            Op::Auipc { rd, imm } => {
+                (b, c) = (imm, imm);
                 a = self.state.pc.wrapping_add(b);
-                self.rw(rd, a);
+                self.rw_in_mode::<MODE, UNCONSTRAINED>(rd, a);
             }
 
             // System instructions.
-            Opcode::ECALL => {
+            Op::Ecall => {
                 // We peek at register x5 to get the syscall id. The reason we don't `self.rr` this
                 // register is that we write to it later.
                 let t0 = Register::X5;
-                let syscall_id = self.register(t0);
-                c = self.rr(Register::X11, MemoryAccessPosition::C);
-                b = self.rr(Register::X10, MemoryAccessPosition::B);
-                let syscall = SyscallCode::from_u32(syscall_id);
-
-                // if self.print_report && !self.unconstrained {
-                //     // self.report.syscall_counts[syscall] += 1;
-                // }
-
-                // `hint_slice` is allowed in unconstrained mode since it is used to write the hint.
-                // Other syscalls are not allowed because they can lead to non-deterministic
-                // behavior, especially since many syscalls modify memory in place,
-                // which is not permitted in unconstrained mode. This will result in
-                // non-zero memory interactions when generating a proof.
-
-                if self.unconstrained
-                    && (syscall != SyscallCode::EXIT_UNCONSTRAINED && syscall != SyscallCode::WRITE)
-                {
-                    return Err(ExecutionError::InvalidSyscallUsage(syscall_id as u64));
-                }
-
-                // Update the syscall counts.
-                let syscall_for_count = syscall.count_map();
-                let syscall_count = self
-                    .state
-                    .syscall_counts
-                    .entry(syscall_for_count)
-                    .or_insert(0);
-                *syscall_count += 1;
-
-                let syscall_impl = self.get_syscall(syscall).cloned();
-                if syscall.should_send() != 0 {
-                    // self.emit_syscall(clk, syscall.syscall_id(), b, c, syscall_lookup_id);
-                }
-                let mut precompile_rt = SyscallContext::new(self);
-                precompile_rt.syscall_lookup_id = syscall_lookup_id;
-                let (precompile_next_pc, precompile_cycles, _) =
-                    if let Some(syscall_impl) = syscall_impl {
-                        // Executing a syscall optionally returns a value to write to the t0
-                        // register. If it returns None, we just keep the
-                        // syscall_id in t0.
-                        let res = syscall_impl.execute(&mut precompile_rt, syscall, b, c);
-                        if let Some(val) = res {
-                            a = val;
-                        } else {
-                            a = syscall_id;
+                let syscall_id = self.word_in_mode::<MODE, UNCONSTRAINED>(t0 as u32);
+                c = self.rr_in_mode::<MODE, UNCONSTRAINED>(Register::X11, MemoryAccessPosition::C);
+                b = self.rr_in_mode::<MODE, UNCONSTRAINED>(Register::X10, MemoryAccessPosition::B);
+                let custom_syscall = self.custom_syscall_map.get(&syscall_id).cloned();
+                let (precompile_next_pc, precompile_cycles, returned_exit_code) =
+                    if let Some(syscall_impl) = custom_syscall {
+                        // Syscalls that `SyscallCode` doesn't list are not counted, and they are
+                        // not allowed in unconstrained mode either.
+                        if UNCONSTRAINED {
+                            return Err(ExecutionError::InvalidSyscallUsage(syscall_id as u64));
                         }
-
-                        // If the syscall is `HALT` and the exit code is non-zero, return an error.
-                        if syscall == SyscallCode::HALT && precompile_rt.exit_code != 0 {
-                            return Err(ExecutionError::HaltWithNonZeroExitCode(
-                                precompile_rt.exit_code,
-                            ));
+                        let [id, should_send, ..] = syscall_id.to_le_bytes();
+                        if should_send != 0 && MODE == TRACE {
+                            self.emit_syscall(clk, id.into(), b, c, syscall_lookup_id);
                         }
-
+                        let mut precompile_rt = SyscallContext::new(self);
+                        precompile_rt.syscall_lookup_id = syscall_lookup_id;
+                        a = syscall_impl
+                            .execute(&mut precompile_rt, syscall_id, b, c)?
+                            .unwrap_or(syscall_id);
                         (
                             precompile_rt.next_pc,
                             syscall_impl.num_extra_cycles(),
                             precompile_rt.exit_code,
                         )
                     } else {
-                        return Err(ExecutionError::UnsupportedSyscall(syscall_id));
+                        let Some(syscall) = SyscallCode::from_u32(syscall_id) else {
+                            return Err(ExecutionError::UnsupportedSyscall(syscall_id));
+                        };
+
+                        if self.print_report && !UNCONSTRAINED {
+                            self.report.syscall_counts[syscall] += 1;
+                        }
+
+                        // `hint_slice` is allowed in unconstrained mode since it is used to write
+                        // the hint. Other syscalls are not allowed because they can lead to
+                        // non-deterministic behavior, especially since many syscalls modify memory
+                        // in place, which is not permitted in unconstrained mode. This will result
+                        // in non-zero memory interactions when generating a proof.
+
+                        if UNCONSTRAINED
+                            && (syscall != SyscallCode::EXIT_UNCONSTRAINED
+                                && syscall != SyscallCode::WRITE)
+                        {
+                            return Err(ExecutionError::InvalidSyscallUsage(syscall_id as u64));
+                        }
+
+                        // Update the syscall counts.
+                        let syscall_for_count = syscall.count_map();
+                        let syscall_count = self
+                            .state
+                            .syscall_counts
+                            .entry(syscall_for_count)
+                            .or_insert(0);
+                        *syscall_count += 1;
+
+                        let syscall_impl = self.get_syscall(syscall).cloned();
+                        if syscall.should_send() != 0 && MODE == TRACE {
+                            self.emit_syscall(clk, syscall.syscall_id(), b, c, syscall_lookup_id);
+                        }
+                        let mut precompile_rt = SyscallContext::new(self);
+                        precompile_rt.syscall_lookup_id = syscall_lookup_id;
+                        if let Some(syscall_impl) = syscall_impl {
+                            // Executing a syscall optionally returns a value to write to the t0
+                            // register. If it returns None, we just keep the
+                            // syscall_id in t0.
+                            let res = syscall_impl.execute(&mut precompile_rt, syscall, b, c)?;
+                            if let Some(val) = res {
+                                a = val;
+                            } else {
+                                a = syscall_id;
+                            }
+
+                            // If the syscall is `HALT` and the exit code is non-zero, return an
+                            // error.
+                            if syscall == SyscallCode::HALT && precompile_rt.exit_code != 0 {
+                                return Err(ExecutionError::HaltWithNonZeroExitCode(
+                                    precompile_rt.exit_code,
+                                ));
+                            }
+
+                            (
+                                precompile_rt.next_pc,
+                                syscall_impl.num_extra_cycles(),
+                                precompile_rt.exit_code,
+                            )
+                        } else {
+                            return Err(ExecutionError::UnsupportedSyscall(syscall_id));
+                        }
                     };
 
-                self.rw(t0, a);
+                // Entering or exiting an unconstrained block switches the mode in the middle of
+                // the cycle, so the rest of it runs in the new mode.
+                if self.unconstrained == UNCONSTRAINED {
+                    self.rw_in_mode::<MODE, UNCONSTRAINED>(t0, a);
+                } else {
+                    self.rw(t0, a);
+                }
                 next_pc = precompile_next_pc;
                 self.state.clk += precompile_cycles;
+                exit_code = returned_exit_code;
             }
-            Opcode::EBREAK => {
+            Op::Ebreak => {
                 return Err(ExecutionError::Breakpoint());
             }
 
-            // Multiply instructions.
-            Opcode::MUL => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = b.wrapping_mul(c);
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::MULH => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = (((b as i32) as i64).wrapping_mul((c as i32) as i64) >> 32) as u32;
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::MULHU => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = ((b as u64).wrapping_mul(c as u64) >> 32) as u32;
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::MULHSU => {
-                (rd, b, c) = self.alu_rr(instruction);
-                a = (((b as i32) as i64).wrapping_mul(c as i64) >> 32) as u32;
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::DIV => {
-                (rd, b, c) = self.alu_rr(instruction);
-                if c == 0 {
-                    a = u32::MAX;
-                } else {
-                    a = (b as i32).wrapping_div(c as i32) as u32;
-                }
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::DIVU => {
-                (rd, b, c) = self.alu_rr(instruction);
-                if c == 0 {
-                    a = u32::MAX;
-                } else {
-                    a = b.wrapping_div(c);
-                }
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::REM => {
-                (rd, b, c) = self.alu_rr(instruction);
-                if c == 0 {
-                    a = b;
-                } else {
-                    a = (b as i32).wrapping_rem(c as i32) as u32;
-                }
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-            Opcode::REMU => {
-                (rd, b, c) = self.alu_rr(instruction);
-                if c == 0 {
-                    a = b;
-                } else {
-                    a = b.wrapping_rem(c);
-                }
-                self.alu_rw(instruction, rd, a, b, c, lookup_id);
-            }
-
             // See https://github.com/riscv-non-isa/riscv-asm-manual/blob/master/riscv-asm.md#instruction-aliases
-            Opcode::UNIMP => {
+            Op::Unimp => {
                 return Err(ExecutionError::Unimplemented());
             }
+
+            Op::Fused(_) => unreachable!(),
+        }
+
+        // Emit the CPU event for this cycle.
+        let trace = if self.unconstrained == UNCONSTRAINED {
+            MODE == TRACE
+        } else {
+            self.executor_mode == ExecutorMode::Trace
+        };
+        if trace {
+            self.emit_cpu(
+                shard,
+                clk,
+                pc,
+                next_pc,
+                self.program.instructions[index],
+                a,
+                b,
+                c,
+                memory_store_value,
+                self.memory_accesses,
+                exit_code,
+                lookup_id,
+                syscall_lookup_id,
+            );
         }
 
         // Update the program counter.
@@ -1019,22 +1595,141 @@ impl<'a> Executor<'a> {
         Ok(())
     }
 
-    /// Executes one cycle of the program, returning whether the program has finished.
+    /// Execute the pair of instructions fused into the op at the given index in simple mode.
+    fn execute_fused<const UNCONSTRAINED: bool>(
+        &mut self,
+        index: usize,
+        fused: Fused,
+    ) -> Result<(), ExecutionError> {
+        if self.print_report && !UNCONSTRAINED {
+            for instruction in &self.program.instructions[index..index + 2] {
+                self.report.opcode_counts[instruction.opcode] += 1;
+            }
+        }
+
+        let pc = self.state.pc;
+        let mut next_pc = pc.wrapping_add(8);
+        match fused {
+            Fused::Constants { rd1, a1, rd2, a2 } => {
+                self.rw_in_mode::<SIMPLE, UNCONSTRAINED>(rd1, a1);
+                self.rw_in_mode::<SIMPLE, UNCONSTRAINED>(rd2, a2);
+            }
+            Fused::Call {
+                rd,
+                a,
+                link,
+                target,
+            } => {
+                self.rw_in_mode::<SIMPLE, UNCONSTRAINED>(rd, a);
+                self.rw_in_mode::<SIMPLE, UNCONSTRAINED>(link, next_pc);
+                next_pc = target;
+            }
+            Fused::AuipcLoad {
+                rd,
+                a,
+                opcode,
+                load_rd,
+                addr,
+            } => {
+                self.rw_in_mode::<SIMPLE, UNCONSTRAINED>(rd, a);
+                match self.execute_load::<SIMPLE, UNCONSTRAINED>(opcode, addr) {
+                    Ok((value, _)) => self.rw_in_mode::<SIMPLE, UNCONSTRAINED>(load_rd, value),
+                    Err(error) => {
+                        // The `auipc` completed, but the load didn't.
+                        self.state.pc = pc.wrapping_add(4);
+                        self.state.clk += 4;
+                        self.state.global_clk += 1;
+                        return Err(error);
+                    }
+                }
+            }
+            Fused::CompareBranch {
+                opcode,
+                rd,
+                rs1,
+                rs2,
+                taken_if_set,
+                target,
+            } => {
+                let c = self.rr_in_mode::<SIMPLE, UNCONSTRAINED>(rs2, MemoryAccessPosition::C);
+                let b = self.rr_in_mode::<SIMPLE, UNCONSTRAINED>(rs1, MemoryAccessPosition::B);
+                let a = alu(opcode, b, c);
+                self.rw_in_mode::<SIMPLE, UNCONSTRAINED>(rd, a);
+                if (a != 0) == taken_if_set {
+                    next_pc = target;
+                }
+            }
+        }
+
+        self.state.pc = next_pc;
+        self.state.clk += 8;
+        Ok(())
+    }
+
+    /// Executes the basic block at the current program counter, returning whether the program
+    /// has finished.
+    ///
+    /// The block is cut short if the shard or the cycle limit is reached before its end, so that
+    /// the checks after it are equivalent to checking after every cycle.
     #[inline]
-    #[allow(clippy::too_many_lines)]
-    fn execute_cycle(&mut self) -> Result<bool, ExecutionError> {
-        // Fetch the instruction at the current program counter.
-        let instruction = self.fetch();
+    fn execute_block<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+    ) -> Result<bool, ExecutionError> {
+        let start = ((self.state.pc - self.program.pc_base) / 4) as usize;
+        let mut end = self.basic_block_ends[start] as usize;
 
-        // Log the current state of the runtime.
-        #[cfg(debug_assertions)]
-        self.log(&instruction);
+        // Every instruction but the last of a block takes exactly 4 clk, so the shard ends after
+        // the first one that leaves no room for another.
+        if !self.unconstrained {
+            let room = self
+                .shard_size
+                .saturating_sub(self.max_syscall_cycles + self.state.clk);
+            end = end.min(start + room.div_ceil(4).max(1) as usize);
+        }
+        if let Some(max_cycles) = self.max_cycles {
+            let remaining = max_cycles.saturating_sub(self.state.global_clk).max(1);
+            end = end.min(start.saturating_add(remaining.try_into().unwrap_or(usize::MAX)));
+        }
 
-        // Execute the instruction.
-        self.execute_instruction(&instruction)?;
+        // In simple mode, run what can be run of the block, and possibly of the blocks after it,
+        // as compiled code.
+        let start = if MODE == SIMPLE && !UNCONSTRAINED && self.trace_buf.is_none() {
+            self.execute_compiled(start, end)?
+        } else {
+            start
+        };
 
-        // Increment the clock.
-        self.state.global_clk += 1;
+        let mut index = start;
+        while index < end {
+            // Log the current state of the runtime.
+            #[cfg(debug_assertions)]
+            self.log();
+
+            // In simple mode, execute a pair of fused instructions at once if it fits in the block.
+            if let Op::Fused(fused) = self.ops[index] {
+                if MODE == SIMPLE && index + 1 < end && self.trace_buf.is_none() {
+                    self.execute_fused::<UNCONSTRAINED>(index, fused)?;
+                    self.state.global_clk += 2;
+                    index += 2;
+                    continue;
+                }
+            }
+
+            // Execute the instruction.
+            self.execute_instruction::<MODE, UNCONSTRAINED>(index)?;
+
+            // Increment the clock.
+            self.state.global_clk += 1;
+            index += 1;
+        }
+
+        // If there's not enough room left in the shard for another instruction, move on to the
+        // next shard. Unconstrained blocks are never split since their clock is reset on exit.
+        if !self.unconstrained && self.max_syscall_cycles + self.state.clk >= self.shard_size {
+            self.state.current_shard += 1;
+            self.state.clk = 0;
+            self.bump_record();
+        }
 
         // If the cycle limit is exceeded, return an error.
         if let Some(max_cycles) = self.max_cycles {
@@ -1056,66 +1751,378 @@ impl<'a> Executor<'a> {
         Ok(done)
     }
 
+    /// Execute the compiled code of the block starting at the instruction `start`, if there is
+    /// any, returning the index of the next instruction of the block to interpret.
+    ///
+    /// Blocks compiled ahead of time are preferred to the JIT.
+    fn execute_compiled(&mut self, start: usize, end: usize) -> Result<usize, ExecutionError> {
+        if let Some(blocks) = self.aot_blocks {
+            return self.execute_compiled_blocks(
+                start,
+                end,
+                |executor, start| {
+                    let block = blocks(executor.program.pc_base + 4 * start as u32)?;
+                    Some((block.len as usize, block))
+                },
+                Self::execute_aot_block,
+            );
+        }
+        #[cfg(feature = "jit")]
+        let start = self.execute_compiled_blocks(
+            start,
+            end,
+            |executor, start| executor.jit_block(start).map(|block| (block.len, block)),
+            Self::execute_jit_block,
+        )?;
+        Ok(start)
+    }
+
+    /// Execute the compiled block starting at the instruction `start`, returning the index of the
+    /// next instruction of the block to interpret.
+    ///
+    /// `block` gets the compiled block starting at an instruction along with its number of
+    /// instructions, and `execute` executes it. Nothing is executed if the compiled block would go
+    /// past `end`. If the whole block is compiled, the blocks that follow it are executed too, as
+    /// long as they are compiled and the checks after each block would pass, and `end` is
+    /// returned.
+    fn execute_compiled_blocks<B>(
+        &mut self,
+        start: usize,
+        end: usize,
+        block: impl Fn(&mut Self, usize) -> Option<(usize, B)>,
+        execute: impl Fn(&mut Self, usize, B) -> Result<(), ExecutionError>,
+    ) -> Result<usize, ExecutionError> {
+        let Some((len, compiled)) = block(self, start) else {
+            return Ok(start);
+        };
+        if start + len > end {
+            return Ok(start);
+        }
+        execute(self, start, compiled)?;
+        if start + len < self.basic_block_ends[start] as usize {
+            return Ok(start + len);
+        }
+
+        // An `ECALL` may enter an unconstrained block, which is never compiled.
+        loop {
+            let offset = self.state.pc.wrapping_sub(self.program.pc_base);
+            if self.state.pc == 0 || offset >= (self.ops.len() * 4) as u32 || self.unconstrained {
+                break;
+            }
+            let start = (offset / 4) as usize;
+            let Some((len, compiled)) = block(self, start) else {
+                break;
+            };
+            if start + len < self.basic_block_ends[start] as usize || !self.fits(len) {
+                break;
+            }
+            execute(self, start, compiled)?;
+        }
+        Ok(end)
+    }
+
+    /// Whether `len` instructions can be executed without reaching the end of the shard or the
+    /// cycle limit, in which case [`Executor::execute_block`] wouldn't cut them short.
+    fn fits(&self, len: usize) -> bool {
+        let room = self
+            .shard_size
+            .saturating_sub(self.max_syscall_cycles + self.state.clk);
+        let remaining = self.max_cycles.map_or(u64::MAX, |max_cycles| {
+            max_cycles.saturating_sub(self.state.global_clk)
+        });
+        room > 0 && len <= room.div_ceil(4) as usize && len as u64 <= remaining
+    }
+
+    /// Account for the `executed` instructions run by compiled code from the instruction `start`,
+    /// after which the program counter is `next_pc`. If the instruction at `next_pc` `faulted`, it
+    /// is counted in the report, but not executed.
+    pub(crate) fn finish_compiled_block(
+        &mut self,
+        start: usize,
+        executed: usize,
+        faulted: bool,
+        next_pc: u32,
+    ) {
+        if self.print_report {
+            let counted = executed + usize::from(faulted);
+            for instruction in &self.program.instructions[start..start + counted] {
+                self.report.opcode_counts[instruction.opcode] += 1;
+            }
+        }
+        self.state.pc = next_pc;
+        self.state.clk += 4 * executed as u32;
+        self.state.global_clk += executed as u64;
+    }
+
     fn initialize(&mut self) {
         self.state.clk = 0;
 
         tracing::debug!("loading memory image");
         for (&addr, value) in &self.program.memory_image {
-            self.state
-                .memory
-                .insert(addr, MemoryRecord::new(0, 0, *value));
+            self.state.memory.insert(addr, *value);
         }
     }
 
-    /// Executes the program without tracing and without emitting events.
+    /// Executes the program without tracing and without emitting events, returning the execution
+    /// report.
     ///
     /// # Errors
     ///
     /// This function will return an error if the program execution fails.
-    pub fn run_fast(&mut self) -> Result<(), ExecutionError> {
+    pub fn run_fast(&mut self) -> Result<ExecutionReport, ExecutionError> {
         self.executor_mode = ExecutorMode::Simple;
         self.print_report = true;
         while !self.execute()? {}
-        Ok(())
+        Ok(self.report.clone())
     }
 
-    /// Executes the program and prints the execution report.
+    /// Executes the program, returning and printing the execution report.
+    ///
+    /// Records of finished shards are dropped as execution proceeds. Use
+    /// [`Executor::execute_record`] to collect them instead.
     ///
     /// # Errors
     ///
     /// This function will return an error if the program execution fails.
-    pub fn run(&mut self) -> Result<(), ExecutionError> {
+    pub fn run(&mut self) -> Result<ExecutionReport, ExecutionError> {
         self.executor_mode = ExecutorMode::Trace;
         self.print_report = true;
-        while !self.execute()? {}
-        Ok(())
+        while !self.execute()? {
+            self.records.clear();
+        }
+        Ok(self.report.clone())
+    }
+
+    /// Executes the next batch of shards of the program with full tracing, returning the records
+    /// of the shards that finished executing and whether the program has finished.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the program execution fails.
+    pub fn execute_record(&mut self) -> Result<(Vec<ExecutionRecord>, bool), ExecutionError> {
+        self.executor_mode = ExecutorMode::Trace;
+        let done = self.execute()?;
+        Ok((std::mem::take(&mut self.records), done))
+    }
+
+    /// Executes the next batch of shards of the program without tracing, returning a checkpoint
+    /// of the state at the start of the batch and whether the program has finished.
+    ///
+    /// The checkpoint only holds the registers and the memory touched by the batch, which is all
+    /// that is needed to re-execute it from a recovered executor. The checkpoint of the last batch
+    /// holds the whole memory instead, since every address is needed to finalize memory at the end
+    /// of the program.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the program execution fails.
+    pub fn execute_checkpoint(&mut self) -> Result<(ExecutionState, bool), ExecutionError> {
+        self.memory_checkpoint.clear();
+        self.uninitialized_memory_checkpoint.clear();
+        self.hint_checkpoint.clear();
+        self.executor_mode = ExecutorMode::Checkpoint;
+
+        // Clone `self.state` without the memory, which is filled in with the touched addresses
+        // after the batch. The registers are small enough to always be included.
+        let memory = std::mem::take(&mut self.state.memory);
+        let memory_timestamps = std::mem::take(&mut self.state.memory_timestamps);
+        let uninitialized_memory = std::mem::take(&mut self.state.uninitialized_memory);
+        let mut checkpoint = self.state.clone();
+        self.state.memory = memory;
+        self.state.memory_timestamps = memory_timestamps;
+        self.state.uninitialized_memory = uninitialized_memory;
+
+        let done = self.execute()?;
+
+        let memory_checkpoint = std::mem::take(&mut self.memory_checkpoint);
+        let uninitialized_memory_checkpoint =
+            std::mem::take(&mut self.uninitialized_memory_checkpoint);
+        let mut hint_checkpoint = std::mem::take(&mut self.hint_checkpoint);
+        if done {
+            // Take all of memory, reverting the addresses touched by this batch.
+            checkpoint.memory.clone_from(&self.state.memory);
+            checkpoint
+                .memory_timestamps
+                .clone_from(&self.state.memory_timestamps);
+            for (addr, record) in memory_checkpoint {
+                checkpoint.set_memory_record(addr, record);
+            }
+            checkpoint
+                .uninitialized_memory
+                .clone_from(&self.state.uninitialized_memory);
+            for addr in hint_checkpoint {
+                checkpoint.uninitialized_memory.remove(addr);
+            }
+        } else {
+            for (addr, record) in memory_checkpoint {
+                if record.is_some() {
+                    checkpoint.set_memory_record(addr, record);
+                }
+            }
+            // Regions hinted in this batch are left out, since they are hinted again.
+            hint_checkpoint.sort_unstable();
+            let uninitialized_memory = &self.state.uninitialized_memory;
+            checkpoint.uninitialized_memory = uninitialized_memory_checkpoint
+                .into_iter()
+                .filter(|&(_, had_value)| had_value)
+                .filter_map(|(addr, _)| {
+                    let (start, words) = uninitialized_memory.region(addr)?;
+                    hint_checkpoint
+                        .binary_search(&start)
+                        .is_err()
+                        .then(|| (addr, words[(addr - start) as usize / 4]))
+                })
+                .collect();
+        }
+
+        Ok((checkpoint, done))
     }
 
-    /// Executes up to `self.shard_batch_size` cycles of the program, returning whether the program
+    /// Executes the program, regenerating the records of its shards in parallel, and calls
+    /// `on_record` with the record of every shard in order.
+    ///
+    /// The program is executed serially with [`Self::execute_checkpoint`], which only has to
+    /// track the memory touched by each batch of shards. The checkpoints are then handed out to
+    /// the rayon thread pool, where each one is recovered with [`Self::recover`] and re-executed
+    /// in trace mode. Checkpoints are taken a few batches at a time, so the serial pass over the
+    /// next batches overlaps with the tracing of the previous ones.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the program execution fails.
+    pub fn execute_record_parallel(
+        &mut self,
+        mut on_record: impl FnMut(ExecutionRecord),
+    ) -> Result<(), ExecutionError> {
+        let num_checkpoints = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+
+        // The tracing executors are configured like this one.
+        let program = self.program.clone();
+        let hook_registry = self.hook_registry.clone();
+        let max_cycles = self.max_cycles;
+        let shard_size = self.shard_size;
+        let shard_batch_size = self.shard_batch_size;
+        let lookup_id_generator = self.lookup_id_generator;
+        let syscall_map = self.syscall_map.clone();
+        let custom_syscall_map = self.custom_syscall_map.clone();
+        let max_syscall_cycles = self.max_syscall_cycles;
+        let trace = |checkpoints: Vec<ExecutionState>| {
+            checkpoints
+                .into_par_iter()
+                .map(|checkpoint| {
+                    let mut runtime = Executor::recover(program.as_ref().clone(), checkpoint);
+                    runtime.hook_registry = hook_registry.clone();
+                    runtime.max_cycles = max_cycles;
+                    runtime.shard_size = shard_size;
+                    runtime.shard_batch_size = shard_batch_size;
+                    runtime.lookup_id_generator = lookup_id_generator;
+                    runtime.syscall_map.clone_from(&syscall_map);
+                    runtime.custom_syscall_map.clone_from(&custom_syscall_map);
+                    runtime.max_syscall_cycles = max_syscall_cycles;
+                    runtime.execute_record().map(|(records, _)| records)
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let next_checkpoints = |runtime: &mut Self| {
+            let mut checkpoints = Vec::with_capacity(num_checkpoints);
+            while checkpoints.len() < num_checkpoints {
+                let (checkpoint, done) = runtime.execute_checkpoint()?;
+                checkpoints.push(checkpoint);
+                if done {
+                    return Ok((checkpoints, true));
+                }
+            }
+            Ok::<_, ExecutionError>((checkpoints, false))
+        };
+
+        let (mut checkpoints, mut done) = next_checkpoints(self)?;
+        loop {
+            let (records, next) = join(
+                || trace(checkpoints),
+                || (!done).then(|| next_checkpoints(self)).transpose(),
+            );
+            records?.into_iter().flatten().for_each(&mut on_record);
+            match next? {
+                Some(next) => (checkpoints, done) = next,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Executes up to `self.shard_batch_size` shards of the program, returning whether the program
     /// has finished.
+    ///
+    /// Call this repeatedly to execute the program one batch of shards at a time.
     pub fn execute(&mut self) -> Result<bool, ExecutionError> {
         // If it's the first cycle, initialize the program.
         if self.state.global_clk == 0 {
             self.initialize();
         }
-
-        // Loop until we've executed `self.shard_batch_size` shards if `self.shard_batch_size` is
-        // set.
-        let done;
-        loop {
-            if self.execute_cycle()? {
-                done = true;
-                break;
+        self.record.shard = self.shard();
+
+        // Loop until the program halts or we've executed `self.shard_batch_size` shards,
+        // switching to the loop of the new mode whenever an unconstrained block is entered or
+        // exited.
+        let start_shard = self.state.current_shard;
+        let done = loop {
+            if let Some(done) = dispatch_mode!(self.execute_cycles(start_shard))? {
+                break done;
             }
-        }
+        };
 
         if done {
             self.postprocess();
+
+            // Push the remaining execution record.
+            if !self.record.is_empty() {
+                self.bump_record();
+            }
         }
 
         Ok(done)
     }
 
+    /// Executes basic blocks in the given mode until the program halts, the batch of shards started
+    /// at `start_shard` is finished or the mode changes, returning whether the program has
+    /// finished in the first two cases.
+    fn execute_cycles<const MODE: u8, const UNCONSTRAINED: bool>(
+        &mut self,
+        start_shard: u32,
+    ) -> Result<Option<bool>, ExecutionError> {
+        loop {
+            if self.execute_block::<MODE, UNCONSTRAINED>()? {
+                return Ok(Some(true));
+            }
+            if self.state.current_shard - start_shard >= self.shard_batch_size {
+                return Ok(Some(false));
+            }
+            if self.unconstrained != UNCONSTRAINED {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Starts the record of a new shard, moving the finished one into `self.records` if we're
+    /// tracing.
+    fn bump_record(&mut self) {
+        let mut record = ExecutionRecord::new(self.program.clone());
+        record.shard = self.shard();
+        let mut removed_record = std::mem::replace(&mut self.record, record);
+
+        // The record only holds events when tracing, so it is dropped in the other modes.
+        if self.executor_mode == ExecutorMode::Trace {
+            // Copy all of the existing local memory accesses to the record's local_memory_access
+            // vec, sorted so that the record doesn't depend on the order of the map.
+            removed_record
+                .cpu_local_memory_access
+                .extend(self.local_memory_access.drain().map(|(_, event)| event));
+            removed_record
+                .cpu_local_memory_access
+                .sort_unstable_by_key(|event| event.addr);
+            self.records.push(removed_record);
+        }
+    }
+
     fn postprocess(&mut self) {
         // Flush remaining stdout/stderr
         for (fd, buf) in &self.io_buf {
@@ -1140,6 +2147,88 @@ impl<'a> Executor<'a> {
         if self.state.input_stream_ptr != self.state.input_stream.len() {
             tracing::warn!("Not all input bytes were read.");
         }
+
+        if self.executor_mode == ExecutorMode::Trace {
+            self.emit_memory_initialize_finalize();
+        }
+
+        self.report.total_cycles = self.state.global_clk;
+        if self.print_report {
+            for line in self.report.to_string().lines() {
+                tracing::info!("{line}");
+            }
+        }
+    }
+
+    /// Emits the initialize and finalize events of every touched memory address for the global
+    /// memory argument, sorted by address.
+    ///
+    /// Addresses written by `HINT_READ` are initialized with the hinted value even if the program
+    /// never accessed them.
+    fn emit_memory_initialize_finalize(&mut self) {
+        let mut initialize_events = Vec::new();
+        let mut finalize_events = Vec::new();
+
+        // Address 0 must always be present, as it's constrained to be the first row of both
+        // tables.
+        let addr_0_record = self.state.memory_record(0);
+        initialize_events.push(MemoryInitializeFinalizeEvent::initialize(
+            0,
+            0,
+            addr_0_record.is_some(),
+        ));
+        finalize_events.push(MemoryInitializeFinalizeEvent::finalize_from_record(
+            0,
+            &addr_0_record.unwrap_or(MemoryRecord::new(0, 1, 0)),
+        ));
+
+        let registers = (1..32).filter_map(|addr| Some((addr, self.state.memory_record(addr)?)));
+        let memory = self.state.memory.iter().map(|(addr, &value)| {
+            let last = self
+                .state
+                .memory_timestamps
+                .get(&addr)
+                .copied()
+                .unwrap_or_default();
+            (addr, MemoryRecord::new(last.shard, last.timestamp, value))
+        });
+        for (addr, record) in registers.chain(memory) {
+            // Program memory is initialized by the program's memory image, so it doesn't need an
+            // initialize event.
+            if !self.program.memory_image.contains_key(&addr) {
+                let value = self.state.uninitialized_memory.get(addr).unwrap_or(0);
+                initialize_events
+                    .push(MemoryInitializeFinalizeEvent::initialize(addr, value, true));
+            }
+            finalize_events.push(MemoryInitializeFinalizeEvent::finalize_from_record(
+                addr, &record,
+            ));
+        }
+
+        // Hinted addresses that were never accessed are finalized with the value and timestamp
+        // they were initialized with.
+        for (addr, value) in self.state.uninitialized_memory.iter() {
+            if self.state.memory.get(&addr).is_none() {
+                let initialize_event = MemoryInitializeFinalizeEvent::initialize(addr, value, true);
+                let record =
+                    MemoryRecord::new(initialize_event.shard, initialize_event.timestamp, value);
+                initialize_events.push(initialize_event);
+                finalize_events.push(MemoryInitializeFinalizeEvent::finalize_from_record(
+                    addr, &record,
+                ));
+            }
+        }
+
+        initialize_events.sort_unstable_by_key(|event| event.addr);
+        finalize_events.sort_unstable_by_key(|event| event.addr);
+
+        // If the program halted right at the end of a shard, the events go into that shard.
+        let record = match self.records.last_mut() {
+            Some(last) if self.record.cpu_events.is_empty() => last,
+            _ => &mut self.record,
+        };
+        record.global_memory_initialize_events = initialize_events;
+        record.global_memory_finalize_events = finalize_events;
     }
 
     fn get_syscall(&mut self, code: SyscallCode) -> Option<&Arc<dyn Syscall>> {
@@ -1148,7 +2237,7 @@ impl<'a> Executor<'a> {
 
     #[inline]
     #[cfg(debug_assertions)]
-    fn log(&mut self, _: &Instruction) {
+    fn log(&mut self) {
         // Write the current program counter to the trace buffer for the cycle tracer.
         if let Some(ref mut buf) = self.trace_buf {
             if !self.unconstrained {
@@ -1182,11 +2271,25 @@ pub const fn align(addr: u32) -> u32 {
 #[cfg(test)]
 mod tests {
 
-    use alloy_primitives::B256;
+    use std::io::{Seek, Write};
 
-    use crate::Register;
+    use alloy_primitives::B256;
+    use hashbrown::HashSet;
+
+    use crate::{
+        compile_to_rust,
+        events::PrecompileEvent,
+        memory_map::AlreadyInitialized,
+        ops::{Fused, Op},
+        syscalls::SyscallCode,
+        Register, SP1Context,
+    };
 
-    use super::{Executor, Instruction, Opcode, Program};
+    use super::{
+        CheckpointError, ExecutionError, ExecutionRecord, ExecutionReport, ExecutionState,
+        Executor, ExecutorMode, Instruction, LookupIdGenerator, MemoryAccessPosition, Opcode,
+        Program, SIMPLE,
+    };
 
     fn _assert_send<T: Send>() {}
 
@@ -1537,24 +2640,307 @@ mod tests {
     }
 
     #[test]
-    fn test_jalr() {
-        //   addi x11, x11, 100
-        //   jalr x5, x11, 8
-        //
-        // `JALR rd offset(rs)` reads the value at rs, adds offset to it and uses it as the
-        // destination address. It then stores the address of the next instruction in rd in case
-        // we'd want to come back here.
+    fn test_jalr() {
+        //   addi x11, x11, 100
+        //   jalr x5, x11, 8
+        //
+        // `JALR rd offset(rs)` reads the value at rs, adds offset to it and uses it as the
+        // destination address. It then stores the address of the next instruction in rd in case
+        // we'd want to come back here.
+
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 11, 11, 100, false, true),
+            Instruction::new(Opcode::JALR, 5, 11, 8, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program);
+        runtime.run().unwrap();
+        assert_eq!(runtime.registers()[Register::X5 as usize], 8);
+        assert_eq!(runtime.registers()[Register::X11 as usize], 100);
+        assert_eq!(runtime.state.pc, 108);
+    }
+
+    #[test]
+    fn test_shard_split() {
+        // 12 x `addi x29, x29, 1`
+        let instructions = vec![Instruction::new(Opcode::ADD, 29, 29, 1, false, true); 12];
+        let program = Program::new(instructions, 0, 0);
+        let context = SP1Context::builder().shard_size(16).build();
+        let mut runtime = Executor::with_context(program, context).unwrap();
+
+        // Each shard fits four instructions before running out of room for a syscall.
+        let mut shards = 0;
+        while !runtime.execute().unwrap() {
+            shards += 1;
+            assert_eq!(runtime.state.current_shard, shards + 1);
+            assert_eq!(runtime.state.clk, 0);
+            assert_eq!(runtime.state.global_clk, 4 * u64::from(shards));
+        }
+        assert_eq!(shards, 2);
+        assert_eq!(runtime.state.global_clk, 12);
+        assert_eq!(runtime.register(Register::X29), 12);
+    }
+
+    #[test]
+    fn test_shard_size_bounds() {
+        let executor = |shard_size: u64| {
+            let program = Program::new(
+                vec![Instruction::new(Opcode::ADD, 29, 0, 5, false, true)],
+                0,
+                0,
+            );
+            let context = SP1Context::builder().shard_size(shard_size).build();
+            Executor::with_context(program, context)
+                .map(|executor| executor.shard_size)
+                .map_err(|error| error.to_string())
+        };
+        let error = |shard_size| Err(ExecutionError::InvalidShardSize(shard_size).to_string());
+        // `SHA_EXTEND` takes the most extra cycles, 48.
+        assert_eq!(executor(13), Ok(52));
+        assert_eq!(executor(12), error(12));
+        assert_eq!(executor((1 << 30) - 1), Ok(u32::MAX - 3));
+        assert_eq!(executor(1 << 30), error(1 << 30));
+        assert_eq!(executor(u64::MAX), error(u64::MAX));
+    }
+
+    #[test]
+    fn test_execute_record() {
+        //     addi x29, x0, 5
+        //     addi x30, x0, 37
+        //     add x31, x30, x29
+        //     sw x31, 0x10000(x0)
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
+            Instruction::new(Opcode::ADD, 31, 30, 29, false, false),
+            Instruction::new(Opcode::SW, 31, 0, 0x10000, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program);
+        let (records, done) = runtime.execute_record().unwrap();
+        assert!(done);
+        assert_eq!(records.len(), 1);
+
+        let record = &records[0];
+        assert_eq!(record.shard, 1);
+        assert_eq!(record.cpu_events.len(), 4);
+        assert_eq!(record.add_events.len(), 3);
+        assert_eq!(record.add_events[2].a, 42);
+
+        let store = &record.cpu_events[3];
+        assert_eq!(store.memory, Some(42));
+        assert_eq!(store.memory_record.unwrap().value(), 42);
+        assert_eq!(store.a_record.unwrap().value(), 42);
+
+        // x0, x29, x30, x31 and the stored word.
+        assert_eq!(record.cpu_local_memory_access.len(), 5);
+    }
+
+    #[test]
+    fn test_execute_record_shards() {
+        let instructions = vec![Instruction::new(Opcode::ADD, 29, 29, 1, false, true); 12];
+        let program = Program::new(instructions, 0, 0);
+        let context = SP1Context::builder().shard_size(16).build();
+        let mut runtime = Executor::with_context(program, context).unwrap();
+
+        let mut records = Vec::new();
+        loop {
+            let (shard_records, done) = runtime.execute_record().unwrap();
+            records.extend(shard_records);
+            if done {
+                break;
+            }
+        }
+        assert_eq!(records.len(), 3);
+        for (i, record) in records.iter().enumerate() {
+            assert_eq!(record.shard, i as u32 + 1);
+            assert_eq!(record.cpu_events.len(), 4);
+            assert!(record.cpu_events.iter().all(|e| e.shard == record.shard));
+            assert_eq!(record.cpu_events[0].clk, 0);
+        }
+
+        // The program halts at the end of the last shard, which still gets the memory events.
+        assert!(!records[2].global_memory_finalize_events.is_empty());
+    }
+
+    /// The deterministic fields of the CPU events of a record.
+    fn cpu_summary(record: &ExecutionRecord) -> Vec<String> {
+        record
+            .cpu_events
+            .iter()
+            .map(|e| {
+                format!(
+                    "{} {} {} {} {:?} {:?} {:?} {:?} {:?}",
+                    e.pc,
+                    e.a,
+                    e.b,
+                    e.c,
+                    e.memory,
+                    e.a_record,
+                    e.b_record,
+                    e.c_record,
+                    e.memory_record
+                )
+            })
+            .collect()
+    }
+
+    /// Stores and loads a counter to consecutive words, touching fresh memory in every shard.
+    fn store_load_program() -> Program {
+        //     addi x10, x0, 0x10000
+        // 5x: addi x29, x29, 1
+        //     sw x29, 0(x10)
+        //     lw x30, 0(x10)
+        //     addi x10, x10, 4
+        let mut instructions = vec![Instruction::new(Opcode::ADD, 10, 0, 0x10000, false, true)];
+        for _ in 0..5 {
+            instructions.extend([
+                Instruction::new(Opcode::ADD, 29, 29, 1, false, true),
+                Instruction::new(Opcode::SW, 29, 10, 0, false, true),
+                Instruction::new(Opcode::LW, 30, 10, 0, false, true),
+                Instruction::new(Opcode::ADD, 10, 10, 4, false, true),
+            ]);
+        }
+        Program::new(instructions, 0, 0)
+    }
+
+    #[test]
+    fn test_execute_checkpoint() {
+        let program = store_load_program();
+        let context = || {
+            SP1Context::builder()
+                .shard_size(16)
+                .shard_batch_size(2)
+                .build()
+        };
+
+        let mut runtime = Executor::with_context(program.clone(), context()).unwrap();
+        let mut checkpoints = Vec::new();
+        loop {
+            let (checkpoint, done) = runtime.execute_checkpoint().unwrap();
+            checkpoints.push(checkpoint);
+            if done {
+                break;
+            }
+        }
+        assert_eq!(checkpoints.len(), 3);
+        assert!(runtime.memory_checkpoint.is_empty());
+        assert!(runtime.uninitialized_memory_checkpoint.is_empty());
+
+        // Intermediate checkpoints only hold what their batch touches, the last one holds all of
+        // memory.
+        assert!(checkpoints[1].memory.get(&0x10000).is_none());
+        assert!(checkpoints[1].memory.get(&0x10004).is_none());
+        assert!(checkpoints[2].memory.get(&0x10000).is_some());
+        assert_eq!(checkpoints[1].current_shard, 3);
+        assert_eq!(checkpoints[1].global_clk, 8);
+
+        let mut expected = Executor::with_context(program.clone(), context()).unwrap();
+        // Each batch can be re-executed on its own from its checkpoint.
+        for checkpoint in checkpoints {
+            let (expected_records, expected_done) = expected.execute_record().unwrap();
+            let mut replay = Executor::with_context(program.clone(), context()).unwrap();
+            replay.state = checkpoint;
+            let (records, done) = replay.execute_record().unwrap();
+            assert_eq!(done, expected_done);
+            assert_eq!(records.len(), expected_records.len());
+            for (record, expected_record) in records.iter().zip(expected_records.iter()) {
+                assert_eq!(cpu_summary(record), cpu_summary(expected_record));
+            }
+        }
+        assert_eq!(expected.register(Register::X30), 5);
+    }
+
+    /// Trace the program of `runtime` to the end, returning the records of all its shards.
+    fn execute_records(runtime: &mut Executor) -> Vec<ExecutionRecord> {
+        let mut records = Vec::new();
+        loop {
+            let (batch, done) = runtime.execute_record().unwrap();
+            records.extend(batch);
+            if done {
+                return records;
+            }
+        }
+    }
+
+    #[test]
+    fn test_execute_record_parallel() {
+        let program = store_load_program();
+        let context = || {
+            SP1Context::builder()
+                .shard_size(16)
+                .shard_batch_size(2)
+                .build()
+        };
 
-        let instructions = vec![
-            Instruction::new(Opcode::ADD, 11, 11, 100, false, true),
-            Instruction::new(Opcode::JALR, 5, 11, 8, false, true),
-        ];
+        let expected_records =
+            execute_records(&mut Executor::with_context(program.clone(), context()).unwrap());
+
+        let mut runtime = Executor::with_context(program, context()).unwrap();
+        let mut records = Vec::new();
+        runtime
+            .execute_record_parallel(|record| records.push(record))
+            .unwrap();
+        assert_eq!(records.len(), 6);
+        assert_eq!(records.len(), expected_records.len());
+        for (i, (record, expected_record)) in records.iter().zip(&expected_records).enumerate() {
+            assert_eq!(record.shard, i as u32 + 1);
+            assert_eq!(
+                bincode::serialize(record).unwrap(),
+                bincode::serialize(expected_record).unwrap()
+            );
+        }
+        assert_eq!(runtime.state.global_clk, 21);
+        assert_eq!(runtime.register(Register::X30), 5);
+    }
+
+    #[test]
+    fn test_execute_record_syscalls() {
+        let ecall = |code: SyscallCode, arg2: u32| {
+            [
+                Instruction::new(Opcode::ADD, 5, 0, code as u32, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, arg2, false, true),
+                Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            ]
+        };
+        let mut instructions = vec![Instruction::new(Opcode::ADD, 10, 0, 0x10000, false, true)];
+        instructions.extend(ecall(SyscallCode::KECCAK_PERMUTE, 0));
+        instructions.extend(ecall(SyscallCode::BN254_FP_ADD, 0x10100));
+        instructions.extend(ecall(SyscallCode::BN254_FP_MUL, 0x10100));
+        instructions.extend(ecall(SyscallCode::KECCAK_PERMUTE, 0));
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Executor::new(program);
-        runtime.run().unwrap();
-        assert_eq!(runtime.registers()[Register::X5 as usize], 8);
-        assert_eq!(runtime.registers()[Register::X11 as usize], 100);
-        assert_eq!(runtime.state.pc, 108);
+        let (records, done) = runtime.execute_record().unwrap();
+        assert!(done);
+
+        let record = &records[0];
+        let syscall_nonces = record
+            .syscall_events
+            .iter()
+            .map(|e| e.nonce)
+            .collect::<Vec<_>>();
+        assert_eq!(syscall_nonces, vec![0, 1, 2, 3]);
+
+        // Events of syscalls sharing a table are stored together and numbered by row.
+        for (code, len) in [
+            (SyscallCode::KECCAK_PERMUTE, 2),
+            (SyscallCode::BN254_FP_ADD, 2),
+            (SyscallCode::BN254_FP_MUL, 2),
+        ] {
+            let events = record.precompile_events.get_events(code).unwrap();
+            assert_eq!(events.len(), len);
+            for (i, (syscall_event, _)) in events.iter().enumerate() {
+                assert_eq!(syscall_event.nonce, i as u32);
+            }
+        }
+        let fp_events = record
+            .precompile_events
+            .get_events(SyscallCode::BN254_FP_ADD)
+            .unwrap();
+        assert_eq!(
+            fp_events[1].0.syscall_id,
+            SyscallCode::BN254_FP_MUL.syscall_id()
+        );
     }
 
     fn simple_op_code_test(opcode: Opcode, expected: u32, a: u32, b: u32) {
@@ -1737,4 +3123,1677 @@ mod tests {
         simple_op_code_test(Opcode::SRA, 0xfffe0606, 0x81818181, 14);
         simple_op_code_test(Opcode::SRA, 0xffffffff, 0x81818181, 31);
     }
+
+    #[test]
+    fn test_precompile_events_only_traced() {
+        let instructions = vec![
+            Instruction::new(
+                Opcode::ADD,
+                5,
+                0,
+                SyscallCode::SHA_EXTEND as u32,
+                false,
+                true,
+            ),
+            Instruction::new(Opcode::ADD, 10, 0, 0x10000, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+
+        let mut fast = Executor::new(program.clone());
+        fast.run_fast().unwrap();
+        assert_eq!(fast.record.precompile_events.all_events().count(), 0);
+
+        let mut traced = Executor::new(program);
+        let (records, done) = traced.execute_record().unwrap();
+        assert!(done);
+        let events = records[0]
+            .precompile_events
+            .get_events(SyscallCode::SHA_EXTEND)
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].1, PrecompileEvent::ShaExtend(_)));
+    }
+
+    #[test]
+    fn test_memory_initialize_finalize() {
+        //     addi x5, x0, HINT_READ
+        //     addi x10, x0, 0x10008
+        //     addi x11, x0, 8
+        //     ecall
+        //     lw x29, 0x10008(x0)
+        //     sw x29, 0x10000(x0)
+        //     lw x30, 0x10004(x0)
+        let instructions = vec![
+            Instruction::new(
+                Opcode::ADD,
+                5,
+                0,
+                SyscallCode::HINT_READ as u32,
+                false,
+                true,
+            ),
+            Instruction::new(Opcode::ADD, 10, 0, 0x10008, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 8, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            Instruction::new(Opcode::LW, 29, 0, 0x10008, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x10000, false, true),
+            Instruction::new(Opcode::LW, 30, 0, 0x10004, false, true),
+        ];
+        let mut program = Program::new(instructions, 0, 0);
+        program.memory_image.insert(0x10004, 7);
+        let mut runtime = Executor::new(program);
+        runtime.write_stdin_slice(&[5, 0, 0, 0, 9, 0, 0, 0]);
+        let (records, done) = runtime.execute_record().unwrap();
+        assert!(done);
+
+        let record = records.last().unwrap();
+        let initialize = record
+            .global_memory_initialize_events
+            .iter()
+            .map(|e| (e.addr, e.value))
+            .collect::<Vec<_>>();
+        let finalize = record
+            .global_memory_finalize_events
+            .iter()
+            .map(|e| (e.addr, e.value))
+            .collect::<Vec<_>>();
+        let code = SyscallCode::HINT_READ as u32;
+        assert_eq!(
+            initialize,
+            vec![
+                (0, 0),
+                (5, 0),
+                (10, 0),
+                (11, 0),
+                (29, 0),
+                (30, 0),
+                (0x10000, 0),
+                (0x10008, 5),
+                (0x1000c, 9),
+            ]
+        );
+        assert_eq!(
+            finalize,
+            vec![
+                (0, 0),
+                (5, code),
+                (10, 0x10008),
+                (11, 8),
+                (29, 5),
+                (30, 7),
+                (0x10000, 5),
+                (0x10004, 7),
+                (0x10008, 5),
+                (0x1000c, 9),
+            ]
+        );
+
+        // The hinted address that was never accessed is left as it was initialized.
+        let initialize = record.global_memory_initialize_events.last().unwrap();
+        let finalize = record.global_memory_finalize_events.last().unwrap();
+        assert_eq!(
+            (finalize.shard, finalize.timestamp),
+            (initialize.shard, initialize.timestamp)
+        );
+    }
+
+    #[test]
+    fn test_deterministic_lookup_ids() {
+        let execute = |lookup_id_generator: LookupIdGenerator| {
+            let mut runtime = Executor::new(store_load_program());
+            runtime.lookup_id_generator = lookup_id_generator;
+            let (records, done) = runtime.execute_record().unwrap();
+            assert!(done);
+            bincode::serialize(&records).unwrap()
+        };
+        assert_eq!(
+            execute(LookupIdGenerator::new(1)),
+            execute(LookupIdGenerator::new(1))
+        );
+        assert_ne!(
+            execute(LookupIdGenerator::new(1)),
+            execute(LookupIdGenerator::new(2))
+        );
+
+        // Every lookup of a cycle gets its own id.
+        let generator = LookupIdGenerator::default();
+        let ids = (0..8)
+            .map(|index| generator.lookup_id(1, 4, index))
+            .chain((0..8).map(|index| generator.lookup_id(1, 8, index)))
+            .chain((0..8).map(|index| generator.lookup_id(2, 4, index)))
+            .collect::<HashSet<_>>();
+        assert_eq!(ids.len(), 24);
+    }
+
+    #[test]
+    fn test_execution_report() {
+        let mut program = Program::new(Vec::new(), 0, 0);
+        let mut write = |addr: u32, s: &str| {
+            for (i, chunk) in s.as_bytes().chunks(4).enumerate() {
+                let mut word = [0; 4];
+                word[..chunk.len()].copy_from_slice(chunk);
+                program
+                    .memory_image
+                    .insert(addr + 4 * i as u32, u32::from_le_bytes(word));
+            }
+            [
+                Instruction::new(Opcode::ADD, 10, 0, 1, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, addr, false, true),
+                Instruction::new(Opcode::ADD, 12, 0, s.len() as u32, false, true),
+                Instruction::new(Opcode::ADD, 5, 0, SyscallCode::WRITE as u32, false, true),
+                Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            ]
+        };
+        let mut instructions = write(0x20000, "cycle-tracker-report-start: foo").to_vec();
+        instructions.extend([
+            Instruction::new(Opcode::ADD, 29, 29, 1, false, true),
+            Instruction::new(Opcode::SUB, 29, 29, 1, false, true),
+            Instruction::new(Opcode::XOR, 29, 29, 1, false, true),
+        ]);
+        instructions.extend(write(0x20100, "cycle-tracker-report-end: foo"));
+        program.instructions = instructions;
+
+        let mut runtime = Executor::new(program);
+        let report = runtime.run_fast().unwrap();
+        assert_eq!(report.total_cycles, 13);
+        assert_eq!(report.total_instruction_count(), 13);
+        assert_eq!(report.opcode_counts[Opcode::ADD], 9);
+        assert_eq!(report.opcode_counts[Opcode::ECALL], 2);
+        assert_eq!(report.opcode_counts[Opcode::SUB], 1);
+        assert_eq!(report.syscall_counts[SyscallCode::WRITE], 2);
+        assert_eq!(report.total_syscall_count(), 2);
+
+        // The span covers the cycles from the start write to the end write.
+        assert_eq!(report.cycle_tracker["foo"], 8);
+
+        let bytes = bincode::serialize(&report).unwrap();
+        assert_eq!(
+            bincode::deserialize::<ExecutionReport>(&bytes).unwrap(),
+            report
+        );
+    }
+
+    #[test]
+    fn test_resume_from_file() {
+        let program = store_load_program();
+        let context = || SP1Context::builder().shard_size(16).build();
+        let path = std::env::temp_dir().join(format!("sp1-checkpoint-{}", std::process::id()));
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+
+        let mut runtime = Executor::with_context(program.clone(), context()).unwrap();
+        runtime.print_report = true;
+        assert!(!runtime.execute().unwrap());
+        assert!(!runtime.execute().unwrap());
+        runtime.save_to_file(&mut file).unwrap();
+
+        let mut resumed =
+            Executor::resume_from_file(program.clone(), context(), &mut file).unwrap();
+        assert_eq!(resumed.state.global_clk, 8);
+        assert_eq!(resumed.report, runtime.report);
+        let report = resumed.run().unwrap();
+        runtime.run().unwrap();
+        assert_eq!(resumed.registers(), runtime.registers());
+        assert_eq!(resumed.state.global_clk, 21);
+        assert_eq!(report.total_instruction_count(), 21);
+
+        // A bare execution state can be loaded too, but only for the program it was taken from.
+        file.set_len(0).unwrap();
+        file.rewind().unwrap();
+        runtime.state.save(&program, &mut file).unwrap();
+        let state = ExecutionState::load(&program, &mut file).unwrap();
+        assert_eq!(state.global_clk, 21);
+        let other = Program::new(program.instructions[1..].to_vec(), 0, 0);
+        file.rewind().unwrap();
+        assert!(matches!(
+            Executor::resume_from_file(other, context(), &mut file),
+            Err(CheckpointError::ProgramMismatch)
+        ));
+
+        file.set_len(0).unwrap();
+        file.rewind().unwrap();
+        file.write_all(&[0; 64]).unwrap();
+        file.rewind().unwrap();
+        assert!(matches!(
+            ExecutionState::load(&program, &mut file),
+            Err(CheckpointError::InvalidMagic)
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_simple_mode_memory_values() {
+        let program = store_load_program();
+
+        let mut fast = Executor::new(program.clone());
+        fast.run_fast().unwrap();
+        assert_eq!(fast.state.memory_timestamps.iter().count(), 0);
+
+        let mut traced = Executor::new(program);
+        traced.run().unwrap();
+        let values = |runtime: &Executor| {
+            runtime
+                .state
+                .memory
+                .iter()
+                .map(|(addr, &value)| (addr, value))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(&fast), values(&traced));
+        assert_eq!(fast.state.memory.get(&0x10010), Some(&5));
+        assert_eq!(fast.register(Register::X30), 5);
+
+        let record = traced.state.memory_record(0x10010).unwrap();
+        assert_eq!((record.shard, record.value), (1, 5));
+        assert!(record.timestamp > 0);
+    }
+
+    #[test]
+    fn test_unconstrained_block() {
+        //     addi x29, x0, 5
+        //     addi x10, x0, 0x10000
+        //     sw x29, 0(x10)
+        //     ecall ENTER_UNCONSTRAINED
+        //     beq x5, x0, end
+        //     addi x29, x0, 7
+        //     sw x29, 0(x10)
+        //     ecall EXIT_UNCONSTRAINED
+        // end:
+        //     lw x30, 0(x10)
+        //     add x31, x29, x0
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 0x10000, false, true),
+            Instruction::new(Opcode::SW, 29, 10, 0, false, true),
+            Instruction::new(
+                Opcode::ADD,
+                5,
+                0,
+                SyscallCode::ENTER_UNCONSTRAINED as u32,
+                false,
+                true,
+            ),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            Instruction::new(Opcode::BEQ, 5, 0, 20, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 7, false, true),
+            Instruction::new(Opcode::SW, 29, 10, 0, false, true),
+            Instruction::new(
+                Opcode::ADD,
+                5,
+                0,
+                SyscallCode::EXIT_UNCONSTRAINED as u32,
+                false,
+                true,
+            ),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            Instruction::new(Opcode::LW, 30, 10, 0, false, true),
+            Instruction::new(Opcode::ADD, 31, 29, 0, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0);
+
+        let mut fast = Executor::new(program.clone());
+        fast.run_fast().unwrap();
+        let mut traced = Executor::new(program);
+        let (records, done) = traced.execute_record().unwrap();
+        assert!(done);
+
+        // Changes made in the block are reverted when it's exited.
+        for runtime in [&mut fast, &mut traced] {
+            assert!(!runtime.unconstrained);
+            assert_eq!(runtime.register(Register::X5), 0);
+            assert_eq!(runtime.register(Register::X30), 5);
+            assert_eq!(runtime.register(Register::X31), 5);
+            assert_eq!(runtime.word(0x10000), 5);
+        }
+        assert_eq!(fast.executor_mode, ExecutorMode::Simple);
+        assert_eq!(traced.executor_mode, ExecutorMode::Trace);
+
+        // Tracing stops when the block is entered and resumes when it's exited.
+        let pcs = records[0]
+            .cpu_events
+            .iter()
+            .map(|event| event.pc)
+            .collect::<Vec<_>>();
+        assert_eq!(pcs, vec![0, 4, 8, 12, 36, 20, 40, 44]);
+    }
+
+    #[test]
+    fn test_memory_access_in_any_mode() {
+        for mode in [
+            ExecutorMode::Simple,
+            ExecutorMode::Checkpoint,
+            ExecutorMode::Trace,
+        ] {
+            let mut runtime = Executor::new(store_load_program());
+            runtime.executor_mode = mode;
+            runtime.state.clk = 4;
+            runtime.rw(Register::X5, 7);
+            runtime.rw(Register::X0, 7);
+            runtime.mw_cpu(0x10000, 9, MemoryAccessPosition::Memory);
+            runtime.state.clk += 4;
+            assert_eq!(runtime.rr(Register::X5, MemoryAccessPosition::B), 7);
+            assert_eq!(runtime.rr(Register::X0, MemoryAccessPosition::C), 0);
+            assert_eq!(runtime.mr_cpu(0x10000, MemoryAccessPosition::Memory), 9);
+        }
+    }
+
+    #[test]
+    fn test_basic_blocks() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 1, false, true),
+            Instruction::new(Opcode::BEQ, 5, 0, 8, false, true),
+            Instruction::new(Opcode::ADD, 6, 0, 2, false, true),
+            Instruction::new(Opcode::ADD, 7, 0, 3, false, true),
+            Instruction::new(Opcode::JAL, 1, 8, 0, true, true),
+            Instruction::new(Opcode::ADD, 8, 0, 4, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        assert_eq!(program.basic_block_ends(), vec![2, 2, 5, 5, 5, 6]);
+
+        // The cycle limit is hit in the middle of a block, at the exact cycle.
+        let context = SP1Context::builder().max_cycles(10).build();
+        let mut runtime = Executor::with_context(store_load_program(), context).unwrap();
+        assert!(matches!(
+            runtime.run_fast(),
+            Err(ExecutionError::ExceededCycleLimit(10))
+        ));
+        assert_eq!(runtime.state.global_clk, 10);
+        assert_eq!(runtime.state.pc, 40);
+    }
+
+    #[test]
+    fn test_lowered_ops() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 6, 0, 3, false, true),
+            Instruction::new(Opcode::ADD, 0, 6, 5, false, true),
+            Instruction::new(Opcode::ADD, 0, 6, 6, false, false),
+            Instruction::new(Opcode::JAL, 0, 8, 0, true, true),
+            Instruction::new(Opcode::ADD, 7, 0, 99, false, true),
+            Instruction::new(Opcode::ADD, 31, 6, 0, false, false),
+        ];
+        let ops = instructions.iter().map(Op::lower).collect::<Vec<_>>();
+        assert!(matches!(
+            ops[0],
+            Op::AluImmediate {
+                opcode: Opcode::ADD,
+                rd: Register::X6,
+                rs1: Register::X0,
+                imm: 3,
+            }
+        ));
+        assert!(matches!(ops[1], Op::AluImmediateX0 { imm: 5, .. }));
+        assert!(matches!(ops[2], Op::AluRegistersX0 { .. }));
+        assert!(matches!(ops[3], Op::JalX0 { imm: 8 }));
+        assert!(matches!(
+            ops[5],
+            Op::AluRegisters {
+                rd: Register::X31,
+                ..
+            }
+        ));
+
+        let program = Program::new(instructions, 0, 0);
+        let mut fast = Executor::new(program.clone());
+        fast.run_fast().unwrap();
+        let mut traced = Executor::new(program);
+        let (records, _) = traced.execute_record().unwrap();
+        assert_eq!(fast.registers(), traced.registers());
+        assert_eq!(fast.register(Register::X0), 0);
+        assert_eq!(fast.register(Register::X7), 0);
+        assert_eq!(fast.register(Register::X31), 3);
+
+        // Writes to x0 are still traced.
+        let events = &records[0].cpu_events;
+        assert_eq!(events.len(), 5);
+        assert!(events[1..4].iter().all(|event| event.a_record.is_some()));
+        assert_eq!(events[1].a, 8);
+    }
+
+    #[test]
+    fn test_register_file() {
+        let program = store_load_program();
+
+        // Simple mode only keeps the values of the registers.
+        let mut fast = Executor::new(program.clone());
+        fast.run_fast().unwrap();
+        assert!(fast.state.register_timestamps.iter().all(Option::is_none));
+        assert!(fast.state.memory_record(Register::X29 as u32).is_none());
+
+        let mut traced = Executor::new(program.clone());
+        let (records, _) = traced.execute_record().unwrap();
+        assert_eq!(fast.registers(), traced.registers());
+        let last = traced.state.register_timestamps[Register::X29 as usize].unwrap();
+        let record = traced.state.memory_record(Register::X29 as u32).unwrap();
+        assert_eq!(
+            (record.shard, record.timestamp, record.value),
+            (last.shard, last.timestamp, 5)
+        );
+
+        // Touched registers are finalized like any other address.
+        let finalized = records
+            .iter()
+            .flat_map(|record| &record.global_memory_finalize_events)
+            .map(|event| event.addr)
+            .collect::<Vec<_>>();
+        assert!(finalized.contains(&(Register::X29 as u32)));
+        assert!(!finalized.contains(&(Register::X1 as u32)));
+
+        // Checkpoints include the whole register file.
+        let context = SP1Context::builder()
+            .shard_size(16)
+            .shard_batch_size(2)
+            .build();
+        let mut executor = Executor::with_context(program, context).unwrap();
+        let (checkpoint, _) = executor.execute_checkpoint().unwrap();
+        assert_eq!(checkpoint.registers, [0; 32]);
+        let (checkpoint, _) = executor.execute_checkpoint().unwrap();
+        assert_eq!(checkpoint.global_clk, 8);
+        assert_eq!(checkpoint.registers[Register::X29 as usize], 2);
+    }
+
+    /// Values that exercise the edge cases of the ALU operations.
+    #[cfg(feature = "jit")]
+    const JIT_TEST_VALUES: [u32; 9] = [
+        0,
+        1,
+        2,
+        31,
+        32,
+        u32::MAX,
+        1 << 31,
+        (1 << 31) - 1,
+        0xdead_beef,
+    ];
+
+    /// A random instruction on x0-x15, with loads and stores into the scratch buffer addressed by
+    /// x20 and branches that skip the next instruction.
+    #[cfg(feature = "jit")]
+    fn random_instruction(rng: &mut rand::rngs::StdRng) -> Instruction {
+        use rand::Rng;
+
+        let alu_opcodes = [
+            Opcode::ADD,
+            Opcode::SUB,
+            Opcode::XOR,
+            Opcode::OR,
+            Opcode::AND,
+            Opcode::SLL,
+            Opcode::SRL,
+            Opcode::SRA,
+            Opcode::SLT,
+            Opcode::SLTU,
+            Opcode::MUL,
+            Opcode::MULH,
+            Opcode::MULHU,
+            Opcode::MULHSU,
+            Opcode::DIV,
+            Opcode::DIVU,
+            Opcode::REM,
+            Opcode::REMU,
+        ];
+        let loads = [Opcode::LB, Opcode::LH, Opcode::LW, Opcode::LBU, Opcode::LHU];
+        let stores = [Opcode::SB, Opcode::SH, Opcode::SW];
+        let branches = [
+            Opcode::BEQ,
+            Opcode::BNE,
+            Opcode::BLT,
+            Opcode::BGE,
+            Opcode::BLTU,
+            Opcode::BGEU,
+        ];
+        let (rd, rs1, rs2) = (
+            rng.gen_range(0..16),
+            rng.gen_range(0..16),
+            rng.gen_range(0..16),
+        );
+        let offset = |width: u32, rng: &mut rand::rngs::StdRng| rng.gen_range(0..16) * width;
+        match rng.gen_range(0..6) {
+            0 => Instruction::new(
+                alu_opcodes[rng.gen_range(0..18)],
+                rd,
+                rs1,
+                rs2,
+                false,
+                false,
+            ),
+            1 => {
+                let imm = JIT_TEST_VALUES[rng.gen_range(0..JIT_TEST_VALUES.len())];
+                Instruction::new(alu_opcodes[rng.gen_range(0..18)], rd, rs1, imm, false, true)
+            }
+            2 => Instruction::new(
+                alu_opcodes[rng.gen_range(0..18)],
+                rd,
+                rng.gen(),
+                rng.gen(),
+                true,
+                true,
+            ),
+            3 => {
+                let opcode = loads[rng.gen_range(0..loads.len())];
+                let width = match opcode {
+                    Opcode::LW => 4,
+                    Opcode::LH | Opcode::LHU => 2,
+                    _ => 1,
+                };
+                Instruction::new(opcode, rd, 20, offset(width, rng), false, true)
+            }
+            4 => {
+                let opcode = stores[rng.gen_range(0..stores.len())];
+                let width = match opcode {
+                    Opcode::SW => 4,
+                    Opcode::SH => 2,
+                    _ => 1,
+                };
+                Instruction::new(opcode, rs1, 20, offset(width, rng), false, true)
+            }
+            _ => Instruction::new(branches[rng.gen_range(0..6)], rs1, rs2, 8, false, true),
+        }
+    }
+
+    /// A random program for comparing the JIT against the interpreter: random operations followed
+    /// by a loop, a call and a commit of the scratch buffer at 0x10000 as public values.
+    #[cfg(feature = "jit")]
+    fn random_program(seed: u64) -> Program {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut instructions = vec![Instruction::new(Opcode::ADD, 20, 0, 0x10000, false, true)];
+        for (i, &value) in JIT_TEST_VALUES.iter().enumerate() {
+            instructions.push(Instruction::new(
+                Opcode::ADD,
+                i as u32 + 1,
+                0,
+                value,
+                false,
+                true,
+            ));
+        }
+        for _ in 0..2000 {
+            instructions.push(random_instruction(&mut rng));
+        }
+
+        // Sum a countdown, then call a function and return from it.
+        instructions.extend([
+            Instruction::new(Opcode::ADD, 21, 0, 10, false, true),
+            Instruction::new(Opcode::ADD, 22, 22, 21, false, false),
+            Instruction::new(Opcode::ADD, 21, 21, u32::MAX, false, true),
+            Instruction::new(Opcode::BNE, 21, 0, 8u32.wrapping_neg(), false, true),
+            Instruction::new(Opcode::JAL, 1, 12, 0, true, true),
+            Instruction::new(Opcode::ADD, 23, 0, 1, false, true),
+            Instruction::new(Opcode::JAL, 0, 12, 0, true, true),
+            Instruction::new(Opcode::AUIPC, 24, 0x1000, 0x1000, true, true),
+            Instruction::new(Opcode::JALR, 0, 1, 0, false, true),
+        ]);
+
+        // Commit the scratch buffer as public values.
+        instructions.extend([
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::WRITE as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 3, false, true),
+            Instruction::new(Opcode::ADD, 11, 20, 0, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 64, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+        ]);
+
+        Program::new(instructions, 0, 0)
+    }
+
+    /// Runs `program` with the JIT and with the interpreter, checks that they end in the same state
+    /// and returns the executor that ran the JIT.
+    #[cfg(feature = "jit")]
+    fn assert_jit_matches_interpreter(
+        program: &Program,
+        context: impl Fn() -> SP1Context<'static>,
+    ) -> Executor<'static> {
+        let mut jit = Executor::with_context(program.clone(), context()).unwrap();
+        jit.run_fast().unwrap();
+        let mut interpreted = Executor::with_context(program.clone(), context()).unwrap();
+        interpreted.run().unwrap();
+        assert_eq!(jit.report, interpreted.report);
+        assert_eq!(jit.registers(), interpreted.registers());
+        assert_eq!(jit.state.global_clk, interpreted.state.global_clk);
+        assert_eq!(jit.state.current_shard, interpreted.state.current_shard);
+        assert_eq!(jit.state.clk, interpreted.state.clk);
+        assert_eq!(
+            jit.state.public_values_stream,
+            interpreted.state.public_values_stream
+        );
+        jit
+    }
+
+    #[test]
+    #[cfg(feature = "jit")]
+    fn test_jit() {
+        let program = random_program(0);
+        let context = || SP1Context::builder().shard_size(64).build();
+        let jit = assert_jit_matches_interpreter(&program, context);
+        assert_eq!(jit.register(Register::X22), 55);
+        assert_eq!(jit.register(Register::X23), 1);
+
+        // The cycle limit is hit at the same cycle.
+        let context = || SP1Context::builder().max_cycles(1000).build();
+        let mut jit = Executor::with_context(program.clone(), context()).unwrap();
+        let mut interpreted = Executor::with_context(program, context()).unwrap();
+        assert!(matches!(
+            jit.run_fast(),
+            Err(ExecutionError::ExceededCycleLimit(1000))
+        ));
+        assert!(interpreted.run().is_err());
+        assert_eq!(jit.state.pc, interpreted.state.pc);
+        assert_eq!(jit.registers(), interpreted.registers());
+
+        // Faults stop at the faulting instruction.
+        let program = Program::new(
+            vec![
+                Instruction::new(Opcode::ADD, 10, 0, 0x10002, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, 7, false, true),
+                Instruction::new(Opcode::LW, 12, 10, 0, false, true),
+                Instruction::new(Opcode::ADD, 13, 0, 7, false, true),
+            ],
+            0,
+            0,
+        );
+        let mut jit = Executor::new(program);
+        assert!(matches!(
+            jit.run_fast(),
+            Err(ExecutionError::InvalidMemoryAccess(Opcode::LW, 0x10002))
+        ));
+        assert_eq!(jit.state.pc, 8);
+        assert_eq!(jit.state.global_clk, 2);
+        assert_eq!(jit.register(Register::X11), 7);
+    }
+
+    #[test]
+    #[cfg(feature = "jit")]
+    fn test_jit_syscalls() {
+        let ecall = |number: SyscallCode, arg1: u32, arg2: u32| {
+            [
+                Instruction::new(Opcode::ADD, 5, 0, number as u32, false, true),
+                Instruction::new(Opcode::ADD, 10, 0, arg1, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, arg2, false, true),
+                Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            ]
+        };
+        // Commit a countdown from 20 as public values, extending a SHA-256 message schedule on
+        // each step.
+        let mut instructions = vec![
+            Instruction::new(Opcode::ADD, 21, 0, 20, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+            Instruction::new(Opcode::SW, 21, 0, 0x10000, false, true),
+        ];
+        instructions.extend(ecall(SyscallCode::SHA_EXTEND, 0x10100, 0));
+        instructions.extend(ecall(SyscallCode::WRITE, 3, 0x10000));
+        instructions.extend([
+            Instruction::new(Opcode::ADD, 21, 21, u32::MAX, false, true),
+            Instruction::new(Opcode::BNE, 21, 0, 40u32.wrapping_neg(), false, true),
+        ]);
+        // Enter an unconstrained block, and halt after it.
+        instructions.extend(ecall(SyscallCode::ENTER_UNCONSTRAINED, 0, 0));
+        instructions.extend([
+            Instruction::new(Opcode::BEQ, 5, 0, 24, false, true),
+            Instruction::new(Opcode::ADD, 22, 0, 7, false, true),
+        ]);
+        instructions.extend(ecall(SyscallCode::EXIT_UNCONSTRAINED, 0, 0));
+        instructions.extend(ecall(SyscallCode::HALT, 0, 0));
+        instructions.push(Instruction::new(Opcode::ADD, 23, 0, 1, false, true));
+        let program = Program::new(instructions, 0, 0);
+
+        let context = || SP1Context::builder().shard_size(64).build();
+        let mut jit = assert_jit_matches_interpreter(&program, context);
+        assert_eq!(jit.state.current_shard, 9);
+        assert_eq!(jit.report.syscall_counts[SyscallCode::SHA_EXTEND], 20);
+        let values = (1..=20u32).rev().flat_map(u32::to_le_bytes);
+        assert_eq!(jit.state.public_values_stream, values.collect::<Vec<_>>());
+        assert_eq!(jit.register(Register::X22), 0);
+        assert_eq!(jit.register(Register::X23), 0);
+        // The syscalls are called from compiled code.
+        assert_eq!(jit.jit_block(2).map(|block| block.len), Some(5));
+        assert_eq!(jit.jit_block(7).map(|block| block.len), Some(4));
+
+        // A syscall that fails stops at its `ECALL`.
+        let mut instructions = vec![Instruction::new(Opcode::ADD, 21, 0, 7, false, true)];
+        instructions.extend(ecall(SyscallCode::SHA_EXTEND, 0x100, 0));
+        let program = Program::new(instructions, 0, 0);
+        let mut jit = Executor::new(program.clone());
+        let mut interpreted = Executor::new(program);
+        for result in [jit.run_fast(), interpreted.run()] {
+            assert!(matches!(
+                result,
+                Err(ExecutionError::InvalidMemoryAccess(Opcode::ECALL, 0x100))
+            ));
+        }
+        assert_eq!(jit.report, interpreted.report);
+        assert_eq!(jit.state.pc, 16);
+        assert_eq!(
+            (jit.state.clk, jit.state.global_clk),
+            (interpreted.state.clk, interpreted.state.global_clk)
+        );
+        assert_eq!(jit.register(Register::X21), 7);
+    }
+
+    #[test]
+    fn test_aot() {
+        // Sum a countdown from 5, then jump to the end.
+        let program = Program::new(
+            vec![
+                Instruction::new(Opcode::ADD, 10, 0, 5, false, true),
+                Instruction::new(Opcode::ADD, 11, 11, 10, false, false),
+                Instruction::new(Opcode::ADD, 10, 10, u32::MAX, false, true),
+                Instruction::new(Opcode::BNE, 10, 0, 8u32.wrapping_neg(), false, true),
+                Instruction::new(Opcode::JAL, 0, 4, 0, true, true),
+            ],
+            0,
+            0,
+        );
+        let source = compile_to_rust(&program);
+        assert!(source.contains(&format!(
+            "pub const DIGEST: [u8; 32] = {:?};",
+            program.digest()
+        )));
+        let dispatcher = concat!(
+            "pub fn block(pc: u32) -> Option<AotBlock> {\n",
+            "    Some(match pc {\n",
+            "        0x00000000 => AotBlock { len: 4, execute: block_00000000 },\n",
+            "        0x00000004 => AotBlock { len: 3, execute: block_00000004 },\n",
+            "        0x00000010 => AotBlock { len: 1, execute: block_00000010 },\n",
+            "        _ => return None,\n",
+            "    })\n",
+            "}\n",
+        );
+        assert!(source.contains(dispatcher), "{source}");
+        let loop_block = concat!(
+            "fn block_00000004(rt: &mut Executor<'_>) -> Result<u32, (u32, ExecutionError)> {\n",
+            "    // 0x00000004: add        %x11       %x11       %x10\n",
+            "    rt.state.registers[11] = alu(Opcode::ADD, rt.state.registers[11], ",
+            "rt.state.registers[10]);\n",
+            "    // 0x00000008: add        %x10       %x10       -1\n",
+            "    rt.state.registers[10] = alu(Opcode::ADD, rt.state.registers[10], 0xffffffff);\n",
+            "    // 0x0000000c: bne        %x10       %x0        -8\n",
+            "    Ok(if rt.state.registers[10] != rt.state.registers[0] { 0x00000004 } ",
+            "else { 0x00000010 })\n",
+            "}\n",
+        );
+        assert!(source.contains(loop_block), "{source}");
+        // A block that never touches the executor doesn't use its argument.
+        let jump_block = concat!(
+            "fn block_00000010(_rt: &mut Executor<'_>) -> Result<u32, (u32, ExecutionError)> {\n",
+            "    // 0x00000010: jal        %x0        4          0\n",
+            "    Ok(0x00000014)\n",
+            "}\n",
+        );
+        assert!(source.contains(jump_block), "{source}");
+    }
+
+    /// The `BigUint` formulas that the Fp syscalls of a field used for `x op y` when `x` and `y`
+    /// have one element, and that its Fp2 syscalls used when they have two, for the add, sub and
+    /// mul operations in that order.
+    fn fptower_reference<P: sp1_curves::weierstrass::FpOpField>(
+        op: usize,
+        x: &[num::BigUint],
+        y: &[num::BigUint],
+    ) -> Vec<num::BigUint> {
+        use num::BigUint;
+
+        // The Fp formulas reduced their inputs first. The Fp2 formulas didn't, which gives the
+        // same results except that they could underflow when subtracting an unreduced value, so
+        // their inputs are reduced here too.
+        let modulus = &BigUint::from_bytes_le(P::MODULUS);
+        let x = x.iter().map(|value| value % modulus).collect::<Vec<_>>();
+        let y = y.iter().map(|value| value % modulus).collect::<Vec<_>>();
+        match (&x[..], &y[..], op) {
+            ([a], [b], 0) => vec![(a + b) % modulus],
+            ([a], [b], 1) => vec![((a + modulus) - b) % modulus],
+            ([a], [b], _) => vec![(a * b) % modulus],
+            ([ac0, ac1], [bc0, bc1], 0) => vec![(ac0 + bc0) % modulus, (ac1 + bc1) % modulus],
+            ([ac0, ac1], [bc0, bc1], 1) => vec![
+                (ac0 + modulus - bc0) % modulus,
+                (ac1 + modulus - bc1) % modulus,
+            ],
+            ([ac0, ac1], [bc0, bc1], _) => vec![
+                ((modulus + (ac0 * bc0) % modulus) - (ac1 * bc1) % modulus) % modulus,
+                ((ac0 * bc1) % modulus + (ac1 * bc0) % modulus) % modulus,
+            ],
+            _ => unreachable!(),
+        }
+    }
+
+    /// Run the Fp and Fp2 add, sub and mul syscalls of a field on edge cases and random values,
+    /// and compare the results with the `BigUint` formulas they replaced.
+    fn check_fptower_syscalls<P: sp1_curves::weierstrass::FpOpField>(
+        fp: [SyscallCode; 3],
+        fp2: [SyscallCode; 3],
+    ) {
+        use num::{BigUint, One, Zero};
+        use rand::Rng;
+        use sp1_curves::params::NumWords;
+        use typenum::Unsigned;
+
+        let modulus = &BigUint::from_bytes_le(P::MODULUS);
+        let n = <P as NumWords>::WordsFieldElement::USIZE;
+        let one = BigUint::one();
+        let mut rng = rand::thread_rng();
+        let mut values = vec![
+            BigUint::zero(),
+            one.clone(),
+            modulus - 2u32,
+            modulus - 1u32,
+            modulus >> 1,
+            (modulus >> 1) + 1u32,
+            // Values that aren't reduced modulo the field.
+            modulus.clone(),
+            modulus + 1u32,
+            (&one << (32 * n)) - 1u32,
+        ];
+        values.extend((0..6).map(|_| {
+            BigUint::from_slice(&(0..n).map(|_| rng.gen()).collect::<Vec<u32>>()) % modulus
+        }));
+        let to_words = |value: &BigUint| {
+            let mut words = value.to_u32_digits();
+            words.resize(n, 0);
+            words
+        };
+
+        // The first case has a zero c0 in its Fp2 sum.
+        let mut cases = vec![(
+            [BigUint::zero(), one.clone()],
+            [BigUint::zero(), BigUint::zero()],
+        )];
+        for (i, a) in values.iter().enumerate() {
+            for b in &values {
+                let c = &values[rng.gen_range(0..values.len())];
+                let d = &values[(i + 1) % values.len()];
+                cases.push(([a.clone(), c.clone()], [b.clone(), d.clone()]));
+            }
+        }
+
+        // Each call keeps x and y in consecutive slots of two field elements, and the Fp
+        // syscalls only use the first element of each.
+        let x_ptr = |call: usize| 0x10_0000 + 16 * (n * call) as u32;
+        let y_ptr = |call: usize| x_ptr(call) + 8 * n as u32;
+        let mut program = Program::new(Vec::new(), 0, 0);
+        let mut calls = Vec::new();
+        for (is_fp2, syscalls) in [fp, fp2].into_iter().enumerate() {
+            for (op, syscall) in syscalls.into_iter().enumerate() {
+                for (case, (x, y)) in cases.iter().enumerate() {
+                    let call = calls.len();
+                    for (ptr, value) in [(x_ptr(call), x), (y_ptr(call), y)] {
+                        let words = value.iter().flat_map(to_words);
+                        for (j, word) in words.enumerate() {
+                            program.memory_image.insert(ptr + 4 * j as u32, word);
+                        }
+                    }
+                    program.instructions.extend([
+                        Instruction::new(Opcode::ADD, 5, 0, syscall as u32, false, true),
+                        Instruction::new(Opcode::ADD, 10, 0, x_ptr(call), false, true),
+                        Instruction::new(Opcode::ADD, 11, 0, y_ptr(call), false, true),
+                        Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+                    ]);
+                    calls.push((is_fp2 == 1, op, case));
+                }
+            }
+        }
+        let mut runtime = Executor::new(program);
+        runtime.run_fast().unwrap();
+
+        let mut results = Vec::new();
+        for (call, &(is_fp2, op, case)) in calls.iter().enumerate() {
+            let (x, y) = &cases[case];
+            let (expected, syscall) = if is_fp2 {
+                let result = fptower_reference::<P>(op, x, y)
+                    .iter()
+                    .flat_map(to_words)
+                    .collect();
+                (result, fp2[op])
+            } else {
+                (
+                    to_words(&fptower_reference::<P>(op, &x[..1], &y[..1])[0]),
+                    fp[op],
+                )
+            };
+            let result = (0..expected.len() as u32)
+                .map(|j| runtime.word(x_ptr(call) + 4 * j))
+                .collect::<Vec<_>>();
+            assert_eq!(result, expected, "{syscall:?} of {x:?} and {y:?}");
+            results.push(result);
+        }
+
+        // The formulas used to chain the digits of c0 and c1 without padding c0, so c1 landed
+        // in the words of c0 when c0 was short. The output changed on purpose to always keep c1
+        // in the second half.
+        let sum = &results[3 * cases.len()];
+        let mut old = [BigUint::zero(), one]
+            .iter()
+            .flat_map(BigUint::to_u32_digits)
+            .collect::<Vec<_>>();
+        old.resize(2 * n, 0);
+        assert_eq!(sum[n], 1);
+        assert_ne!(*sum, old);
+    }
+
+    #[test]
+    fn test_fptower_syscalls() {
+        use sp1_curves::weierstrass::{bls12_381::Bls12381BaseField, bn254::Bn254BaseField};
+
+        check_fptower_syscalls::<Bn254BaseField>(
+            [
+                SyscallCode::BN254_FP_ADD,
+                SyscallCode::BN254_FP_SUB,
+                SyscallCode::BN254_FP_MUL,
+            ],
+            [
+                SyscallCode::BN254_FP2_ADD,
+                SyscallCode::BN254_FP2_SUB,
+                SyscallCode::BN254_FP2_MUL,
+            ],
+        );
+        check_fptower_syscalls::<Bls12381BaseField>(
+            [
+                SyscallCode::BLS12381_FP_ADD,
+                SyscallCode::BLS12381_FP_SUB,
+                SyscallCode::BLS12381_FP_MUL,
+            ],
+            [
+                SyscallCode::BLS12381_FP2_ADD,
+                SyscallCode::BLS12381_FP2_SUB,
+                SyscallCode::BLS12381_FP2_MUL,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_fuse_instructions() {
+        let program = Program::new(
+            vec![
+                // A constant, stored to memory and loaded back relative to the program counter.
+                Instruction::new(Opcode::ADD, 5, 0, 0x1234_5000, true, true),
+                Instruction::new(Opcode::ADD, 5, 5, 0x678, false, true),
+                Instruction::new(Opcode::ADD, 7, 0, 0x20000, false, true),
+                Instruction::new(Opcode::SW, 5, 7, 0, false, true),
+                Instruction::new(Opcode::AUIPC, 6, 0x20000, 0x20000, true, true),
+                Instruction::new(Opcode::LW, 8, 6, 16u32.wrapping_neg(), false, true),
+                // A countdown from 10, looping through a call.
+                Instruction::new(Opcode::ADD, 9, 0, 10, false, true),
+                Instruction::new(Opcode::SLT, 10, 0, 9, false, false),
+                Instruction::new(Opcode::BEQ, 10, 0, 16, false, true),
+                Instruction::new(Opcode::ADD, 9, 9, u32::MAX, false, true),
+                Instruction::new(Opcode::AUIPC, 1, 0, 0, true, true),
+                Instruction::new(Opcode::JALR, 1, 1, 12u32.wrapping_neg(), false, true),
+                // A misaligned load relative to the program counter.
+                Instruction::new(Opcode::AUIPC, 11, 0x20000, 0x20000, true, true),
+                Instruction::new(Opcode::LW, 12, 11, 1, false, true),
+            ],
+            0,
+            0,
+        );
+
+        let fused = Executor::with_context(
+            program.clone(),
+            SP1Context::builder().fuse_instructions().build(),
+        )
+        .unwrap();
+        assert!(matches!(fused.ops[0], Op::Fused(Fused::Constants { .. })));
+        assert!(matches!(fused.ops[4], Op::Fused(Fused::AuipcLoad { .. })));
+        assert!(matches!(
+            fused.ops[7],
+            Op::Fused(Fused::CompareBranch { .. })
+        ));
+        assert!(matches!(fused.ops[10], Op::Fused(Fused::Call { .. })));
+        assert!(matches!(fused.ops[11], Op::Jalr { .. }));
+
+        // The state after each block is the same with and without fusion, including when blocks
+        // are cut short in the middle of a pair by the end of a shard.
+        let max_syscall_cycles = u64::from(fused.max_syscall_cycles);
+        for shard_size in [None, Some(max_syscall_cycles / 4 + 3)] {
+            let mut executors = [true, false].map(|fuse| {
+                let mut builder = SP1Context::builder();
+                if fuse {
+                    builder.fuse_instructions();
+                }
+                if let Some(shard_size) = shard_size {
+                    builder.shard_size(shard_size);
+                }
+                let mut executor =
+                    Executor::with_context(program.clone(), builder.build()).unwrap();
+                executor.print_report = true;
+                executor.initialize();
+                executor
+            });
+            loop {
+                let results = executors
+                    .each_mut()
+                    .map(Executor::execute_block::<SIMPLE, false>);
+                let [fused, unfused] = &executors;
+                assert_eq!(fused.registers(), unfused.registers());
+                assert_eq!(fused.state.pc, unfused.state.pc);
+                assert_eq!(fused.state.clk, unfused.state.clk);
+                assert_eq!(fused.state.global_clk, unfused.state.global_clk);
+                assert_eq!(fused.state.current_shard, unfused.state.current_shard);
+                assert_eq!(
+                    fused.state.memory.get(&0x20000),
+                    unfused.state.memory.get(&0x20000)
+                );
+                assert_eq!(fused.report.opcode_counts, unfused.report.opcode_counts);
+                match results {
+                    [Ok(false), Ok(false)] => {}
+                    [Err(ExecutionError::InvalidMemoryAccess(Opcode::LW, 0x20031)), Err(_)] => {
+                        break;
+                    }
+                    results => panic!("unexpected results: {results:?}"),
+                }
+            }
+            assert_eq!(executors[0].register(Register::X8), 0x1234_5678);
+            assert_eq!(executors[0].register(Register::X1), 48);
+            assert_eq!(executors[0].state.pc, 52);
+        }
+    }
+
+    #[test]
+    fn test_uint256_mul() {
+        use num::{BigUint, One, Zero};
+        use rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        let random = |rng: &mut rand::rngs::ThreadRng, bits: usize| {
+            BigUint::from_slice(&rng.gen::<[u32; 8]>()) >> (256 - bits)
+        };
+        let one = BigUint::one();
+        let mut values = vec![
+            BigUint::zero(),
+            one.clone(),
+            BigUint::from(u64::MAX),
+            &one << 64,
+            (&one << 128) + 1u32,
+            (&one << 192) - 1u32,
+            &one << 255,
+            (&one << 256) - 1u32,
+        ];
+        values.extend((1..=256).step_by(15).map(|bits| random(&mut rng, bits)));
+        let mut cases = Vec::new();
+        for x in &values {
+            for y in &values {
+                cases.push((
+                    x.clone(),
+                    y.clone(),
+                    values[rng.gen_range(0..values.len())].clone(),
+                ));
+            }
+        }
+        cases.extend((0..200).map(|_| {
+            let modulus_bits = rng.gen_range(1..=256);
+            (
+                random(&mut rng, 256),
+                random(&mut rng, 256),
+                random(&mut rng, modulus_bits),
+            )
+        }));
+
+        // Each case keeps x, y and the modulus in consecutive 32-byte slots.
+        let to_words = |value: &BigUint| {
+            let mut words = value.to_u32_digits();
+            words.resize(8, 0);
+            words
+        };
+        let mut program = Program::new(Vec::new(), 0, 0);
+        for (i, (x, y, modulus)) in cases.iter().enumerate() {
+            let x_ptr = 0x10_0000 + 96 * i as u32;
+            for (slot, value) in [x, y, modulus].into_iter().enumerate() {
+                for (j, word) in to_words(value).into_iter().enumerate() {
+                    program
+                        .memory_image
+                        .insert(x_ptr + 32 * slot as u32 + 4 * j as u32, word);
+                }
+            }
+            program.instructions.extend([
+                Instruction::new(
+                    Opcode::ADD,
+                    5,
+                    0,
+                    SyscallCode::UINT256_MUL as u32,
+                    false,
+                    true,
+                ),
+                Instruction::new(Opcode::ADD, 10, 0, x_ptr, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, x_ptr + 32, false, true),
+                Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            ]);
+        }
+        let mut runtime = Executor::new(program);
+        runtime.run_fast().unwrap();
+
+        // The result must match the reduction of the product with `BigUint`, where a zero modulus
+        // stands for 2^256.
+        for (i, (x, y, modulus)) in cases.iter().enumerate() {
+            let x_ptr = 0x10_0000 + 96 * i as u32;
+            let modulus = if modulus.is_zero() {
+                &one << 256
+            } else {
+                modulus.clone()
+            };
+            let expected = to_words(&(x * y % &modulus));
+            let result = (0..8)
+                .map(|j| runtime.word(x_ptr + 4 * j))
+                .collect::<Vec<_>>();
+            assert_eq!(result, expected, "{x} * {y} mod {modulus}");
+        }
+    }
+
+    #[test]
+    fn test_weierstrass_syscalls() {
+        use sp1_curves::{
+            weierstrass::{
+                bls12_381::{bls12381_decompress, Bls12381Parameters},
+                bn254::Bn254Parameters,
+                secp256k1::{secp256k1_decompress, Secp256k1Parameters},
+                SwCurve, WeierstrassParameters,
+            },
+            AffinePoint,
+        };
+
+        /// Run the add, double and decompress syscalls of a curve on multiples of its generator
+        /// and compare the results with the `BigUint` reference.
+        fn check<E: WeierstrassParameters>(
+            add: SyscallCode,
+            double: SyscallCode,
+            decompress: Option<(SyscallCode, DecompressFn<E>)>,
+        ) {
+            let p = SwCurve::<E>::generator().sw_double();
+            let q = p.sw_double().sw_add(&SwCurve::<E>::generator());
+            let (p_words, q_words) = (p.to_words_le(), q.to_words_le());
+            let half = p_words.len() / 2;
+            let (p_ptr, q_ptr, double_ptr, decompress_ptr) = (0x10000, 0x11000, 0x12000, 0x13000);
+
+            let mut program = Program::new(Vec::new(), 0, 0);
+            for (ptr, words) in [
+                (p_ptr, &p_words[..]),
+                (q_ptr, &q_words[..]),
+                (double_ptr, &p_words[..]),
+                (decompress_ptr + 4 * half as u32, &p_words[..half]),
+            ] {
+                for (i, word) in words.iter().enumerate() {
+                    program.memory_image.insert(ptr + 4 * i as u32, *word);
+                }
+            }
+            let mut calls = vec![(add, p_ptr, q_ptr), (double, double_ptr, 0)];
+            calls.extend(decompress.map(|(code, _)| (code, decompress_ptr, 1)));
+            for (code, arg1, arg2) in calls {
+                program.instructions.extend([
+                    Instruction::new(Opcode::ADD, 5, 0, code as u32, false, true),
+                    Instruction::new(Opcode::ADD, 10, 0, arg1, false, true),
+                    Instruction::new(Opcode::ADD, 11, 0, arg2, false, true),
+                    Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+                ]);
+            }
+            let mut runtime = Executor::new(program);
+            runtime.run_fast().unwrap();
+
+            let mut read = |ptr: u32, len: usize| {
+                (0..len as u32)
+                    .map(|i| runtime.word(ptr + 4 * i))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(read(p_ptr, 2 * half), p.sw_add(&q).to_words_le());
+            assert_eq!(read(double_ptr, 2 * half), p.sw_double().to_words_le());
+            if let Some((_, decompress)) = decompress {
+                let mut x_bytes_be = p.x.to_bytes_le();
+                x_bytes_be.resize(4 * half, 0);
+                x_bytes_be.reverse();
+                let expected = decompress(&x_bytes_be, 1).to_words_le();
+                assert_eq!(read(decompress_ptr, half), expected[half..]);
+            }
+        }
+        type DecompressFn<E> = fn(&[u8], u32) -> AffinePoint<SwCurve<E>>;
+
+        check::<Secp256k1Parameters>(
+            SyscallCode::SECP256K1_ADD,
+            SyscallCode::SECP256K1_DOUBLE,
+            Some((SyscallCode::SECP256K1_DECOMPRESS, secp256k1_decompress)),
+        );
+        check::<Bn254Parameters>(SyscallCode::BN254_ADD, SyscallCode::BN254_DOUBLE, None);
+        check::<Bls12381Parameters>(
+            SyscallCode::BLS12381_ADD,
+            SyscallCode::BLS12381_DOUBLE,
+            Some((SyscallCode::BLS12381_DECOMPRESS, bls12381_decompress)),
+        );
+    }
+
+    #[test]
+    fn test_bulk_memory_access() {
+        use crate::syscalls::SyscallContext;
+
+        // The words straddle the boundary between the first two pages, some of them in memory,
+        // some of them only in the uninitialized memory, and some of them in neither.
+        let (addr, len) = (0x11000 - 4 * 20, 40);
+        let executor = |mode| {
+            let mut runtime = Executor::new(Program::new(Vec::new(), 0, 0));
+            runtime.executor_mode = mode;
+            for i in (0..len).step_by(3) {
+                runtime.state.memory.insert(addr + 4 * i, 100 + i);
+            }
+            for i in (0..len).step_by(5) {
+                runtime
+                    .state
+                    .uninitialized_memory
+                    .insert(addr + 4 * i, vec![200 + i])
+                    .unwrap();
+            }
+            runtime
+        };
+        let state = |runtime: &Executor, records: (Vec<u8>, Vec<u8>, Vec<u32>)| {
+            let mut local_memory_access = runtime.local_memory_access.iter().collect::<Vec<_>>();
+            local_memory_access.sort_by_key(|(addr, _)| **addr);
+            (
+                records,
+                runtime
+                    .state
+                    .memory
+                    .iter()
+                    .map(|(addr, value)| (addr, *value))
+                    .collect::<Vec<_>>(),
+                bincode::serialize(&local_memory_access).unwrap(),
+            )
+        };
+
+        for mode in [ExecutorMode::Simple, ExecutorMode::Trace] {
+            let mut bulk = executor(mode);
+            let records = {
+                let mut rt = SyscallContext::new(&mut bulk);
+                let peeked = rt.slice_unsafe(addr - 8, len as usize);
+                let (read_records, _) = rt.mr_slice(addr, len as usize);
+                rt.clk += 1;
+                let values = (0..len + 4).map(|i| 300 + i).collect::<Vec<_>>();
+                let write_records = rt.mw_slice(addr - 8, &values);
+                let local_memory_access = rt.postprocess();
+                rt.rt
+                    .local_memory_access
+                    .extend(local_memory_access.into_iter().map(|e| (e.addr, e)));
+                (
+                    bincode::serialize(&read_records).unwrap(),
+                    bincode::serialize(&write_records).unwrap(),
+                    peeked,
+                )
+            };
+            let bulk_state = state(&bulk, records);
+
+            let mut single = executor(mode);
+            let records = {
+                let mut rt = SyscallContext::new(&mut single);
+                let peeked = (0..len).map(|i| rt.word_unsafe(addr - 8 + 4 * i)).collect();
+                let read_records = (0..len).map(|i| rt.mr(addr + 4 * i).0).collect::<Vec<_>>();
+                rt.clk += 1;
+                let write_records = (0..len + 4)
+                    .map(|i| rt.mw(addr - 8 + 4 * i, 300 + i))
+                    .collect::<Vec<_>>();
+                let local_memory_access = rt.postprocess();
+                rt.rt
+                    .local_memory_access
+                    .extend(local_memory_access.into_iter().map(|e| (e.addr, e)));
+                (
+                    bincode::serialize(&read_records).unwrap(),
+                    bincode::serialize(&write_records).unwrap(),
+                    peeked,
+                )
+            };
+            assert_eq!(bulk_state, state(&single, records), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn test_hint_read_checkpoint() {
+        let hint_read = |ptr: u32, len: u32| {
+            [
+                Instruction::new(
+                    Opcode::ADD,
+                    5,
+                    0,
+                    SyscallCode::HINT_READ as u32,
+                    false,
+                    true,
+                ),
+                Instruction::new(Opcode::ADD, 10, 0, ptr, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, len, false, true),
+                Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            ]
+        };
+        let load =
+            |rd: u8, addr: u32| Instruction::new(Opcode::LW, rd.into(), 0, addr, false, true);
+        let mut instructions = hint_read(0x10100, 12).to_vec();
+        instructions.extend((0..8).map(|i| load(20 + i, 0x10100 + 4 * (u32::from(i) % 2))));
+        instructions.extend(hint_read(0x10200, 5));
+        instructions.extend((0..8).map(|i| load(20 + i, 0x10200 + 4 * (u32::from(i) % 2))));
+        instructions.push(Instruction::new(Opcode::SW, 20, 0, 0x10104, false, true));
+        instructions.extend((0..8).map(|i| load(20 + i, 0x10104 - 4 * (u32::from(i) % 2))));
+        let program = Program::new(instructions, 0, 0);
+        let context = || {
+            SP1Context::builder()
+                .shard_size(16)
+                .shard_batch_size(2)
+                .build()
+        };
+        let executor = || {
+            let mut runtime = Executor::with_context(program.clone(), context()).unwrap();
+            runtime.write_stdin_slice(&[1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+            runtime.write_stdin_slice(&[4, 0, 0, 0, 5]);
+            runtime
+        };
+
+        // Each batch is re-executed from its checkpoint, which must not hold the regions the batch
+        // hints.
+        let expected_records = execute_records(&mut executor());
+        let mut runtime = executor();
+        let mut records = Vec::new();
+        runtime
+            .execute_record_parallel(|record| records.push(record))
+            .unwrap();
+        assert!(records.len() > 4);
+        assert_eq!(
+            bincode::serialize(&records).unwrap(),
+            bincode::serialize(&expected_records).unwrap()
+        );
+        assert_eq!(runtime.register(Register::X26), 4);
+        assert_eq!(runtime.register(Register::X27), 1);
+        assert_eq!(
+            runtime
+                .state
+                .uninitialized_memory
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![
+                (0x10100, 1),
+                (0x10104, 2),
+                (0x10108, 3),
+                (0x10200, 4),
+                (0x10204, 5)
+            ]
+        );
+
+        // Hinting an address twice is an error.
+        let mut uninitialized_memory = runtime.state.uninitialized_memory.clone();
+        assert_eq!(
+            uninitialized_memory.insert(0x100f8, vec![0; 3]),
+            Err(AlreadyInitialized(0x10100))
+        );
+        assert_eq!(
+            uninitialized_memory.insert(0x10204, vec![0]),
+            Err(AlreadyInitialized(0x10204))
+        );
+        assert_eq!(uninitialized_memory.insert(0x100f8, vec![0; 2]), Ok(()));
+        assert_eq!(uninitialized_memory.get(0x100fc), Some(0));
+        assert_eq!(uninitialized_memory.get(0x1010c), None);
+    }
+
+    #[test]
+    fn test_custom_syscalls() {
+        use std::sync::Arc;
+
+        use crate::syscalls::{Syscall, SyscallContext};
+
+        /// Adds `arg2` to the word at `arg1`, returning its previous value.
+        struct AddSyscall;
+
+        impl Syscall<u32> for AddSyscall {
+            fn execute(
+                &self,
+                ctx: &mut SyscallContext,
+                _: u32,
+                arg1: u32,
+                arg2: u32,
+            ) -> Result<Option<u32>, ExecutionError> {
+                let (_, value) = ctx.mr(arg1);
+                ctx.clk += 1;
+                ctx.mw(arg1, value + arg2);
+                Ok(Some(value))
+            }
+
+            fn num_extra_cycles(&self) -> u32 {
+                2
+            }
+        }
+
+        /// A syscall that does nothing.
+        struct NoopSyscall;
+
+        impl Syscall for NoopSyscall {
+            fn execute(
+                &self,
+                _: &mut SyscallContext,
+                _: SyscallCode,
+                _: u32,
+                _: u32,
+            ) -> Result<Option<u32>, ExecutionError> {
+                Ok(None)
+            }
+        }
+
+        const ADD: u32 = 0x00_00_01_40;
+        let ecall = |number: u32, arg1: u32, arg2: u32| {
+            [
+                Instruction::new(Opcode::ADD, 5, 0, number, false, true),
+                Instruction::new(Opcode::ADD, 10, 0, arg1, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, arg2, false, true),
+                Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            ]
+        };
+        let mut instructions = ecall(ADD, 0x10000, 3).to_vec();
+        instructions.extend(ecall(SyscallCode::SHA_EXTEND as u32, 0x10100, 0));
+        instructions.extend(ecall(ADD, 0x10000, 4));
+        instructions.push(Instruction::new(Opcode::LW, 29, 0, 0x10000, false, true));
+        let mut program = Program::new(instructions, 0, 0);
+        program.memory_image.insert(0x10000, 5);
+
+        // The stub replaces the default `SHA_EXTEND`, the syscall with the most extra cycles.
+        let context = SP1Context::builder()
+            .custom_syscall(ADD, Arc::new(AddSyscall))
+            .syscall(SyscallCode::SHA_EXTEND, Arc::new(NoopSyscall))
+            .build();
+        let mut runtime = Executor::with_context(program.clone(), context).unwrap();
+        assert_eq!(runtime.max_syscall_cycles, 2);
+        let (records, done) = runtime.execute_record().unwrap();
+        assert!(done);
+        assert_eq!(runtime.register(Register::X5), 8);
+        assert_eq!(runtime.register(Register::X29), 12);
+        let syscall_ids = records[0]
+            .syscall_events
+            .iter()
+            .map(|event| event.syscall_id)
+            .collect::<Vec<_>>();
+        assert_eq!(syscall_ids, vec![0x40, 0x05, 0x40]);
+        assert_eq!(runtime.state.syscall_counts.values().sum::<u64>(), 1);
+
+        // The executors that trace in parallel have the same syscalls.
+        let context = || {
+            SP1Context::builder()
+                .shard_size(2)
+                .shard_batch_size(2)
+                .custom_syscall(ADD, Arc::new(AddSyscall))
+                .syscall(SyscallCode::SHA_EXTEND, Arc::new(NoopSyscall))
+                .build()
+        };
+        let expected_records =
+            execute_records(&mut Executor::with_context(program.clone(), context()).unwrap());
+        let mut runtime = Executor::with_context(program.clone(), context()).unwrap();
+        let mut records = Vec::new();
+        runtime
+            .execute_record_parallel(|record| records.push(record))
+            .unwrap();
+        assert_eq!(records.len(), 7);
+        assert_eq!(records.len(), expected_records.len());
+        for (record, expected_record) in records.iter().zip(&expected_records) {
+            assert_eq!(
+                bincode::serialize(record).unwrap(),
+                bincode::serialize(expected_record).unwrap()
+            );
+        }
+        assert_eq!(runtime.register(Register::X29), 12);
+
+        // Without the default syscalls, only the registered ones are available.
+        let context = SP1Context::builder()
+            .without_default_syscalls()
+            .custom_syscall(ADD, Arc::new(AddSyscall))
+            .build();
+        let mut runtime = Executor::with_context(program, context).unwrap();
+        assert!(matches!(
+            runtime.run_fast(),
+            Err(ExecutionError::UnsupportedSyscall(id)) if id == SyscallCode::SHA_EXTEND as u32
+        ));
+        assert_eq!(runtime.state.memory.get(&0x10000), Some(&8));
+    }
+
+    #[test]
+    fn test_syscall_errors() {
+        use sp1_curves::{
+            weierstrass::{secp256k1::Secp256k1Parameters, SwCurve},
+            AffinePoint, EllipticCurve,
+        };
+
+        let ecall = |number: u32, arg1: u32, arg2: u32| {
+            [
+                Instruction::new(Opcode::ADD, 5, 0, number, false, true),
+                Instruction::new(Opcode::ADD, 10, 0, arg1, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, arg2, false, true),
+                Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            ]
+        };
+        let run = |ecalls: &[[Instruction; 4]], stdin: &[&[u8]]| {
+            let program = Program::new(ecalls.concat(), 0, 0);
+            let mut runtime = Executor::new(program);
+            for input in stdin {
+                runtime.write_stdin_slice(input);
+            }
+            runtime.run_fast().unwrap_err().to_string()
+        };
+
+        // Run a curve syscall on points in the memory image.
+        let run_on_points = |code: u32, p: &[u32], q: &[u32]| {
+            let mut program = Program::new(ecall(code, 0x10000, 0x11000).to_vec(), 0, 0);
+            for (ptr, words) in [(0x10000, p), (0x11000, q)] {
+                for (i, word) in words.iter().enumerate() {
+                    program.memory_image.insert(ptr + 4 * i as u32, *word);
+                }
+            }
+            Executor::new(program).run_fast().unwrap_err().to_string()
+        };
+        let add = SyscallCode::SECP256K1_ADD as u32;
+        let double = SyscallCode::SECP256K1_DOUBLE as u32;
+        let decompress = SyscallCode::SECP256K1_DECOMPRESS as u32;
+        let g = SwCurve::<Secp256k1Parameters>::generator();
+        let neg_g = SwCurve::<Secp256k1Parameters>::ec_neg(&g).to_words_le();
+        let zero_y = AffinePoint::<SwCurve<Secp256k1Parameters>>::new(g.x.clone(), 0u32.into());
+        let (g, zero_y) = (g.to_words_le(), zero_y.to_words_le());
+
+        // A bad guest stops its execution with an error instead of panicking, including on points
+        // the curve formulas don't cover.
+        let hint_read = SyscallCode::HINT_READ as u32;
+        let cases = [
+            (
+                run(&[ecall(0x1234, 0, 0)], &[]),
+                ExecutionError::UnsupportedSyscall(0x1234),
+            ),
+            (
+                run(&[ecall(SyscallCode::HINT_LEN as u32, 0, 0)], &[]),
+                ExecutionError::StdinExhausted(0, 0),
+            ),
+            (
+                run(&[ecall(hint_read, 0x10000, 4)], &[&[0; 8]]),
+                ExecutionError::HintLengthMismatch(4, 8),
+            ),
+            (
+                run(&[ecall(hint_read, 0x10002, 8)], &[&[0; 8]]),
+                ExecutionError::MisalignedSyscallPointer(hint_read, 0x10002),
+            ),
+            (
+                run(
+                    &[ecall(hint_read, 0x10000, 8), ecall(hint_read, 0x10004, 4)],
+                    &[&[0; 8], &[0; 4]],
+                ),
+                ExecutionError::DoubleInitialization(0x10004),
+            ),
+            (
+                run(
+                    &[ecall(SyscallCode::UINT256_MUL as u32, 0x10000, 0x10101)],
+                    &[],
+                ),
+                ExecutionError::MisalignedSyscallPointer(SyscallCode::UINT256_MUL as u32, 0x10101),
+            ),
+            (
+                run(
+                    &[ecall(SyscallCode::KECCAK_PERMUTE as u32, 0x10000, 1)],
+                    &[],
+                ),
+                ExecutionError::InvalidSyscallArgument(SyscallCode::KECCAK_PERMUTE as u32, 1),
+            ),
+            (
+                run_on_points(add, &g, &g),
+                ExecutionError::InvalidSyscallArgument(add, 0x11000),
+            ),
+            (
+                run_on_points(add, &g, &neg_g),
+                ExecutionError::InvalidSyscallArgument(add, 0x11000),
+            ),
+            (
+                run_on_points(double, &zero_y, &[]),
+                ExecutionError::InvalidSyscallArgument(double, 0x10000),
+            ),
+            (
+                run(&[ecall(add, 0x10000, 0x11002)], &[]),
+                ExecutionError::MisalignedSyscallPointer(add, 0x11002),
+            ),
+            (
+                run(&[ecall(decompress, 0x10000, 2)], &[]),
+                ExecutionError::InvalidSyscallArgument(decompress, 2),
+            ),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(error, expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_syscall_pointer_errors() {
+        // Each syscall writes 32 bytes if it writes to a file descriptor.
+        let run = |number: u32, arg1: u32, arg2: u32| {
+            let program = Program::new(
+                vec![
+                    Instruction::new(Opcode::ADD, 5, 0, number, false, true),
+                    Instruction::new(Opcode::ADD, 10, 0, arg1, false, true),
+                    Instruction::new(Opcode::ADD, 11, 0, arg2, false, true),
+                    Instruction::new(Opcode::ADD, 12, 0, 32, false, true),
+                    Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+                ],
+                0,
+                0,
+            );
+            let mut runtime = Executor::new(program);
+            runtime.write_stdin_slice(&[0; 8]);
+            runtime.run_fast().unwrap_err().to_string()
+        };
+
+        // Pointers into the registers or the reserved addresses, past the end of the address space
+        // or misaligned stop the execution instead of crashing the host.
+        let sha_extend = SyscallCode::SHA_EXTEND as u32;
+        let uint256_mul = SyscallCode::UINT256_MUL as u32;
+        let ed_decompress = SyscallCode::ED_DECOMPRESS as u32;
+        let cases = [
+            (run(sha_extend, 0x100, 0), 0x100),
+            (run(sha_extend, 0xffff_ff04, 0), 0xffff_ff04),
+            (run(uint256_mul, 0x10000, 0xffff_ffe0), 0xffff_ffe0),
+            (run(ed_decompress, 0xffff_ffe0, 0), 0xffff_ffe0),
+            (run(SyscallCode::HINT_READ as u32, 0x100, 8), 0x100),
+            (run(SyscallCode::WRITE as u32, 3, 0xffff_fff0), 0xffff_fff0),
+        ];
+        for (error, addr) in cases {
+            assert_eq!(
+                error,
+                ExecutionError::InvalidMemoryAccess(Opcode::ECALL, addr).to_string()
+            );
+        }
+        assert_eq!(
+            run(sha_extend, 0x10002, 0),
+            ExecutionError::MisalignedSyscallPointer(sha_extend, 0x10002).to_string()
+        );
+    }
 }
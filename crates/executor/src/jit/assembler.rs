@@ -0,0 +1,210 @@
+//! A minimal x86-64 assembler for the instructions emitted by the JIT.
+
+/// A general purpose register.
+///
+/// Only the registers used by the compiled code are listed. Unless noted otherwise, operations
+/// act on their low 32 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Reg {
+    Rax = 0,
+    Rcx = 1,
+    Rdx = 2,
+    Rbx = 3,
+    Rsi = 6,
+    Rdi = 7,
+    R8 = 8,
+    R12 = 12,
+}
+
+/// A two-operand integer operation, identified by its `op r/m32, r32` opcode.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum AluOp {
+    Add = 0x01,
+    Or = 0x09,
+    And = 0x21,
+    Sub = 0x29,
+    Xor = 0x31,
+    Cmp = 0x39,
+}
+
+/// A shift of a register by `cl`, identified by the extension of its `D3 /ext` opcode.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum ShiftOp {
+    Shl = 4,
+    Shr = 5,
+    Sar = 7,
+}
+
+/// A condition on the flags set by a comparison, identified by its condition code.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Cond {
+    Below = 0x2,
+    AboveOrEqual = 0x3,
+    Equal = 0x4,
+    NotEqual = 0x5,
+    Sign = 0x8,
+    Less = 0xC,
+    GreaterOrEqual = 0xD,
+}
+
+/// The register holding the address of the register file of the executor.
+pub(super) const REGISTERS: Reg = Reg::Rbx;
+
+/// The register holding the address of the executor.
+pub(super) const EXECUTOR: Reg = Reg::R12;
+
+/// Assembles the code of a single block, which is a function taking the address of the executor
+/// and returning a 64-bit value.
+pub(super) struct Assembler {
+    code: Vec<u8>,
+    /// The offsets of the 32-bit displacements of the jumps to the epilogue.
+    exits: Vec<usize>,
+}
+
+impl Assembler {
+    /// Start a block with its prologue, which keeps the address of the executor in [`EXECUTOR`]
+    /// and the address of its registers, `registers_offset` bytes into it, in [`REGISTERS`].
+    pub(super) fn new(registers_offset: u32) -> Self {
+        let mut asm = Self {
+            code: Vec::with_capacity(256),
+            exits: Vec::new(),
+        };
+        // push rbx; push r12; sub rsp, 8
+        //
+        // The stack is 16-byte aligned for the calls made by the block.
+        asm.emit(&[0x53, 0x41, 0x54, 0x48, 0x83, 0xEC, 0x08]);
+        asm.mov64(EXECUTOR, Reg::Rdi);
+        // lea rbx, [rdi + registers_offset]
+        asm.emit(&[0x48, 0x8D, 0x9F]);
+        asm.emit(&registers_offset.to_le_bytes());
+        asm
+    }
+
+    /// Finish the block with its epilogue, which returns the value of `rax`, and return its code.
+    pub(super) fn finish(mut self) -> Vec<u8> {
+        let epilogue = self.code.len();
+        for exit in std::mem::take(&mut self.exits) {
+            let rel = (epilogue - (exit + 4)) as u32;
+            self.code[exit..exit + 4].copy_from_slice(&rel.to_le_bytes());
+        }
+        // add rsp, 8; pop r12; pop rbx; ret
+        self.emit(&[0x48, 0x83, 0xC4, 0x08, 0x41, 0x5C, 0x5B, 0xC3]);
+        self.code
+    }
+
+    fn emit(&mut self, bytes: &[u8]) {
+        self.code.extend_from_slice(bytes);
+    }
+
+    /// Emit an instruction whose `ModRM` byte names the registers `reg` and `rm`, along with the
+    /// REX prefix they need.
+    fn emit_rr(&mut self, wide: bool, opcode: &[u8], reg: u8, rm: Reg) {
+        let rex = 0x40 | (u8::from(wide) << 3) | ((reg >> 3) << 2) | ((rm as u8) >> 3);
+        if rex != 0x40 {
+            self.code.push(rex);
+        }
+        self.emit(opcode);
+        self.code.push(0xC0 | ((reg & 7) << 3) | (rm as u8 & 7));
+    }
+
+    /// Emit an instruction whose `ModRM` byte names the register `reg` and the memory operand
+    /// `[REGISTERS + disp]`.
+    fn emit_rm(&mut self, opcode: &[u8], reg: Reg, disp: u8) {
+        if reg as u8 >= 8 {
+            self.code.push(0x44);
+        }
+        self.emit(opcode);
+        self.code
+            .extend_from_slice(&[0x40 | ((reg as u8 & 7) << 3) | REGISTERS as u8, disp]);
+    }
+
+    /// `mov dst, [REGISTERS + 4 * register]`
+    pub(super) fn load_register(&mut self, dst: Reg, register: u8) {
+        self.emit_rm(&[0x8B], dst, register * 4);
+    }
+
+    /// `mov [REGISTERS + 4 * register], src`
+    pub(super) fn store_register(&mut self, register: u8, src: Reg) {
+        self.emit_rm(&[0x89], src, register * 4);
+    }
+
+    /// `mov dst, imm`
+    pub(super) fn mov_imm(&mut self, dst: Reg, imm: u32) {
+        if dst as u8 >= 8 {
+            self.code.push(0x41);
+        }
+        self.code.push(0xB8 | (dst as u8 & 7));
+        self.emit(&imm.to_le_bytes());
+    }
+
+    /// `mov dst, src`
+    pub(super) fn mov(&mut self, dst: Reg, src: Reg) {
+        self.emit_rr(false, &[0x89], src as u8, dst);
+    }
+
+    /// `mov dst, src` on all 64 bits.
+    pub(super) fn mov64(&mut self, dst: Reg, src: Reg) {
+        self.emit_rr(true, &[0x89], src as u8, dst);
+    }
+
+    /// `op dst, src`
+    pub(super) fn alu(&mut self, op: AluOp, dst: Reg, src: Reg) {
+        self.emit_rr(false, &[op as u8], src as u8, dst);
+    }
+
+    /// `op dst, cl`
+    pub(super) fn shift(&mut self, op: ShiftOp, dst: Reg) {
+        self.emit_rr(false, &[0xD3], op as u8, dst);
+    }
+
+    /// `setcc dst; movzx dst, dst`, where `dst` is one of the first four registers.
+    pub(super) fn set(&mut self, cond: Cond, dst: Reg) {
+        self.emit_rr(false, &[0x0F, 0x90 | cond as u8], 0, dst);
+        self.emit_rr(false, &[0x0F, 0xB6], dst as u8, dst);
+    }
+
+    /// `cmovcc dst, src`
+    pub(super) fn cmov(&mut self, cond: Cond, dst: Reg, src: Reg) {
+        self.emit_rr(false, &[0x0F, 0x40 | cond as u8], dst as u8, src);
+    }
+
+    /// `imul dst, src`
+    pub(super) fn imul(&mut self, dst: Reg, src: Reg) {
+        self.emit_rr(false, &[0x0F, 0xAF], dst as u8, src);
+    }
+
+    /// `imul dst, src` on all 64 bits.
+    pub(super) fn imul64(&mut self, dst: Reg, src: Reg) {
+        self.emit_rr(true, &[0x0F, 0xAF], dst as u8, src);
+    }
+
+    /// `movsxd dst, src`, sign-extending the low 32 bits of `src` to 64 bits.
+    pub(super) fn sign_extend(&mut self, dst: Reg, src: Reg) {
+        self.emit_rr(true, &[0x63], dst as u8, src);
+    }
+
+    /// `shr dst, 32` on all 64 bits, moving the high half of `dst` to its low half.
+    pub(super) fn high_half(&mut self, dst: Reg) {
+        self.emit_rr(true, &[0xC1], ShiftOp::Shr as u8, dst);
+        self.code.push(32);
+    }
+
+    /// `mov rax, function; call rax`
+    pub(super) fn call(&mut self, function: usize) {
+        self.emit(&[0x48, 0xB8]);
+        self.emit(&(function as u64).to_le_bytes());
+        self.emit(&[0xFF, 0xD0]);
+    }
+
+    /// `jcc epilogue`
+    pub(super) fn exit_if(&mut self, cond: Cond) {
+        self.emit(&[0x0F, 0x80 | cond as u8]);
+        self.exits.push(self.code.len());
+        self.emit(&[0; 4]);
+    }
+
+    /// `test src, src` on all 64 bits.
+    pub(super) fn test64(&mut self, src: Reg) {
+        self.emit_rr(true, &[0x85], src as u8, src);
+    }
+}
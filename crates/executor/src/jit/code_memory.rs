@@ -0,0 +1,113 @@
+//! Executable memory for compiled code.
+
+use std::ptr::NonNull;
+
+/// The size of the regions of executable memory mapped at once.
+const REGION_SIZE: usize = 1 << 20;
+
+/// A region of memory mapped for code.
+struct Region {
+    ptr: NonNull<u8>,
+    size: usize,
+    used: usize,
+}
+
+/// Memory holding compiled code.
+///
+/// Code is appended to regions mapped with `mmap`, which are only writable while code is being
+/// copied into them and only executable otherwise.
+pub(super) struct CodeMemory {
+    regions: Vec<Region>,
+}
+
+// SAFETY: The regions are owned by the `CodeMemory` and never aliased mutably.
+unsafe impl Send for CodeMemory {}
+
+impl CodeMemory {
+    pub(super) const fn new() -> Self {
+        Self {
+            regions: Vec::new(),
+        }
+    }
+
+    /// Copy `code` into executable memory, returning its address, which is valid as long as
+    /// `self` is.
+    pub(super) fn install(&mut self, code: &[u8]) -> *const u8 {
+        let fits = self
+            .regions
+            .last()
+            .is_some_and(|region| region.size - region.used >= code.len());
+        if !fits {
+            self.regions.push(Region::map(code.len().max(REGION_SIZE)));
+        }
+        let region = self.regions.last_mut().unwrap();
+
+        // SAFETY: The code fits after the used part of the region, which is mapped and only
+        // executed once it's made read-only again.
+        unsafe {
+            region.protect(libc::PROT_READ | libc::PROT_WRITE);
+            let dst = region.ptr.as_ptr().add(region.used);
+            std::ptr::copy_nonoverlapping(code.as_ptr(), dst, code.len());
+            region.protect(libc::PROT_READ | libc::PROT_EXEC);
+            // Keep the code of each block 16-byte aligned.
+            region.used = (region.used + code.len())
+                .next_multiple_of(16)
+                .min(region.size);
+            dst
+        }
+    }
+}
+
+impl Region {
+    /// Map a new region of at least `size` bytes.
+    fn map(size: usize) -> Self {
+        // SAFETY: `sysconf` has no preconditions.
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let size = size.next_multiple_of(page_size);
+        // SAFETY: Mapping new anonymous memory doesn't affect any existing memory.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_EXEC,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            panic!(
+                "failed to map memory for compiled code: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+        Self {
+            ptr: NonNull::new(ptr.cast()).unwrap(),
+            size,
+            used: 0,
+        }
+    }
+
+    /// Change the protection of the region.
+    ///
+    /// # Safety
+    ///
+    /// The region must not be executed while it isn't executable.
+    unsafe fn protect(&mut self, prot: libc::c_int) {
+        if libc::mprotect(self.ptr.as_ptr().cast(), self.size, prot) != 0 {
+            panic!(
+                "failed to protect memory for compiled code: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        // SAFETY: The region was mapped by `Region::map` and its code is no longer referenced.
+        unsafe {
+            libc::munmap(self.ptr.as_ptr().cast(), self.size);
+        }
+    }
+}
@@ -0,0 +1,430 @@
+//! A just-in-time compiler of basic blocks to x86-64 code, used in [`ExecutorMode::Simple`].
+//!
+//! Each block is compiled the first time it's executed into a function that runs it on the
+//! register file of the executor. Memory accesses, divisions and `ECALL` call back into the
+//! executor, so that the compiled code stays small and behaves exactly like the interpreter. An
+//! `ECALL` ends its block, and runs its [`Syscall`] through the [`SyscallContext`] like the
+//! interpreter does.
+//!
+//! [`Syscall`]: crate::syscalls::Syscall
+//! [`SyscallContext`]: crate::syscalls::SyscallContext
+//!
+//! [`ExecutorMode::Simple`]: crate::ExecutorMode::Simple
+
+#[cfg(not(all(target_arch = "x86_64", unix)))]
+compile_error!("the `jit` feature is only supported on x86-64 Unix targets");
+
+mod assembler;
+mod code_memory;
+
+use std::{
+    any::Any,
+    mem::offset_of,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+};
+
+use enum_map::Enum;
+
+use crate::{
+    executor::SIMPLE,
+    ops::{alu, Op},
+    ExecutionError, ExecutionState, Executor, Instruction, Opcode, Register,
+};
+
+use assembler::{AluOp, Assembler, Cond, Reg, ShiftOp, EXECUTOR};
+use code_memory::CodeMemory;
+
+/// The compiled code of a block, which is called with the address of the executor.
+///
+/// It returns the program counter after the block, or [`FAULT`] if one of its instructions didn't
+/// complete, in which case the reason is left in [`Jit::fault`].
+type BlockFn = unsafe extern "sysv64" fn(*mut ()) -> u64;
+
+/// Returned by compiled code and by the functions it calls when an instruction doesn't complete.
+const FAULT: u64 = 1 << 63;
+
+/// A compiled block.
+#[derive(Clone, Copy)]
+pub(crate) struct Block {
+    entry: BlockFn,
+    /// The number of instructions in the block.
+    pub(crate) len: usize,
+    /// Whether the block ends with an `ECALL`, which accounts for the instructions before it.
+    ecall: bool,
+}
+
+/// An instruction of a compiled block that didn't complete.
+struct Fault {
+    /// The program counter of the instruction.
+    pc: u32,
+    /// Why the instruction didn't complete. Panics can't unwind through compiled code, so they
+    /// are resumed once it has returned.
+    result: Result<ExecutionError, Box<dyn Any + Send>>,
+}
+
+/// The compiled blocks of a program.
+pub(crate) struct Jit {
+    code: CodeMemory,
+    /// The compiled block starting at each instruction, if it was compiled.
+    blocks: Vec<Option<Block>>,
+    /// The instruction that didn't complete in the last block that was executed.
+    fault: Option<Fault>,
+}
+
+impl Jit {
+    pub(crate) const fn new() -> Self {
+        Self {
+            code: CodeMemory::new(),
+            blocks: Vec::new(),
+            fault: None,
+        }
+    }
+
+    /// Get the compiled block starting at the instruction `start` and ending at or before `end`,
+    /// compiling it if needed. The block stops after its first `ECALL`, or before the first
+    /// instruction that isn't compiled, so there is none if it starts with one.
+    ///
+    /// The instructions are lowered again rather than taken from the executor, whose ops may be
+    /// fused.
+    fn block(
+        &mut self,
+        instructions: &[Instruction],
+        pc_base: u32,
+        start: usize,
+        end: usize,
+    ) -> Option<Block> {
+        if self.blocks.is_empty() {
+            self.blocks = vec![None; instructions.len()];
+        }
+        if let Some(block) = self.blocks[start] {
+            return Some(block);
+        }
+
+        let mut ops = instructions[start..end]
+            .iter()
+            .map(Op::lower)
+            .take_while(|op| !matches!(op, Op::Ecall | Op::Ebreak | Op::Unimp))
+            .collect::<Vec<_>>();
+        let ecall = instructions[start..end]
+            .get(ops.len())
+            .is_some_and(Instruction::is_ecall_instruction);
+        if ecall {
+            ops.push(Op::Ecall);
+        }
+        if ops.is_empty() {
+            return None;
+        }
+        let len = ops.len();
+        let code = compile(&ops, pc_base + 4 * start as u32);
+        let entry = self.code.install(&code);
+        let block = Block {
+            // SAFETY: The code is a function with the signature of `BlockFn`.
+            entry: unsafe { std::mem::transmute::<*const u8, BlockFn>(entry) },
+            len,
+            ecall,
+        };
+        self.blocks[start] = Some(block);
+        Some(block)
+    }
+}
+
+impl Executor<'_> {
+    /// Get the compiled block starting at the instruction `start`, if it has any instructions.
+    pub(crate) fn jit_block(&mut self, start: usize) -> Option<Block> {
+        let end = self.basic_block_ends[start] as usize;
+        self.jit
+            .block(&self.program.instructions, self.program.pc_base, start, end)
+    }
+
+    /// Execute a compiled block starting at the instruction `start`.
+    pub(crate) fn execute_jit_block(
+        &mut self,
+        start: usize,
+        block: Block,
+    ) -> Result<(), ExecutionError> {
+        // SAFETY: The block was compiled from the program of the executor, which is only accessed
+        // through this pointer until the block returns.
+        let next_pc = unsafe { (block.entry)(std::ptr::from_mut(self).cast()) };
+        let fault = (next_pc == FAULT).then(|| self.jit.fault.take().unwrap());
+        // Once the `ECALL` ending the block is reached, the block is accounted for already.
+        let ecall_pc = self.program.pc_base + 4 * (start + block.len - 1) as u32;
+        match &fault {
+            _ if block.ecall && fault.as_ref().is_none_or(|fault| fault.pc == ecall_pc) => {}
+            Some(fault) => {
+                let executed = ((fault.pc - self.state.pc) / 4) as usize;
+                self.finish_compiled_block(start, executed, true, fault.pc);
+            }
+            None => self.finish_compiled_block(start, block.len, false, next_pc as u32),
+        }
+
+        match fault.map(|fault| fault.result) {
+            None => Ok(()),
+            Some(Ok(error)) => Err(error),
+            Some(Err(payload)) => resume_unwind(payload),
+        }
+    }
+
+    /// Execute the `ECALL` at `pc` that ends the compiled block being executed, returning the
+    /// program counter after it.
+    ///
+    /// The instructions of the block before it are accounted for first, since the syscall sees
+    /// the program counter and the clock of the executor.
+    fn execute_jit_ecall(&mut self, pc: u32) -> Result<u32, ExecutionError> {
+        let start = ((self.state.pc - self.program.pc_base) / 4) as usize;
+        let index = ((pc - self.program.pc_base) / 4) as usize;
+        self.finish_compiled_block(start, index - start, false, pc);
+        self.execute_instruction::<SIMPLE, false>(index)?;
+        self.state.global_clk += 1;
+        Ok(self.state.pc)
+    }
+}
+
+/// Compile the instructions starting at `pc` into a [`BlockFn`]. Only the last instruction may be
+/// a branch, a jump or an `ECALL`.
+#[allow(clippy::too_many_lines)]
+fn compile(ops: &[Op], mut pc: u32) -> Vec<u8> {
+    let registers_offset =
+        offset_of!(Executor<'static>, state) + offset_of!(ExecutionState, registers);
+    let mut asm = Assembler::new(registers_offset as u32);
+    for &op in ops {
+        match op {
+            Op::AluRegisters {
+                opcode,
+                rd,
+                rs1,
+                rs2,
+            } => {
+                asm.load_register(Reg::Rax, rs1 as u8);
+                asm.load_register(Reg::Rcx, rs2 as u8);
+                compile_alu(&mut asm, opcode);
+                write_register(&mut asm, rd);
+            }
+            Op::AluImmediate {
+                opcode,
+                rd,
+                rs1,
+                imm,
+            } => {
+                asm.load_register(Reg::Rax, rs1 as u8);
+                asm.mov_imm(Reg::Rcx, imm);
+                compile_alu(&mut asm, opcode);
+                write_register(&mut asm, rd);
+            }
+            Op::AluImmediates { opcode, rd, b, c } => {
+                asm.mov_imm(Reg::Rax, alu(opcode, b, c));
+                write_register(&mut asm, rd);
+            }
+            Op::AluRegistersX0 { .. } | Op::AluImmediateX0 { .. } => {}
+
+            Op::Load {
+                opcode,
+                rd,
+                rs1,
+                imm,
+            } => {
+                asm.load_register(Reg::Rsi, rs1 as u8);
+                asm.mov_imm(Reg::Rax, imm);
+                asm.alu(AluOp::Add, Reg::Rsi, Reg::Rax);
+                asm.mov_imm(Reg::Rdx, opcode.into_usize() as u32);
+                asm.mov_imm(Reg::Rcx, pc);
+                asm.mov64(Reg::Rdi, EXECUTOR);
+                asm.call(load as *const () as usize);
+                asm.test64(Reg::Rax);
+                asm.exit_if(Cond::Sign);
+                write_register(&mut asm, rd);
+            }
+            Op::Store {
+                opcode,
+                rs1,
+                rs2,
+                imm,
+            } => {
+                asm.load_register(Reg::Rsi, rs2 as u8);
+                asm.mov_imm(Reg::Rax, imm);
+                asm.alu(AluOp::Add, Reg::Rsi, Reg::Rax);
+                asm.load_register(Reg::Rdx, rs1 as u8);
+                asm.mov_imm(Reg::Rcx, opcode.into_usize() as u32);
+                asm.mov_imm(Reg::R8, pc);
+                asm.mov64(Reg::Rdi, EXECUTOR);
+                asm.call(store as *const () as usize);
+                asm.test64(Reg::Rax);
+                asm.exit_if(Cond::Sign);
+            }
+
+            Op::Branch {
+                opcode,
+                rs1,
+                rs2,
+                imm,
+            } => {
+                let cond = match opcode {
+                    Opcode::BEQ => Cond::Equal,
+                    Opcode::BNE => Cond::NotEqual,
+                    Opcode::BLT => Cond::Less,
+                    Opcode::BGE => Cond::GreaterOrEqual,
+                    Opcode::BLTU => Cond::Below,
+                    Opcode::BGEU => Cond::AboveOrEqual,
+                    _ => unreachable!(),
+                };
+                asm.load_register(Reg::Rdx, rs1 as u8);
+                asm.load_register(Reg::Rsi, rs2 as u8);
+                asm.mov_imm(Reg::Rax, pc.wrapping_add(4));
+                asm.mov_imm(Reg::Rcx, pc.wrapping_add(imm));
+                asm.alu(AluOp::Cmp, Reg::Rdx, Reg::Rsi);
+                asm.cmov(cond, Reg::Rax, Reg::Rcx);
+                return asm.finish();
+            }
+            Op::Jal { rd, imm } => {
+                asm.mov_imm(Reg::Rax, pc.wrapping_add(4));
+                write_register(&mut asm, rd);
+                asm.mov_imm(Reg::Rax, pc.wrapping_add(imm));
+                return asm.finish();
+            }
+            Op::JalX0 { imm } => {
+                asm.mov_imm(Reg::Rax, pc.wrapping_add(imm));
+                return asm.finish();
+            }
+            Op::Jalr { rd, rs1, imm } => {
+                // The target is computed before `rd` is written, since it may be `rs1`.
+                asm.load_register(Reg::Rcx, rs1 as u8);
+                asm.mov_imm(Reg::Rax, imm);
+                asm.alu(AluOp::Add, Reg::Rcx, Reg::Rax);
+                asm.mov_imm(Reg::Rax, pc.wrapping_add(4));
+                write_register(&mut asm, rd);
+                asm.mov(Reg::Rax, Reg::Rcx);
+                return asm.finish();
+            }
+            Op::JalrX0 { rs1, imm } => {
+                asm.load_register(Reg::Rax, rs1 as u8);
+                asm.mov_imm(Reg::Rcx, imm);
+                asm.alu(AluOp::Add, Reg::Rax, Reg::Rcx);
+                return asm.finish();
+            }
+
+            Op::Auipc { rd, imm } => {
+                asm.mov_imm(Reg::Rax, pc.wrapping_add(imm));
+                write_register(&mut asm, rd);
+            }
+
+            Op::Ecall => {
+                asm.mov_imm(Reg::Rsi, pc);
+                asm.mov64(Reg::Rdi, EXECUTOR);
+                asm.call(ecall as *const () as usize);
+                return asm.finish();
+            }
+
+            Op::Ebreak | Op::Unimp | Op::Fused(_) => {
+                unreachable!("{op:?} is not compiled")
+            }
+        }
+        pc = pc.wrapping_add(4);
+    }
+
+    asm.mov_imm(Reg::Rax, pc);
+    asm.finish()
+}
+
+/// Compile an ALU operation on `eax` and `ecx`, leaving the result in `eax`.
+fn compile_alu(asm: &mut Assembler, opcode: Opcode) {
+    match opcode {
+        Opcode::ADD => asm.alu(AluOp::Add, Reg::Rax, Reg::Rcx),
+        Opcode::SUB => asm.alu(AluOp::Sub, Reg::Rax, Reg::Rcx),
+        Opcode::XOR => asm.alu(AluOp::Xor, Reg::Rax, Reg::Rcx),
+        Opcode::OR => asm.alu(AluOp::Or, Reg::Rax, Reg::Rcx),
+        Opcode::AND => asm.alu(AluOp::And, Reg::Rax, Reg::Rcx),
+        // x86 shifts also only use the low 5 bits of the shift amount.
+        Opcode::SLL => asm.shift(ShiftOp::Shl, Reg::Rax),
+        Opcode::SRL => asm.shift(ShiftOp::Shr, Reg::Rax),
+        Opcode::SRA => asm.shift(ShiftOp::Sar, Reg::Rax),
+        Opcode::SLT => {
+            asm.alu(AluOp::Cmp, Reg::Rax, Reg::Rcx);
+            asm.set(Cond::Less, Reg::Rax);
+        }
+        Opcode::SLTU => {
+            asm.alu(AluOp::Cmp, Reg::Rax, Reg::Rcx);
+            asm.set(Cond::Below, Reg::Rax);
+        }
+        Opcode::MUL => asm.imul(Reg::Rax, Reg::Rcx),
+        // The operands are zero-extended to 64 bits, unless they are signed.
+        Opcode::MULH => {
+            asm.sign_extend(Reg::Rax, Reg::Rax);
+            asm.sign_extend(Reg::Rcx, Reg::Rcx);
+            asm.imul64(Reg::Rax, Reg::Rcx);
+            asm.high_half(Reg::Rax);
+        }
+        Opcode::MULHU => {
+            asm.imul64(Reg::Rax, Reg::Rcx);
+            asm.high_half(Reg::Rax);
+        }
+        Opcode::MULHSU => {
+            asm.sign_extend(Reg::Rax, Reg::Rax);
+            asm.imul64(Reg::Rax, Reg::Rcx);
+            asm.high_half(Reg::Rax);
+        }
+        // Division by zero and overflow are defined differently than on x86.
+        Opcode::DIV | Opcode::DIVU | Opcode::REM | Opcode::REMU => {
+            asm.mov(Reg::Rdi, Reg::Rax);
+            asm.mov(Reg::Rsi, Reg::Rcx);
+            asm.mov_imm(Reg::Rdx, opcode.into_usize() as u32);
+            asm.call(divide as *const () as usize);
+        }
+        _ => unreachable!("{opcode:?} is not an ALU operation"),
+    }
+}
+
+/// Write `eax` to the register `rd`, or 0 if it's `x0`, like [`Executor::rw`] in simple mode.
+fn write_register(asm: &mut Assembler, rd: Register) {
+    if rd == Register::X0 {
+        asm.mov_imm(Reg::Rax, 0);
+    }
+    asm.store_register(rd as u8, Reg::Rax);
+}
+
+/// Call `f` with the executor running compiled code, turning the errors and panics of the
+/// instruction at `pc` into a [`Fault`].
+///
+/// # Safety
+///
+/// `executor` must be the executor passed to the compiled code.
+unsafe fn call_back<T>(
+    executor: *mut (),
+    pc: u32,
+    f: impl FnOnce(&mut Executor<'_>) -> Result<T, ExecutionError>,
+) -> Option<T> {
+    let executor = &mut *executor.cast::<Executor<'_>>();
+    let result = match catch_unwind(AssertUnwindSafe(|| f(executor))) {
+        Ok(Ok(value)) => return Some(value),
+        Ok(Err(error)) => Ok(error),
+        Err(payload) => Err(payload),
+    };
+    executor.jit.fault = Some(Fault { pc, result });
+    None
+}
+
+/// Execute a load for compiled code, returning the loaded value or [`FAULT`].
+unsafe extern "sysv64" fn load(executor: *mut (), addr: u32, opcode: u32, pc: u32) -> u64 {
+    let opcode = Opcode::from_usize(opcode as usize);
+    call_back(executor, pc, |executor| {
+        executor.execute_load::<SIMPLE, false>(opcode, addr)
+    })
+    .map_or(FAULT, |(value, _)| value.into())
+}
+
+/// Execute a store for compiled code, returning 0 or [`FAULT`].
+unsafe extern "sysv64" fn store(executor: *mut (), addr: u32, a: u32, opcode: u32, pc: u32) -> u64 {
+    let opcode = Opcode::from_usize(opcode as usize);
+    call_back(executor, pc, |executor| {
+        executor.execute_store::<SIMPLE, false>(opcode, addr, a)
+    })
+    .map_or(FAULT, |_| 0)
+}
+
+/// Execute an `ECALL` for compiled code, returning the program counter after it or [`FAULT`].
+unsafe extern "sysv64" fn ecall(executor: *mut (), pc: u32) -> u64 {
+    call_back(executor, pc, |executor| executor.execute_jit_ecall(pc)).map_or(FAULT, u64::from)
+}
+
+/// Compute a division or a remainder for compiled code.
+extern "sysv64" fn divide(b: u32, c: u32, opcode: u32) -> u32 {
+    alu(Opcode::from_usize(opcode as usize), b, c)
+}
@@ -19,6 +19,7 @@
 #![allow(clippy::explicit_iter_loop)]
 #![warn(missing_docs)]
 
+mod aot;
 mod context;
 mod disassembler;
 pub mod events;
@@ -26,20 +27,29 @@ mod executor;
 mod hook;
 mod instruction;
 mod io;
+#[cfg(feature = "jit")]
+mod jit;
 mod memory_map;
 mod opcode;
+mod ops;
 mod program;
+mod record;
 mod register;
+mod report;
 mod state;
 pub mod syscalls;
 mod utils;
 
+pub use aot::*;
 pub use context::*;
 pub use executor::*;
 pub use hook::*;
 pub use instruction::*;
 pub use opcode::*;
+pub use ops::alu;
 pub use program::*;
+pub use record::*;
 pub use register::*;
+pub use report::*;
 pub use state::*;
 pub use utils::*;
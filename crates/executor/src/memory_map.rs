@@ -1,18 +1,45 @@
-use hashbrown::{
-    hash_map::{DefaultHashBuilder, Entry, OccupiedEntry, VacantEntry},
-    HashMap,
-};
-use nohash_hasher::BuildNoHashHasher;
+use std::{collections::BTreeMap, ops::Range};
+
+use hashbrown::{hash_map::DefaultHashBuilder, HashMap};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 type MemoryHasher = DefaultHashBuilder;
 
+/// The number of bits of a word index that select the word within its page.
+const PAGE_BITS: u32 = 10;
+
+/// The number of words in a page.
+const PAGE_SIZE: usize = 1 << PAGE_BITS;
+
+/// A page of words, each of which may not have been accessed yet.
+type Page<V> = Box<[Option<V>]>;
+
 /// Is memory
+///
+/// Memory is a two-level page table: words are grouped into pages of [`PAGE_SIZE`] words, which
+/// are only allocated once one of their words is accessed. Accesses are highly clustered, so this
+/// is both smaller and much faster to index than a hash map. Registers are not part of memory,
+/// see [`crate::ExecutionState::registers`].
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct MemoryMap<V> {
-    /// TODO: docs
-    pub registers: [Option<V>; 32],
-    memory: HashMap<u32, V, MemoryHasher>,
+    pages: Vec<Option<Page<V>>>,
+}
+
+/// Splits `len` consecutive words from `addr` into runs that each lie in one page, given as the
+/// address of their first word and the range of their indices among the words.
+pub fn page_runs(addr: u32, len: usize) -> impl Iterator<Item = (u32, Range<usize>)> {
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        (start < len).then(|| {
+            let run_addr = addr + 4 * start as u32;
+            let (_, index) = MemoryMap::<()>::page_index(run_addr);
+            let end = len.min(start + PAGE_SIZE - index);
+            let run = (run_addr, start..end);
+            start = end;
+            run
+        })
+    })
 }
 
 /// TODO: docs
@@ -52,66 +79,44 @@ impl<'a, V> MemEntry<'a, V> {
     }
 }
 
-/// TODO: docs
-pub enum MemVacant<'a, V> {
-    /// TODO: docs
-    Register(&'a mut Option<V>),
-    /// TODO: docs
-    HashMap(VacantEntry<'a, u32, V, MemoryHasher>),
-}
+/// An empty slot of a register or of a page.
+pub struct MemVacant<'a, V>(&'a mut Option<V>);
 
-/// TODO: docs
-pub enum MemOccupied<'a, V> {
-    /// TODO: docs
-    Register(&'a mut V),
-    /// TODO: docs
-    HashMap(OccupiedEntry<'a, u32, V, MemoryHasher>),
-}
+/// An occupied slot of a register or of a page.
+pub struct MemOccupied<'a, V>(&'a mut V);
 
 impl<'a, V> MemOccupied<'a, V> {
     /// docs
     pub fn get(&self) -> &V {
-        match self {
-            Self::HashMap(entry) => entry.get(),
-            Self::Register(record) => &*record,
-        }
+        self.0
     }
 
     /// docs
     pub fn into_mut(self) -> &'a mut V {
-        match self {
-            Self::HashMap(entry) => entry.into_mut(),
-            Self::Register(record) => record,
-        }
+        self.0
     }
 
     pub fn modify<F>(&mut self, f: F)
     where
         F: FnOnce(&mut V),
     {
-        match self {
-            Self::HashMap(entry) => f(entry.get_mut()),
-            Self::Register(value) => f(*value),
-        }
+        f(self.0);
     }
 }
 
 impl<'a, V> MemVacant<'a, V> {
     /// docs
     pub fn insert(self, value: V) -> &'a mut V {
-        match self {
-            Self::Register(reg_ptr) => reg_ptr.insert(value),
-            Self::HashMap(entry) => entry.insert(value),
-        }
+        self.0.insert(value)
     }
 }
 
 impl<V> MemoryMap<V> {
+    /// Creates an empty map with room for the pages of `capacity` words, which are still only
+    /// allocated once accessed.
     pub fn with_capacity(capacity: usize) -> Self {
-        let memory = HashMap::with_capacity_and_hasher(capacity, MemoryHasher::new());
         Self {
-            memory,
-            registers: [const { None }; 32],
+            pages: Vec::with_capacity(capacity.div_ceil(PAGE_SIZE)),
         }
     }
 
@@ -121,112 +126,266 @@ impl<V> MemoryMap<V> {
 
     #[inline(always)]
     fn translate_addr(addr: u32) -> u32 {
-        // return addr;
         assert!(addr >= 0x10000);
         (addr - 0x10000) >> 2
     }
 
+    #[inline]
+    fn untranslate_addr(key: u32) -> u32 {
+        (key << 2) + 0x10000
+    }
+
+    /// Split an address into the index of its page and the index of its word within the page.
+    #[inline]
+    fn page_index(addr: u32) -> (usize, usize) {
+        let key = Self::translate_addr(addr) as usize;
+        (key >> PAGE_BITS, key & (PAGE_SIZE - 1))
+    }
+
+    /// Get a page, allocating it if needed.
+    #[inline]
+    fn page_mut(&mut self, page: usize) -> &mut Page<V> {
+        if page >= self.pages.len() {
+            self.pages.resize_with(page + 1, || None);
+        }
+        self.pages[page].get_or_insert_with(|| (0..PAGE_SIZE).map(|_| None).collect())
+    }
+
+    /// Get the slot of a word, allocating its page if needed.
+    #[inline]
+    fn slot_mut(&mut self, addr: u32) -> &mut Option<V> {
+        let (page, index) = Self::page_index(addr);
+        &mut self.page_mut(page)[index]
+    }
+
+    /// Gets the slots of `len` consecutive words from `addr`, which must lie in one page as split
+    /// by [`page_runs`], or `None` if no word of the page was accessed yet.
+    #[inline]
+    pub fn get_run(&self, addr: u32, len: usize) -> Option<&[Option<V>]> {
+        let (page, index) = Self::page_index(addr);
+        Some(&self.pages.get(page)?.as_ref()?[index..index + len])
+    }
+
+    /// Gets the slots of `len` consecutive words from `addr`, which must lie in one page as split
+    /// by [`page_runs`], allocating the page if needed.
+    #[inline]
+    pub fn run_mut(&mut self, addr: u32, len: usize) -> &mut [Option<V>] {
+        let (page, index) = Self::page_index(addr);
+        &mut self.page_mut(page)[index..index + len]
+    }
+
     /// inner
-    pub fn into_inner(self) -> HashMap<u32, V, MemoryHasher> {
-        self.memory
+    ///
+    /// The words that were accessed, keyed by their word index from `0x10000` rather than by their
+    /// address.
+    pub fn into_inner(mut self) -> HashMap<u32, V, MemoryHasher> {
+        self.drain()
+            .map(|(addr, value)| (Self::translate_addr(addr), value))
+            .collect()
     }
 
     /// Gets
+    #[inline]
     pub fn get(&self, addr: &u32) -> Option<&V> {
-        if *addr < 32 {
-            return self.registers[*addr as usize].as_ref();
-        }
-        self.memory.get(&Self::translate_addr(*addr))
+        let (page, index) = Self::page_index(*addr);
+        self.pages.get(page)?.as_ref()?[index].as_ref()
     }
 
     /// entry
+    #[inline]
     pub fn entry(&mut self, addr: u32) -> MemEntry<'_, V> {
-        if addr < 32 {
-            if self.registers[addr as usize].is_some() {
-                return MemEntry::Occupied(MemOccupied::Register(
-                    self.registers[addr as usize].as_mut().unwrap(),
-                ));
-            }
-            return MemEntry::Vacant(MemVacant::Register(&mut self.registers[addr as usize]));
-        }
-        match self.memory.entry(Self::translate_addr(addr)) {
-            Entry::Vacant(inner) => MemEntry::Vacant(MemVacant::HashMap(inner)),
-            Entry::Occupied(inner) => MemEntry::Occupied(MemOccupied::HashMap(inner)),
-        }
-    }
-
-    pub fn drain(&mut self) -> MemoryDrainer<'_, V> {
-        MemoryDrainer::RegisterDrainer {
-            map: self,
-            register: 0,
+        let slot = self.slot_mut(addr);
+        match slot {
+            Some(value) => MemEntry::Occupied(MemOccupied(value)),
+            None => MemEntry::Vacant(MemVacant(slot)),
         }
     }
 
     /// insert
+    #[inline]
     pub fn insert(&mut self, addr: u32, record: V) -> Option<V> {
-        if addr < 32 {
-            return std::mem::replace(&mut self.registers[addr as usize], Some(record));
-        }
-        self.memory.insert(Self::translate_addr(addr), record)
+        self.slot_mut(addr).replace(record)
     }
 
     /// remove
+    #[inline]
     pub fn remove(&mut self, addr: &u32) -> Option<V> {
-        if *addr < 32 {
-            return std::mem::replace(&mut self.registers[*addr as usize], None);
-        }
-        self.memory.remove(&Self::translate_addr(*addr))
+        let (page, index) = Self::page_index(*addr);
+        self.pages.get_mut(page)?.as_mut()?[index].take()
+    }
+
+    /// Iterates over the addresses in the map and their values, in increasing order of address.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &V)> {
+        self.pages
+            .iter()
+            .enumerate()
+            .filter_map(|(page, words)| words.as_ref().map(|words| (page, words)))
+            .flat_map(|(page, words)| {
+                words.iter().enumerate().filter_map(move |(index, value)| {
+                    let key = ((page << PAGE_BITS) | index) as u32;
+                    value
+                        .as_ref()
+                        .map(|value| (Self::untranslate_addr(key), value))
+                })
+            })
+    }
+
+    /// Removes all the addresses in the map, returning them and their values in increasing order
+    /// of address.
+    pub fn drain(&mut self) -> impl Iterator<Item = (u32, V)> {
+        std::mem::take(&mut self.pages)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(page, words)| words.map(|words| (page, words)))
+            .flat_map(|(page, words)| {
+                words
+                    .into_vec()
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(move |(index, value)| {
+                        let key = ((page << PAGE_BITS) | index) as u32;
+                        value.map(|value| (Self::untranslate_addr(key), value))
+                    })
+            })
     }
 }
 
-pub enum MemoryDrainer<'a, V> {
-    RegisterDrainer {
-        map: &'a mut MemoryMap<V>,
-        register: u8,
-    },
-    HashMapDrain(hashbrown::hash_map::Drain<'a, u32, V>),
+/// The values that memory addresses are initialized with when they are first accessed, instead of
+/// 0.
+///
+/// `HINT_READ` writes whole buffers here, so the values are kept in regions of consecutive words,
+/// keyed by the address of their first word. Regions never overlap.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UninitializedMemory {
+    regions: BTreeMap<u32, Vec<u32>>,
 }
 
-impl<'a, V> Iterator for MemoryDrainer<'a, V> {
-    type Item = (u32, V);
-
-    fn next(&mut self) -> Option<Self::Item> {
-        while let Self::RegisterDrainer { map, register } = self {
-            // Check if we've reached the end of registers
-            if *register == 32 {
-                // Need to swap out self without having active borrows
-                let drain_iter = std::mem::replace(
-                    self,
-                    // Create a temporary placeholder that will be immediately replaced
-                    Self::RegisterDrainer {
-                        map: unsafe { std::mem::transmute(std::ptr::null_mut::<MemoryMap<V>>()) },
-                        register: 0,
-                    },
-                );
-
-                // Extract the map from the old value to create the drain
-                if let Self::RegisterDrainer { map, .. } = drain_iter {
-                    *self = Self::HashMapDrain(map.memory.drain());
-                    return self.next();
-                }
+/// An error for an address that was given a value to be initialized with twice.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("hint read address {0:#x} is initialized already")]
+pub struct AlreadyInitialized(pub u32);
+
+impl UninitializedMemory {
+    /// Creates an empty [`UninitializedMemory`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the region holding `addr`, as the address of its first word and its words.
+    #[inline]
+    pub fn region(&self, addr: u32) -> Option<(u32, &[u32])> {
+        let (&start, words) = self.regions.range(..=addr).next_back()?;
+        ((addr - start) as usize / 4 < words.len()).then_some((start, words))
+    }
+
+    /// Gets the value `addr` is initialized with, if it has one.
+    #[inline]
+    pub fn get(&self, addr: u32) -> Option<u32> {
+        let (start, words) = self.region(addr)?;
+        Some(words[(addr - start) as usize / 4])
+    }
+
+    /// Sets the values of the consecutive words from `addr` to `words`, as a new region.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first of the words that already has a value, in which case nothing is set.
+    pub fn insert(&mut self, addr: u32, words: Vec<u32>) -> Result<(), AlreadyInitialized> {
+        if words.is_empty() {
+            return Ok(());
+        }
+        if self.region(addr).is_some() {
+            return Err(AlreadyInitialized(addr));
+        }
+        if let Some((&next, _)) = self.regions.range(addr..).next() {
+            if ((next - addr) as usize / 4) < words.len() {
+                return Err(AlreadyInitialized(next));
+            }
+        }
+        self.regions.insert(addr, words);
+        Ok(())
+    }
+
+    /// Removes the region starting at `addr`, returning its words.
+    pub fn remove(&mut self, addr: u32) -> Option<Vec<u32>> {
+        self.regions.remove(&addr)
+    }
+
+    /// Returns whether no address has a value.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
 
-                // This should never happen due to the match above
-                unsafe { std::hint::unreachable_unchecked() }
-            } else {
-                // Handle register case
-                let addr = *register as u32;
-                *register += 1;
+    /// Iterates over the addresses that have a value and their values, in increasing order of
+    /// address.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.regions.iter().flat_map(|(&start, words)| {
+            words
+                .iter()
+                .enumerate()
+                .map(move |(i, &value)| (start + 4 * i as u32, value))
+        })
+    }
+}
 
-                // Try to remove the value at this address
-                if let Some(value) = map.remove(&addr) {
-                    return Some((addr, value));
+impl FromIterator<(u32, u32)> for UninitializedMemory {
+    /// Collects addresses and their values, merging consecutive addresses into one region.
+    fn from_iter<I: IntoIterator<Item = (u32, u32)>>(iter: I) -> Self {
+        let mut values = iter.into_iter().collect::<Vec<_>>();
+        values.sort_unstable_by_key(|&(addr, _)| addr);
+
+        let mut regions = BTreeMap::new();
+        let mut region: Option<(u32, Vec<u32>)> = None;
+        for (addr, value) in values {
+            match &mut region {
+                Some((start, words)) if start.wrapping_add(4 * words.len() as u32) == addr => {
+                    words.push(value);
                 }
+                _ => regions.extend(region.replace((addr, vec![value]))),
             }
         }
+        regions.extend(region);
+        Self { regions }
+    }
+}
 
-        match self {
-            Self::HashMapDrain(drain) => drain.next(),
-            Self::RegisterDrainer { .. } => unsafe { std::hint::unreachable_unchecked() },
+#[cfg(test)]
+mod tests {
+    use super::{MemEntry, MemoryMap};
+
+    #[test]
+    fn test_memory_map() {
+        let mut memory = MemoryMap::with_capacity(1024);
+        let addrs = [0x10000, 0x10004, 0x10000 + 4 * 1024, 0x7fff_fffc];
+        for (i, &addr) in addrs.iter().enumerate().rev() {
+            assert_eq!(memory.insert(addr, i), None);
         }
+        assert_eq!(memory.get(&0x10004), Some(&1));
+        assert_eq!(memory.get(&0x10008), None);
+        assert_eq!(memory.get(&0x8000_0000), None);
+        assert_eq!(memory.insert(0x10004, 7), Some(1));
+
+        match memory.entry(0x10008) {
+            MemEntry::Vacant(entry) => *entry.insert(3) += 1,
+            MemEntry::Occupied(_) => panic!("0x10008 is vacant"),
+        }
+        *memory.entry(0x10008).or_insert(0) += 1;
+        assert_eq!(memory.get(&0x10008), Some(&5));
+        assert_eq!(memory.remove(&0x10008), Some(5));
+        assert_eq!(memory.remove(&0x10008), None);
+
+        let expected = vec![(0x10000, 0), (0x10004, 7), (0x11000, 2), (0x7fff_fffc, 3)];
+        let entries = memory
+            .iter()
+            .map(|(addr, &value)| (addr, value))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, expected);
+        assert_eq!(memory.clone().drain().collect::<Vec<_>>(), expected);
+
+        let mut inner = memory.into_inner().into_iter().collect::<Vec<_>>();
+        inner.sort_unstable();
+        assert_eq!(inner, vec![(0, 0), (1, 7), (1024, 2), (0x1fff_bfff, 3)]);
     }
 }
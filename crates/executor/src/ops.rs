@@ -0,0 +1,364 @@
+//! Instructions lowered for execution.
+
+use crate::{Instruction, Opcode, Register};
+
+/// An instruction lowered for execution by the [`crate::Executor`].
+///
+/// Unlike [`Instruction`], each variant is a separate form of the instruction with its register
+/// and immediate operands already resolved, so that executing it doesn't need to decode it first.
+/// Forms whose destination is `x0` discard their result instead of writing it, unless the write
+/// has to be traced or checkpointed.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Op {
+    /// An ALU operation on two registers.
+    AluRegisters {
+        opcode: Opcode,
+        rd: Register,
+        rs1: Register,
+        rs2: Register,
+    },
+    /// An ALU operation on two registers whose destination is `x0`.
+    AluRegistersX0 {
+        opcode: Opcode,
+        rs1: Register,
+        rs2: Register,
+    },
+    /// An ALU operation on a register and an immediate.
+    AluImmediate {
+        opcode: Opcode,
+        rd: Register,
+        rs1: Register,
+        imm: u32,
+    },
+    /// An ALU operation on a register and an immediate whose destination is `x0`.
+    AluImmediateX0 {
+        opcode: Opcode,
+        rs1: Register,
+        imm: u32,
+    },
+    /// An ALU operation on two immediates.
+    AluImmediates {
+        opcode: Opcode,
+        rd: Register,
+        b: u32,
+        c: u32,
+    },
+    /// A load from memory.
+    Load {
+        opcode: Opcode,
+        rd: Register,
+        rs1: Register,
+        imm: u32,
+    },
+    /// A store to memory.
+    Store {
+        opcode: Opcode,
+        rs1: Register,
+        rs2: Register,
+        imm: u32,
+    },
+    /// A conditional branch.
+    Branch {
+        opcode: Opcode,
+        rs1: Register,
+        rs2: Register,
+        imm: u32,
+    },
+    /// A jump that links the return address.
+    Jal { rd: Register, imm: u32 },
+    /// A jump whose destination is `x0`.
+    JalX0 { imm: u32 },
+    /// An indirect jump that links the return address.
+    Jalr {
+        rd: Register,
+        rs1: Register,
+        imm: u32,
+    },
+    /// An indirect jump whose destination is `x0`.
+    JalrX0 { rs1: Register, imm: u32 },
+    /// An addition of an upper immediate to the program counter.
+    Auipc { rd: Register, imm: u32 },
+    /// A system call.
+    Ecall,
+    /// A breakpoint.
+    Ebreak,
+    /// An unimplemented instruction.
+    Unimp,
+    /// A pair of instructions fused by [`fuse`], starting with this one.
+    Fused(Fused),
+}
+
+/// A common pair of consecutive instructions, executed as one in simple mode.
+///
+/// Each instruction of the pair still takes a cycle and writes its destination register.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Fused {
+    /// Two constants, like `lui` followed by `addi` on its result.
+    Constants {
+        rd1: Register,
+        a1: u32,
+        rd2: Register,
+        a2: u32,
+    },
+    /// A call, which is `auipc` followed by `jalr` on its result.
+    Call {
+        rd: Register,
+        a: u32,
+        link: Register,
+        target: u32,
+    },
+    /// A load relative to the program counter, which is `auipc` followed by a load from its
+    /// result.
+    AuipcLoad {
+        rd: Register,
+        a: u32,
+        opcode: Opcode,
+        load_rd: Register,
+        addr: u32,
+    },
+    /// A comparison and a branch on it, which is `slt` or `sltu` followed by `bnez` or `beqz` on
+    /// its result.
+    CompareBranch {
+        opcode: Opcode,
+        rd: Register,
+        rs1: Register,
+        rs2: Register,
+        taken_if_set: bool,
+        target: u32,
+    },
+}
+
+impl Op {
+    /// Lower an instruction.
+    #[must_use]
+    pub(crate) fn lower(instruction: &Instruction) -> Self {
+        let opcode = instruction.opcode;
+        if instruction.is_alu_instruction() {
+            return match (instruction.imm_b, instruction.imm_c) {
+                (_, false) => {
+                    let (rd, rs1, rs2) = instruction.r_type();
+                    if rd == Register::X0 {
+                        Self::AluRegistersX0 { opcode, rs1, rs2 }
+                    } else {
+                        Self::AluRegisters {
+                            opcode,
+                            rd,
+                            rs1,
+                            rs2,
+                        }
+                    }
+                }
+                (false, true) => {
+                    let (rd, rs1, imm) = instruction.i_type();
+                    if rd == Register::X0 {
+                        Self::AluImmediateX0 { opcode, rs1, imm }
+                    } else {
+                        Self::AluImmediate {
+                            opcode,
+                            rd,
+                            rs1,
+                            imm,
+                        }
+                    }
+                }
+                (true, true) => Self::AluImmediates {
+                    opcode,
+                    rd: Register::from_u32(instruction.op_a),
+                    b: instruction.op_b,
+                    c: instruction.op_c,
+                },
+            };
+        }
+
+        match opcode {
+            Opcode::LB | Opcode::LH | Opcode::LW | Opcode::LBU | Opcode::LHU => {
+                let (rd, rs1, imm) = instruction.i_type();
+                Self::Load {
+                    opcode,
+                    rd,
+                    rs1,
+                    imm,
+                }
+            }
+            Opcode::SB | Opcode::SH | Opcode::SW => {
+                let (rs1, rs2, imm) = instruction.s_type();
+                Self::Store {
+                    opcode,
+                    rs1,
+                    rs2,
+                    imm,
+                }
+            }
+            Opcode::BEQ | Opcode::BNE | Opcode::BLT | Opcode::BGE | Opcode::BLTU | Opcode::BGEU => {
+                let (rs1, rs2, imm) = instruction.b_type();
+                Self::Branch {
+                    opcode,
+                    rs1,
+                    rs2,
+                    imm,
+                }
+            }
+            Opcode::JAL => {
+                let (rd, imm) = instruction.j_type();
+                if rd == Register::X0 {
+                    Self::JalX0 { imm }
+                } else {
+                    Self::Jal { rd, imm }
+                }
+            }
+            Opcode::JALR => {
+                let (rd, rs1, imm) = instruction.i_type();
+                if rd == Register::X0 {
+                    Self::JalrX0 { rs1, imm }
+                } else {
+                    Self::Jalr { rd, rs1, imm }
+                }
+            }
+            Opcode::AUIPC => {
+                let (rd, imm) = instruction.u_type();
+                Self::Auipc { rd, imm }
+            }
+            Opcode::ECALL => Self::Ecall,
+            Opcode::EBREAK => Self::Ebreak,
+            _ => Self::Unimp,
+        }
+    }
+}
+
+/// Fuse the pairs of consecutive ops of a program starting at `pc_base` that have a [`Fused`]
+/// form, replacing the first op of each pair.
+///
+/// The second op of a pair is kept, so that execution can still start at it, and may itself be
+/// the first op of another pair.
+pub(crate) fn fuse(ops: &mut [Op], pc_base: u32) {
+    for i in 0..ops.len().saturating_sub(1) {
+        let pc = pc_base + 4 * i as u32;
+        let fused = match (ops[i], ops[i + 1]) {
+            (
+                Op::AluImmediates { opcode, rd, b, c },
+                Op::AluImmediate {
+                    opcode: opcode2,
+                    rd: rd2,
+                    rs1,
+                    imm,
+                },
+            ) if rs1 == rd && rd != Register::X0 => {
+                let a1 = alu(opcode, b, c);
+                Fused::Constants {
+                    rd1: rd,
+                    a1,
+                    rd2,
+                    a2: alu(opcode2, a1, imm),
+                }
+            }
+            (Op::Auipc { rd, imm }, Op::Jalr { rs1, .. } | Op::JalrX0 { rs1, .. })
+                if rs1 == rd && rd != Register::X0 =>
+            {
+                let (link, offset) = match ops[i + 1] {
+                    Op::Jalr { rd, imm, .. } => (rd, imm),
+                    Op::JalrX0 { imm, .. } => (Register::X0, imm),
+                    _ => unreachable!(),
+                };
+                let a = pc.wrapping_add(imm);
+                Fused::Call {
+                    rd,
+                    a,
+                    link,
+                    target: a.wrapping_add(offset),
+                }
+            }
+            (
+                Op::Auipc { rd, imm },
+                Op::Load {
+                    opcode,
+                    rd: load_rd,
+                    rs1,
+                    imm: offset,
+                },
+            ) if rs1 == rd && rd != Register::X0 => {
+                let a = pc.wrapping_add(imm);
+                Fused::AuipcLoad {
+                    rd,
+                    a,
+                    opcode,
+                    load_rd,
+                    addr: a.wrapping_add(offset),
+                }
+            }
+            (
+                Op::AluRegisters {
+                    opcode: opcode @ (Opcode::SLT | Opcode::SLTU),
+                    rd,
+                    rs1,
+                    rs2,
+                },
+                Op::Branch {
+                    opcode: branch @ (Opcode::BEQ | Opcode::BNE),
+                    rs1: a,
+                    rs2: Register::X0,
+                    imm,
+                },
+            ) if a == rd => Fused::CompareBranch {
+                opcode,
+                rd,
+                rs1,
+                rs2,
+                taken_if_set: branch == Opcode::BNE,
+                target: (pc + 4).wrapping_add(imm),
+            },
+            _ => continue,
+        };
+        ops[i] = Op::Fused(fused);
+    }
+}
+
+/// Compute the result of an ALU operation.
+#[inline]
+#[must_use]
+pub fn alu(opcode: Opcode, b: u32, c: u32) -> u32 {
+    match opcode {
+        Opcode::ADD => b.wrapping_add(c),
+        Opcode::SUB => b.wrapping_sub(c),
+        Opcode::XOR => b ^ c,
+        Opcode::OR => b | c,
+        Opcode::AND => b & c,
+        Opcode::SLL => b.wrapping_shl(c),
+        Opcode::SRL => b.wrapping_shr(c),
+        Opcode::SRA => (b as i32).wrapping_shr(c) as u32,
+        Opcode::SLT => u32::from((b as i32) < (c as i32)),
+        Opcode::SLTU => u32::from(b < c),
+        Opcode::MUL => b.wrapping_mul(c),
+        Opcode::MULH => (((b as i32) as i64).wrapping_mul((c as i32) as i64) >> 32) as u32,
+        Opcode::MULHU => ((b as u64).wrapping_mul(c as u64) >> 32) as u32,
+        Opcode::MULHSU => (((b as i32) as i64).wrapping_mul(c as i64) >> 32) as u32,
+        Opcode::DIV => {
+            if c == 0 {
+                u32::MAX
+            } else {
+                (b as i32).wrapping_div(c as i32) as u32
+            }
+        }
+        Opcode::DIVU => {
+            if c == 0 {
+                u32::MAX
+            } else {
+                b.wrapping_div(c)
+            }
+        }
+        Opcode::REM => {
+            if c == 0 {
+                b
+            } else {
+                (b as i32).wrapping_rem(c as i32) as u32
+            }
+        }
+        Opcode::REMU => {
+            if c == 0 {
+                b
+            } else {
+                b.wrapping_rem(c)
+            }
+        }
+        _ => unreachable!("{opcode:?} is not an ALU operation"),
+    }
+}
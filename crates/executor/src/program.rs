@@ -4,6 +4,7 @@ use std::{fs::File, io::Read};
 
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
+use tiny_keccak::{Hasher, Keccak};
 
 use crate::{
     disassembler::{transpile, Elf},
@@ -38,6 +39,49 @@ impl Program {
         }
     }
 
+    /// Compute the Keccak-256 digest of the program, which identifies it in checkpoint files.
+    #[must_use]
+    pub fn digest(&self) -> [u8; 32] {
+        // Sort the memory image so that the digest doesn't depend on the order of the map.
+        let mut memory_image = self.memory_image.iter().collect::<Vec<_>>();
+        memory_image.sort_unstable();
+        let bytes = bincode::serialize(&(
+            &self.instructions,
+            self.pc_start,
+            self.pc_base,
+            memory_image,
+        ))
+        .unwrap();
+
+        let mut hasher = Keccak::v256();
+        hasher.update(&bytes);
+        let mut digest = [0; 32];
+        hasher.finalize(&mut digest);
+        digest
+    }
+
+    /// Split the program into basic blocks, returning for each instruction the index one past the
+    /// last instruction of the block that starts at it.
+    ///
+    /// Blocks end at branches, jumps and `ECALL`, after which execution may not continue with the
+    /// next instruction or may continue in another mode. Since jumps can land in the middle of a
+    /// block, every instruction starts a block, which ends where the block containing it does.
+    #[must_use]
+    pub fn basic_block_ends(&self) -> Vec<u32> {
+        let mut ends = vec![0; self.instructions.len()];
+        let mut end = self.instructions.len() as u32;
+        for (index, instruction) in self.instructions.iter().enumerate().rev() {
+            if instruction.is_branch_instruction()
+                || instruction.is_jump_instruction()
+                || instruction.is_ecall_instruction()
+            {
+                end = index as u32 + 1;
+            }
+            ends[index] = end;
+        }
+        ends
+    }
+
     /// Disassemble a RV32IM ELF to a program that be executed by the VM.
     ///
     /// # Errors
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::{
+        AluEvent, CpuEvent, MemoryInitializeFinalizeEvent, MemoryLocalEvent, PrecompileEvent,
+        PrecompileEvents, SyscallEvent,
+    },
+    syscalls::SyscallCode,
+    Opcode, Program,
+};
+
+/// A record of the execution of a program.
+///
+/// The trace of the execution is represented as a list of "events" that occur every cycle. A
+/// record holds the events of a single shard.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    /// The program.
+    pub program: Arc<Program>,
+    /// The shard number.
+    pub shard: u32,
+    /// A trace of the CPU events which get emitted during execution.
+    pub cpu_events: Vec<CpuEvent>,
+    /// A trace of the ADD, and ADDI events.
+    pub add_events: Vec<AluEvent>,
+    /// A trace of the MUL events.
+    pub mul_events: Vec<AluEvent>,
+    /// A trace of the SUB events.
+    pub sub_events: Vec<AluEvent>,
+    /// A trace of the XOR, XORI, OR, ORI, AND, and ANDI events.
+    pub bitwise_events: Vec<AluEvent>,
+    /// A trace of the SLL and SLLI events.
+    pub shift_left_events: Vec<AluEvent>,
+    /// A trace of the SRL, SRLI, SRA, and SRAI events.
+    pub shift_right_events: Vec<AluEvent>,
+    /// A trace of the DIV, DIVU, REM, and REMU events.
+    pub divrem_events: Vec<AluEvent>,
+    /// A trace of the SLT, SLTI, SLTU, and SLTIU events.
+    pub lt_events: Vec<AluEvent>,
+    /// All the precompile events.
+    pub precompile_events: PrecompileEvents,
+    /// A trace of all the syscall events.
+    pub syscall_events: Vec<SyscallEvent>,
+    /// The local memory access events of the CPU.
+    pub cpu_local_memory_access: Vec<MemoryLocalEvent>,
+    /// A trace of the memory initialize events, sorted by address.
+    pub global_memory_initialize_events: Vec<MemoryInitializeFinalizeEvent>,
+    /// A trace of the memory finalize events, sorted by address.
+    pub global_memory_finalize_events: Vec<MemoryInitializeFinalizeEvent>,
+}
+
+impl ExecutionRecord {
+    /// Create a new [`ExecutionRecord`].
+    #[must_use]
+    pub fn new(program: Arc<Program>) -> Self {
+        Self {
+            program,
+            ..Default::default()
+        }
+    }
+
+    /// Add an ALU event to the table of its opcode.
+    pub fn add_alu_event(&mut self, event: AluEvent) {
+        match event.opcode {
+            Opcode::ADD => self.add_events.push(event),
+            Opcode::SUB => self.sub_events.push(event),
+            Opcode::XOR | Opcode::OR | Opcode::AND => self.bitwise_events.push(event),
+            Opcode::SLL => self.shift_left_events.push(event),
+            Opcode::SRL | Opcode::SRA => self.shift_right_events.push(event),
+            Opcode::SLT | Opcode::SLTU => self.lt_events.push(event),
+            Opcode::MUL | Opcode::MULHU | Opcode::MULHSU | Opcode::MULH => {
+                self.mul_events.push(event);
+            }
+            Opcode::DIVU | Opcode::REMU | Opcode::DIV | Opcode::REM => {
+                self.divrem_events.push(event);
+            }
+            _ => unreachable!("{} is not an ALU opcode", event.opcode),
+        }
+    }
+
+    /// Add a precompile event to the execution record.
+    pub fn add_precompile_event(
+        &mut self,
+        syscall_code: SyscallCode,
+        syscall_event: SyscallEvent,
+        event: PrecompileEvent,
+    ) {
+        self.precompile_events
+            .add_event(syscall_code, syscall_event, event);
+    }
+
+    /// Whether any CPU cycles or memory initialize/finalize events were recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cpu_events.is_empty()
+            && self.global_memory_initialize_events.is_empty()
+            && self.global_memory_finalize_events.is_empty()
+    }
+}
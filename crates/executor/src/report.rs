@@ -0,0 +1,101 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    ops::{Add, AddAssign},
+};
+
+use enum_map::{EnumArray, EnumMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{events::sorted_table_lines, syscalls::SyscallCode, Opcode};
+
+/// An execution report.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    /// The opcode counts.
+    pub opcode_counts: Box<EnumMap<Opcode, u64>>,
+    /// The syscall counts.
+    pub syscall_counts: Box<EnumMap<SyscallCode, u64>>,
+    /// The total number of cycles of the execution.
+    pub total_cycles: u64,
+    /// The total cycles of each named `cycle-tracker-report` span.
+    pub cycle_tracker: BTreeMap<String, u64>,
+}
+
+impl ExecutionReport {
+    /// Compute the total number of instructions run during the execution.
+    #[must_use]
+    pub fn total_instruction_count(&self) -> u64 {
+        self.opcode_counts.values().sum()
+    }
+
+    /// Compute the total number of syscalls made during the execution.
+    #[must_use]
+    pub fn total_syscall_count(&self) -> u64 {
+        self.syscall_counts.values().sum()
+    }
+}
+
+/// Adds the counts of `rhs` to the counts of `lhs`.
+fn counts_add_assign<K, V>(lhs: &mut EnumMap<K, V>, rhs: EnumMap<K, V>)
+where
+    K: EnumArray<V>,
+    V: AddAssign,
+{
+    for (k, v) in rhs {
+        lhs[k] += v;
+    }
+}
+
+impl AddAssign for ExecutionReport {
+    fn add_assign(&mut self, rhs: Self) {
+        counts_add_assign(&mut self.opcode_counts, *rhs.opcode_counts);
+        counts_add_assign(&mut self.syscall_counts, *rhs.syscall_counts);
+        self.total_cycles += rhs.total_cycles;
+        for (name, cycles) in rhs.cycle_tracker {
+            *self.cycle_tracker.entry(name).or_default() += cycles;
+        }
+    }
+}
+
+impl Add for ExecutionReport {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl Display for ExecutionReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "total cycles: {}", self.total_cycles)?;
+
+        writeln!(
+            f,
+            "opcode counts ({} total instructions):",
+            self.total_instruction_count()
+        )?;
+        for line in sorted_table_lines(self.opcode_counts.iter().filter(|(_, &count)| count > 0)) {
+            writeln!(f, "  {line}")?;
+        }
+
+        writeln!(
+            f,
+            "syscall counts ({} total syscall instructions):",
+            self.total_syscall_count()
+        )?;
+        for line in sorted_table_lines(self.syscall_counts.iter().filter(|(_, &count)| count > 0)) {
+            writeln!(f, "  {line}")?;
+        }
+
+        if !self.cycle_tracker.is_empty() {
+            writeln!(f, "cycle tracker:")?;
+            for line in sorted_table_lines(&self.cycle_tracker) {
+                writeln!(f, "  {line}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,13 +1,26 @@
 use std::{
     fs::File,
-    io::{Seek, Write},
+    io::{BufReader, BufWriter, Read, Seek, Write},
 };
 
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-use crate::memory_map::MemoryMap;
-use crate::{events::MemoryRecord, syscalls::SyscallCode, ExecutorMode};
+use crate::memory_map::{MemoryMap, UninitializedMemory};
+use crate::{
+    events::MemoryRecord, record::ExecutionRecord, syscalls::SyscallCode, ExecutionError,
+    ExecutorMode, MemoryAccessRecord, Program,
+};
+
+/// The magic bytes at the start of every checkpoint file.
+pub const CHECKPOINT_MAGIC: [u8; 8] = *b"SP1CKPT\0";
+
+/// The version of the checkpoint file format.
+///
+/// It must be bumped whenever the encoding of [`ExecutionState`] changes, including the layout of
+/// the memory maps it holds.
+pub const CHECKPOINT_VERSION: u32 = 5;
 
 /// Holds data describing the current state of a program's execution.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -19,9 +32,25 @@ pub struct ExecutionState {
     /// The shard clock keeps track of how many shards have been executed.
     pub current_shard: u32,
 
-    /// The memory which instructions operate over. Values contain the memory value and last shard
-    /// + timestamp that each memory address was accessed.
-    pub memory: MemoryMap<MemoryRecord>,
+    /// The values of the registers.
+    pub registers: [u32; 32],
+
+    /// The shard and timestamp of the last access to each register, or `None` if it wasn't
+    /// accessed yet.
+    ///
+    /// Like `memory_timestamps`, these are only kept up to date by [`ExecutorMode::Trace`] and
+    /// [`ExecutorMode::Checkpoint`].
+    pub register_timestamps: [Option<MemoryTimestamp>; 32],
+
+    /// The memory which instructions operate over, holding the value of each address.
+    pub memory: MemoryMap<u32>,
+
+    /// The shard and timestamp of the last access to each memory address.
+    ///
+    /// These are kept apart from the values since only [`ExecutorMode::Trace`] and
+    /// [`ExecutorMode::Checkpoint`] keep them up to date. An address without an entry was last
+    /// accessed at shard 0 and timestamp 0.
+    pub memory_timestamps: MemoryMap<MemoryTimestamp>,
 
     /// The global clock keeps track of how many instructions have been executed through all shards.
     pub global_clk: u64,
@@ -32,7 +61,7 @@ pub struct ExecutionState {
 
     /// Uninitialized memory addresses that have a specific value they should be initialized with.
     /// `SyscallHintRead` uses this to write hint data into uninitialized memory.
-    pub uninitialized_memory: HashMap<u32, u32>,
+    pub uninitialized_memory: UninitializedMemory,
 
     /// A stream of input values (global to the entire program).
     pub input_stream: Vec<Vec<u8>>,
@@ -64,8 +93,11 @@ impl ExecutionState {
             current_shard: 1,
             clk: 0,
             pc: pc_start,
-            memory: MemoryMap::with_capacity(200_000),
-            uninitialized_memory: HashMap::new(),
+            registers: [0; 32],
+            register_timestamps: [None; 32],
+            memory: MemoryMap::new(),
+            memory_timestamps: MemoryMap::new(),
+            uninitialized_memory: UninitializedMemory::new(),
             input_stream: Vec::new(),
             input_stream_ptr: 0,
             public_values_stream: Vec::new(),
@@ -76,6 +108,23 @@ impl ExecutionState {
     }
 }
 
+/// The shard and timestamp of an access to a memory address.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryTimestamp {
+    /// The shard number.
+    pub shard: u32,
+    /// The timestamp.
+    pub timestamp: u32,
+}
+
+impl MemoryTimestamp {
+    /// Create a new [`MemoryTimestamp`].
+    #[must_use]
+    pub const fn new(shard: u32, timestamp: u32) -> Self {
+        Self { shard, timestamp }
+    }
+}
+
 /// Holds data to track changes made to the runtime since a fork point.
 #[derive(Debug, Clone, Default)]
 #[allow(dead_code)]
@@ -86,23 +135,171 @@ pub struct ForkState {
     pub clk: u32,
     /// The original `pc` value at the fork point.
     pub pc: u32,
+    /// The original values of the registers at the fork point.
+    pub registers: [u32; 32],
+    /// The original last accesses of the registers at the fork point.
+    pub register_timestamps: [Option<MemoryTimestamp>; 32],
     /// All memory changes since the fork point.
     pub memory_diff: HashMap<u32, Option<MemoryRecord>>,
-    // /// The original memory access record at the fork point.
-    // pub op_record: MemoryAccessRecord,
-    // /// The original execution record at the fork point.
-    // pub record: ExecutionRecord,
+    /// The original memory access record at the fork point.
+    pub op_record: MemoryAccessRecord,
+    /// The original execution record at the fork point.
+    pub record: ExecutionRecord,
     /// Whether `emit_events` was enabled at the fork point.
     pub executor_mode: ExecutorMode,
 }
 
+/// The header of a checkpoint file, followed by the [`ExecutionState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointHeader {
+    /// Always [`CHECKPOINT_MAGIC`].
+    pub magic: [u8; 8],
+    /// The version of the format the file was written with.
+    pub version: u32,
+    /// The [`Program::digest`] of the program the checkpoint was taken from.
+    pub program_digest: [u8; 32],
+}
+
+/// Errors that can occur while saving or loading a checkpoint file.
+#[derive(Error, Debug)]
+pub enum CheckpointError {
+    /// Reading or writing the file failed.
+    #[error("checkpoint io failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The contents of the file could not be encoded or decoded.
+    #[error("malformed checkpoint: {0}")]
+    Malformed(#[from] bincode::Error),
+
+    /// The file does not start with [`CHECKPOINT_MAGIC`].
+    #[error("not a checkpoint file")]
+    InvalidMagic,
+
+    /// The file was written with another version of the format.
+    #[error("unsupported checkpoint version {0}, expected {CHECKPOINT_VERSION}")]
+    UnsupportedVersion(u32),
+
+    /// The checkpoint was taken from another program.
+    #[error("checkpoint was taken from a different program")]
+    ProgramMismatch,
+
+    /// The context to resume with is invalid.
+    #[error("invalid context: {0}")]
+    InvalidContext(#[from] ExecutionError),
+}
+
+impl CheckpointHeader {
+    /// Create the header of a checkpoint of `program`.
+    #[must_use]
+    pub fn new(program: &Program) -> Self {
+        Self {
+            magic: CHECKPOINT_MAGIC,
+            version: CHECKPOINT_VERSION,
+            program_digest: program.digest(),
+        }
+    }
+
+    /// Check that the header belongs to a checkpoint of `program` that this version can read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the magic, the version or the program digest doesn't match.
+    pub fn validate(&self, program: &Program) -> Result<(), CheckpointError> {
+        if self.magic != CHECKPOINT_MAGIC {
+            return Err(CheckpointError::InvalidMagic);
+        }
+        if self.version != CHECKPOINT_VERSION {
+            return Err(CheckpointError::UnsupportedVersion(self.version));
+        }
+        if self.program_digest != program.digest() {
+            return Err(CheckpointError::ProgramMismatch);
+        }
+        Ok(())
+    }
+}
+
 impl ExecutionState {
-    /// Save the execution state to a file.
-    pub fn save(&self, file: &mut File) -> std::io::Result<()> {
-        let mut writer = std::io::BufWriter::new(file);
-        bincode::serialize_into(&mut writer, self).unwrap();
+    /// Get the value of a memory address along with the shard and timestamp of its last access,
+    /// or `None` if it was never accessed.
+    ///
+    /// Addresses below 32 are the registers, which only have a record once they were accessed by
+    /// [`ExecutorMode::Trace`] or [`ExecutorMode::Checkpoint`].
+    #[must_use]
+    pub fn memory_record(&self, addr: u32) -> Option<MemoryRecord> {
+        if addr < 32 {
+            let last = self.register_timestamps[addr as usize]?;
+            let value = self.registers[addr as usize];
+            return Some(MemoryRecord::new(last.shard, last.timestamp, value));
+        }
+
+        let value = *self.memory.get(&addr)?;
+        let last = self
+            .memory_timestamps
+            .get(&addr)
+            .copied()
+            .unwrap_or_default();
+        Some(MemoryRecord::new(last.shard, last.timestamp, value))
+    }
+
+    /// Set the value and last access of a memory address, or forget it if `record` is `None`.
+    pub fn set_memory_record(&mut self, addr: u32, record: Option<MemoryRecord>) {
+        if addr < 32 {
+            self.registers[addr as usize] = record.map_or(0, |record| record.value);
+            self.register_timestamps[addr as usize] =
+                record.map(|record| MemoryTimestamp::new(record.shard, record.timestamp));
+            return;
+        }
+
+        if let Some(record) = record {
+            self.memory.insert(addr, record.value);
+            self.memory_timestamps
+                .insert(addr, MemoryTimestamp::new(record.shard, record.timestamp));
+        } else {
+            self.memory.remove(&addr);
+            self.memory_timestamps.remove(&addr);
+        }
+    }
+
+    /// Save the execution state of `program` to a checkpoint file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the file fails.
+    pub fn save(&self, program: &Program, file: &mut File) -> Result<(), CheckpointError> {
+        let mut writer = BufWriter::new(file);
+        self.write_checkpoint(program, &mut writer)?;
         writer.flush()?;
         writer.seek(std::io::SeekFrom::Start(0))?;
         Ok(())
     }
+
+    /// Load the execution state of `program` from a checkpoint file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading the file fails, or if it isn't a checkpoint of `program`.
+    pub fn load(program: &Program, file: &mut File) -> Result<Self, CheckpointError> {
+        Self::read_checkpoint(program, &mut BufReader::new(file))
+    }
+
+    /// Write the header of the checkpoint and the execution state.
+    pub(crate) fn write_checkpoint(
+        &self,
+        program: &Program,
+        writer: &mut impl Write,
+    ) -> Result<(), CheckpointError> {
+        bincode::serialize_into(&mut *writer, &CheckpointHeader::new(program))?;
+        bincode::serialize_into(writer, self)?;
+        Ok(())
+    }
+
+    /// Read and validate the header of the checkpoint, then read the execution state.
+    pub(crate) fn read_checkpoint(
+        program: &Program,
+        reader: &mut impl Read,
+    ) -> Result<Self, CheckpointError> {
+        let header: CheckpointHeader = bincode::deserialize_from(&mut *reader)?;
+        header.validate(program)?;
+        Ok(bincode::deserialize_from(reader)?)
+    }
 }
@@ -131,10 +131,10 @@ pub enum SyscallCode {
 }
 
 impl SyscallCode {
-    /// Create a [`SyscallCode`] from a u32.
+    /// Create a [`SyscallCode`] from a u32, or `None` if the number isn't a known syscall.
     #[must_use]
-    pub fn from_u32(value: u32) -> Self {
-        match value {
+    pub fn from_u32(value: u32) -> Option<Self> {
+        let code = match value {
             0x00_00_00_00 => SyscallCode::HALT,
             0x00_00_00_02 => SyscallCode::WRITE,
             0x00_00_00_03 => SyscallCode::ENTER_UNCONSTRAINED,
@@ -170,8 +170,9 @@ impl SyscallCode {
             0x00_01_01_2A => SyscallCode::BN254_FP2_SUB,
             0x00_01_01_2B => SyscallCode::BN254_FP2_MUL,
             0x00_00_01_1C => SyscallCode::BLS12381_DECOMPRESS,
-            _ => panic!("invalid syscall number: {value}"),
-        }
+            _ => return None,
+        };
+        Some(code)
     }
 
     /// Get the system call identifier.
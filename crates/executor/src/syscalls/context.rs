@@ -3,7 +3,7 @@ use hashbrown::HashMap;
 use crate::{
     events::{LookupId, MemoryLocalEvent, MemoryReadRecord, MemoryWriteRecord},
     memory_map::MemoryMap,
-    Executor, Register,
+    ExecutionError, ExecutionRecord, Executor, ExecutorMode, Opcode, Register,
 };
 
 pub type LocalMemAccessMap = HashMap<u32, MemoryLocalEvent>;
@@ -44,10 +44,10 @@ impl<'a, 'b> SyscallContext<'a, 'b> {
         }
     }
 
-    // /// Get a mutable reference to the execution record.
-    // pub fn record_mut(&mut self) -> &mut ExecutionRecord {
-    //     &mut self.rt.record
-    // }
+    /// Get a mutable reference to the execution record.
+    pub fn record_mut(&mut self) -> &mut ExecutionRecord {
+        &mut self.rt.record
+    }
 
     /// Get the current shard.
     #[must_use]
@@ -68,16 +68,31 @@ impl<'a, 'b> SyscallContext<'a, 'b> {
 
     /// Read a slice of words from memory.
     pub fn mr_slice(&mut self, addr: u32, len: usize) -> (Vec<MemoryReadRecord>, Vec<u32>) {
-        let mut records = Vec::new();
-        let mut values = Vec::new();
-        for i in 0..len {
-            let (record, value) = self.mr(addr + i as u32 * 4);
-            records.push(record);
-            values.push(value);
-        }
+        let mut records = vec![MemoryReadRecord::default(); len];
+        self.rt.mr_words(
+            addr,
+            self.current_shard,
+            self.clk,
+            Some(&mut self.local_memory_access),
+            &mut records,
+        );
+        let values = records.iter().map(|record| record.value).collect();
         (records, values)
     }
 
+    /// Read `N` consecutive words from memory into arrays, without allocating.
+    pub fn mr_array<const N: usize>(&mut self, addr: u32) -> ([MemoryReadRecord; N], [u32; N]) {
+        let mut records = [MemoryReadRecord::default(); N];
+        self.rt.mr_words(
+            addr,
+            self.current_shard,
+            self.clk,
+            Some(&mut self.local_memory_access),
+            &mut records,
+        );
+        (records, records.map(|record| record.value))
+    }
+
     /// Write a word to memory.
     pub fn mw(&mut self, addr: u32, value: u32) -> MemoryWriteRecord {
         self.rt.mw(
@@ -91,14 +106,83 @@ impl<'a, 'b> SyscallContext<'a, 'b> {
 
     /// Write a slice of words to memory.
     pub fn mw_slice(&mut self, addr: u32, values: &[u32]) -> Vec<MemoryWriteRecord> {
-        let mut records = Vec::new();
-        for i in 0..values.len() {
-            let record = self.mw(addr + i as u32 * 4, values[i]);
-            records.push(record);
-        }
+        let mut records = vec![MemoryWriteRecord::default(); values.len()];
+        self.rt.mw_words(
+            addr,
+            values,
+            self.current_shard,
+            self.clk,
+            Some(&mut self.local_memory_access),
+            &mut records,
+        );
+        records
+    }
+
+    /// Write `N` consecutive words to memory, returning the records in an array without allocating.
+    pub fn mw_array<const N: usize>(
+        &mut self,
+        addr: u32,
+        values: &[u32; N],
+    ) -> [MemoryWriteRecord; N] {
+        let mut records = [MemoryWriteRecord::default(); N];
+        self.rt.mw_words(
+            addr,
+            values,
+            self.current_shard,
+            self.clk,
+            Some(&mut self.local_memory_access),
+            &mut records,
+        );
         records
     }
 
+    /// Check that `len` bytes from `addr` lie in memory, past the registers and the reserved
+    /// addresses below `0x10000`, without wrapping around the address space.
+    ///
+    /// Syscalls check the memory they touch before reading or writing it, since a pointer from the
+    /// guest may be anything.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ExecutionError::InvalidMemoryAccess`] if the bytes don't lie in memory.
+    pub fn check_bytes(&self, addr: u32, len: u32) -> Result<(), ExecutionError> {
+        if len == 0 || (addr >= 0x10000 && addr.checked_add(len - 1).is_some()) {
+            Ok(())
+        } else {
+            Err(ExecutionError::InvalidMemoryAccess(Opcode::ECALL, addr))
+        }
+    }
+
+    /// Check that `len` words from `addr` are aligned and lie in memory, like
+    /// [`Self::check_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ExecutionError::MisalignedSyscallPointer`] if `addr` isn't aligned to 4 bytes, and
+    /// [`ExecutionError::InvalidMemoryAccess`] if the words don't lie in memory.
+    pub fn check_words(
+        &self,
+        syscall_code: u32,
+        addr: u32,
+        len: usize,
+    ) -> Result<(), ExecutionError> {
+        if !addr.is_multiple_of(4) {
+            return Err(ExecutionError::MisalignedSyscallPointer(syscall_code, addr));
+        }
+        let len = u32::try_from(len)
+            .ok()
+            .and_then(|len| len.checked_mul(4))
+            .ok_or(ExecutionError::InvalidMemoryAccess(Opcode::ECALL, addr))?;
+        self.check_bytes(addr, len)
+    }
+
+    /// Whether the events of the syscall are kept, which is only the case when the executor is
+    /// tracing outside of unconstrained mode. Precompiles skip building their events otherwise.
+    #[must_use]
+    pub fn emits_events(&self) -> bool {
+        self.rt.executor_mode == ExecutorMode::Trace && !self.rt.unconstrained
+    }
+
     /// Postprocess the syscall.  Specifically will process the syscall's memory local events.
     pub fn postprocess(&mut self) -> Vec<MemoryLocalEvent> {
         let mut syscall_local_mem_events = Vec::new();
@@ -111,6 +195,7 @@ impl<'a, 'b> SyscallContext<'a, 'b> {
                 self.rt.local_memory_access.remove(&addr);
                 syscall_local_mem_events.push(event);
             }
+            syscall_local_mem_events.sort_unstable_by_key(|event| event.addr);
         }
 
         syscall_local_mem_events
@@ -138,10 +223,16 @@ impl<'a, 'b> SyscallContext<'a, 'b> {
     /// Get a slice of words, but doesn't use a memory record.
     #[must_use]
     pub fn slice_unsafe(&mut self, addr: u32, len: usize) -> Vec<u32> {
-        let mut values = Vec::new();
-        for i in 0..len {
-            values.push(self.rt.word(addr + i as u32 * 4));
-        }
+        let mut values = vec![0; len];
+        self.rt.words(addr, &mut values);
+        values
+    }
+
+    /// Get `N` consecutive words as an array, but doesn't use a memory record.
+    #[must_use]
+    pub fn array_unsafe<const N: usize>(&mut self, addr: u32) -> [u32; N] {
+        let mut values = [0; N];
+        self.rt.words(addr, &mut values);
         values
     }
 
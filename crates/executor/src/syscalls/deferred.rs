@@ -1,10 +1,17 @@
 use super::{Syscall, SyscallCode, SyscallContext};
+use crate::ExecutionError;
 
 pub(crate) struct CommitDeferredSyscall;
 
 impl Syscall for CommitDeferredSyscall {
     #[allow(clippy::mut_mut)]
-    fn execute(&self, _: &mut SyscallContext, _: SyscallCode, _: u32, _: u32) -> Option<u32> {
-        None
+    fn execute(
+        &self,
+        _: &mut SyscallContext,
+        _: SyscallCode,
+        _: u32,
+        _: u32,
+    ) -> Result<Option<u32>, ExecutionError> {
+        Ok(None)
     }
 }
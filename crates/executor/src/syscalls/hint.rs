@@ -1,4 +1,5 @@
 use super::{Syscall, SyscallCode, SyscallContext};
+use crate::{ExecutionError, ExecutorMode};
 
 pub(crate) struct HintLenSyscall;
 
@@ -9,65 +10,62 @@ impl Syscall for HintLenSyscall {
         _: SyscallCode,
         _arg1: u32,
         _arg2: u32,
-    ) -> Option<u32> {
-        if ctx.rt.state.input_stream_ptr >= ctx.rt.state.input_stream.len() {
-            panic!(
-                "failed reading stdin due to insufficient input data: input_stream_ptr={}, input_stream_len={}",
-                ctx.rt.state.input_stream_ptr,
-                ctx.rt.state.input_stream.len()
-            );
-        }
-        Some(ctx.rt.state.input_stream[ctx.rt.state.input_stream_ptr].len() as u32)
+    ) -> Result<Option<u32>, ExecutionError> {
+        let vec = next_input(ctx)?;
+        Ok(Some(vec.len() as u32))
     }
 }
 
 pub(crate) struct HintReadSyscall;
 
 impl Syscall for HintReadSyscall {
-    fn execute(&self, ctx: &mut SyscallContext, _: SyscallCode, ptr: u32, len: u32) -> Option<u32> {
-        if ctx.rt.state.input_stream_ptr >= ctx.rt.state.input_stream.len() {
-            panic!(
-                "failed reading stdin due to insufficient input data: input_stream_ptr={}, input_stream_len={}",
-                ctx.rt.state.input_stream_ptr,
-                ctx.rt.state.input_stream.len()
-            );
+    fn execute(
+        &self,
+        ctx: &mut SyscallContext,
+        syscall_code: SyscallCode,
+        ptr: u32,
+        len: u32,
+    ) -> Result<Option<u32>, ExecutionError> {
+        if ctx.rt.unconstrained {
+            return Err(ExecutionError::InvalidSyscallUsage(syscall_code as u64));
         }
-        let vec = &ctx.rt.state.input_stream[ctx.rt.state.input_stream_ptr];
+        let vec = next_input(ctx)?;
+        if vec.len() != len as usize {
+            return Err(ExecutionError::HintLengthMismatch(len, vec.len()));
+        }
+        // Split the vec into words. In case the vec is not a multiple of 4, right-pad with 0s.
+        // This is fine because we are assuming the words are uninitialized, so filling them with
+        // 0s makes sense.
+        let words = vec
+            .chunks(4)
+            .map(|chunk| {
+                let mut bytes = [0; 4];
+                bytes[..chunk.len()].copy_from_slice(chunk);
+                u32::from_le_bytes(bytes)
+            })
+            .collect::<Vec<_>>();
+        ctx.check_words(syscall_code as u32, ptr, words.len())?;
         ctx.rt.state.input_stream_ptr += 1;
-        assert!(
-            !ctx.rt.unconstrained,
-            "hint read should not be used in a unconstrained block"
-        );
-        assert_eq!(
-            vec.len() as u32,
-            len,
-            "hint input stream read length mismatch"
-        );
-        assert_eq!(ptr % 4, 0, "hint read address not aligned to 4 bytes");
-        // Iterate through the vec in 4-byte chunks
-        for i in (0..len).step_by(4) {
-            // Get each byte in the chunk
-            let b1 = vec[i as usize];
-            // In case the vec is not a multiple of 4, right-pad with 0s. This is fine because we
-            // are assuming the word is uninitialized, so filling it with 0s makes sense.
-            let b2 = vec.get(i as usize + 1).copied().unwrap_or(0);
-            let b3 = vec.get(i as usize + 2).copied().unwrap_or(0);
-            let b4 = vec.get(i as usize + 3).copied().unwrap_or(0);
-            let word = u32::from_le_bytes([b1, b2, b3, b4]);
 
-            // Save the data into runtime state so the runtime will use the desired data instead of
-            // 0 when first reading/writing from this address.
-            ctx.rt
-                .uninitialized_memory_checkpoint
-                .entry(ptr + i)
-                .or_insert_with(|| false);
-            ctx.rt
-                .state
-                .uninitialized_memory
-                .entry(ptr + i)
-                .and_modify(|_| panic!("hint read address is initialized already"))
-                .or_insert(word);
+        // Save the data into runtime state so the runtime will use the desired data instead of 0
+        // when first reading/writing from these addresses.
+        if ctx.rt.executor_mode == ExecutorMode::Checkpoint && !words.is_empty() {
+            ctx.rt.hint_checkpoint.push(ptr);
         }
-        None
+        ctx.rt.state.uninitialized_memory.insert(ptr, words)?;
+        Ok(None)
     }
 }
+
+/// Get the next input of the input stream, without consuming it.
+fn next_input<'a>(ctx: &'a SyscallContext) -> Result<&'a [u8], ExecutionError> {
+    let state = &ctx.rt.state;
+    state
+        .input_stream
+        .get(state.input_stream_ptr)
+        .map(Vec::as_slice)
+        .ok_or(ExecutionError::StdinExhausted(
+            state.input_stream_ptr,
+            state.input_stream.len(),
+        ))
+}
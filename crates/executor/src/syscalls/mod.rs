@@ -45,25 +45,34 @@ use unconstrained::{EnterUnconstrainedSyscall, ExitUnconstrainedSyscall};
 use verify::VerifySyscall;
 use write::WriteSyscall;
 
-use crate::events::FieldOperation;
+use crate::{events::FieldOperation, ExecutionError};
 
 /// A system call in the SP1 RISC-V zkVM.
 ///
 /// This trait implements methods needed to execute a system call inside the [`crate::Executor`].
-pub trait Syscall: Send + Sync {
+///
+/// The system calls that [`SyscallCode`] lists implement `Syscall`, while the ones it doesn't,
+/// such as prototypes of new precompiles, implement `Syscall<u32>` and are given their number as
+/// is. See [`crate::SP1ContextBuilder::custom_syscall`].
+pub trait Syscall<Code = SyscallCode>: Send + Sync {
     /// Executes the syscall.
     ///
     /// Returns the resulting value of register a0. `arg1` and `arg2` are the values in registers
     /// X10 and X11, respectively. While not a hard requirement, the convention is that the return
     /// value is only for system calls such as `HALT`. Most precompiles use `arg1` and `arg2` to
     /// denote the addresses of the input data, and write the result to the memory at `arg1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the program called the syscall with invalid arguments, which stops its
+    /// execution.
     fn execute(
         &self,
         ctx: &mut SyscallContext,
-        syscall_code: SyscallCode,
+        syscall_code: Code,
         arg1: u32,
         arg2: u32,
-    ) -> Option<u32>;
+    ) -> Result<Option<u32>, ExecutionError>;
 
     /// The number of extra cycles that the syscall takes to execute.
     ///
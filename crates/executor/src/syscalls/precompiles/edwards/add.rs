@@ -3,8 +3,9 @@ use std::marker::PhantomData;
 use sp1_curves::{edwards::EdwardsParameters, EllipticCurve};
 
 use crate::{
-    events::create_ec_add_event,
+    events::{create_ec_add_event, PrecompileEvent},
     syscalls::{Syscall, SyscallCode, SyscallContext},
+    ExecutionError,
 };
 
 pub(crate) struct EdwardsAddAssignSyscall<E: EllipticCurve + EdwardsParameters> {
@@ -28,11 +29,25 @@ impl<E: EllipticCurve + EdwardsParameters> Syscall for EdwardsAddAssignSyscall<E
     fn execute(
         &self,
         rt: &mut SyscallContext,
-        _: SyscallCode,
+        syscall_code: SyscallCode,
         arg1: u32,
         arg2: u32,
-    ) -> Option<u32> {
-        let _ = create_ec_add_event::<E>(rt, arg1, arg2);
-        None
+    ) -> Result<Option<u32>, ExecutionError> {
+        let Some(event) = create_ec_add_event::<E>(rt, syscall_code, arg1, arg2)? else {
+            return Ok(None);
+        };
+        let syscall_event = rt.rt.syscall_event(
+            event.clk,
+            syscall_code.syscall_id(),
+            arg1,
+            arg2,
+            event.lookup_id,
+        );
+        rt.record_mut().add_precompile_event(
+            syscall_code,
+            syscall_event,
+            PrecompileEvent::EdAdd(event),
+        );
+        Ok(None)
     }
 }
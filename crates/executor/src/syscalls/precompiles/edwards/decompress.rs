@@ -8,8 +8,9 @@ use sp1_curves::{
 use sp1_primitives::consts::{bytes_to_words_le, words_to_bytes_le};
 
 use crate::{
-    events::{EdDecompressEvent, MemoryReadRecord, MemoryWriteRecord},
+    events::{EdDecompressEvent, MemoryReadRecord, MemoryWriteRecord, PrecompileEvent},
     syscalls::{Syscall, SyscallCode, SyscallContext},
+    ExecutionError,
 };
 
 pub(crate) struct EdwardsDecompressSyscall<E: EdwardsParameters> {
@@ -29,14 +30,20 @@ impl<E: EdwardsParameters> Syscall for EdwardsDecompressSyscall<E> {
     fn execute(
         &self,
         rt: &mut SyscallContext,
-        _: SyscallCode,
+        syscall_code: SyscallCode,
         arg1: u32,
         sign: u32,
-    ) -> Option<u32> {
+    ) -> Result<Option<u32>, ExecutionError> {
         let start_clk = rt.clk;
         let slice_ptr = arg1;
-        assert!(slice_ptr % 4 == 0, "Pointer must be 4-byte aligned.");
-        assert!(sign <= 1, "Sign bit must be 0 or 1.");
+        // The compressed point is followed by its `y` coordinate.
+        rt.check_words(syscall_code as u32, slice_ptr, 2 * WORDS_FIELD_ELEMENT)?;
+        if sign > 1 {
+            return Err(ExecutionError::InvalidSyscallArgument(
+                syscall_code as u32,
+                sign,
+            ));
+        }
 
         let (y_memory_records_vec, y_vec) = rt.mr_slice(
             slice_ptr + (COMPRESSED_POINT_BYTES as u32),
@@ -68,21 +75,31 @@ impl<E: EdwardsParameters> Syscall for EdwardsDecompressSyscall<E> {
         let x_memory_records_vec = rt.mw_slice(slice_ptr, &decompressed_x_words);
         let x_memory_records: [MemoryWriteRecord; 8] = x_memory_records_vec.try_into().unwrap();
 
-        let lookup_id = rt.syscall_lookup_id;
-        let shard = rt.current_shard();
-        let _ = EdDecompressEvent {
-            lookup_id,
-            shard,
-            clk: start_clk,
-            ptr: slice_ptr,
-            sign: sign_bool,
-            y_bytes,
-            decompressed_x_bytes: decompressed_x_bytes.try_into().unwrap(),
-            x_memory_records,
-            y_memory_records,
-            local_mem_access: rt.postprocess(),
-        };
-        None
+        if rt.emits_events() {
+            let lookup_id = rt.syscall_lookup_id;
+            let shard = rt.current_shard();
+            let event = EdDecompressEvent {
+                lookup_id,
+                shard,
+                clk: start_clk,
+                ptr: slice_ptr,
+                sign: sign_bool,
+                y_bytes,
+                decompressed_x_bytes: decompressed_x_bytes.try_into().unwrap(),
+                x_memory_records,
+                y_memory_records,
+                local_mem_access: rt.postprocess(),
+            };
+            let syscall_event =
+                rt.rt
+                    .syscall_event(start_clk, syscall_code.syscall_id(), arg1, sign, lookup_id);
+            rt.record_mut().add_precompile_event(
+                syscall_code,
+                syscall_event,
+                PrecompileEvent::EdDecompress(event),
+            );
+        }
+        Ok(None)
     }
 
     fn num_extra_cycles(&self) -> u32 {
@@ -1,11 +1,14 @@
-use num::BigUint;
-use sp1_curves::{params::NumWords, weierstrass::FpOpField};
+use sp1_curves::{
+    params::NumWords,
+    weierstrass::{fp::FpElement, FieldType, FpOpField},
+};
 use std::marker::PhantomData;
 use typenum::Unsigned;
 
 use crate::{
-    events::{FieldOperation, FpOpEvent},
+    events::{FieldOperation, FpOpEvent, PrecompileEvent},
     syscalls::{Syscall, SyscallCode, SyscallContext},
+    ExecutionError,
 };
 
 pub struct FpOpSyscall<P> {
@@ -26,63 +29,73 @@ impl<P: FpOpField> Syscall for FpOpSyscall<P> {
     fn execute(
         &self,
         rt: &mut SyscallContext,
-        _: SyscallCode,
+        syscall_code: SyscallCode,
         arg1: u32,
         arg2: u32,
-    ) -> Option<u32> {
+    ) -> Result<Option<u32>, ExecutionError> {
         let clk = rt.clk;
+        let num_words = <P as NumWords>::WordsFieldElement::USIZE;
         let x_ptr = arg1;
-        if x_ptr % 4 != 0 {
-            panic!();
-        }
+        rt.check_words(syscall_code as u32, x_ptr, num_words)?;
         let y_ptr = arg2;
-        if y_ptr % 4 != 0 {
-            panic!();
-        }
-
-        let num_words = <P as NumWords>::WordsFieldElement::USIZE;
+        rt.check_words(syscall_code as u32, y_ptr, num_words)?;
 
         let x = rt.slice_unsafe(x_ptr, num_words);
         let (y_memory_records, y) = rt.mr_slice(y_ptr, num_words);
 
-        let modulus = &BigUint::from_bytes_le(P::MODULUS);
-        let a = BigUint::from_slice(&x) % modulus;
-        let b = BigUint::from_slice(&y) % modulus;
-
+        let a = P::Fp::from_words(&x);
+        let b = P::Fp::from_words(&y);
         let result = match self.op {
-            FieldOperation::Add => (a + b) % modulus,
-            FieldOperation::Sub => ((a + modulus) - b) % modulus,
-            FieldOperation::Mul => (a * b) % modulus,
+            FieldOperation::Add => a.add(&b),
+            FieldOperation::Sub => a.sub(&b),
+            FieldOperation::Mul => a.mul(&b),
             _ => panic!("Unsupported operation"),
         };
-        let mut result = result.to_u32_digits();
-        result.resize(num_words, 0);
+        let mut result_words = vec![0; num_words];
+        result.to_words(&mut result_words);
 
         rt.clk += 1;
-        let x_memory_records = rt.mw_slice(x_ptr, &result);
+        let x_memory_records = rt.mw_slice(x_ptr, &result_words);
 
-        let lookup_id = rt.syscall_lookup_id;
-        let shard = rt.current_shard();
-        let _ = FpOpEvent {
-            lookup_id,
-            shard,
-            clk,
-            x_ptr,
-            x,
-            y_ptr,
-            y,
-            op: self.op,
-            x_memory_records,
-            y_memory_records,
-            local_mem_access: rt.postprocess(),
-        };
+        if rt.emits_events() {
+            let lookup_id = rt.syscall_lookup_id;
+            let shard = rt.current_shard();
+            let event = FpOpEvent {
+                lookup_id,
+                shard,
+                clk,
+                x_ptr,
+                x,
+                y_ptr,
+                y,
+                op: self.op,
+                x_memory_records,
+                y_memory_records,
+                local_mem_access: rt.postprocess(),
+            };
 
-        // Since all the Fp events are on the same table, we need to preserve the ordering of the
-        // events b/c of the nonce.  In this table's trace_gen, the nonce is simply the row number.
-        // Group all of the events for a specific curve into the same syscall code key.
-        // TODO:  FIX THIS.
+            // Since all the Fp events are on the same table, we need to preserve the ordering of
+            // the events b/c of the nonce.  In this table's trace_gen, the nonce is simply the row
+            // number. The events for a specific curve are grouped into the same syscall code key
+            // by `SyscallCode::count_map`.
+            let syscall_event =
+                rt.rt
+                    .syscall_event(clk, syscall_code.syscall_id(), arg1, arg2, lookup_id);
+            match P::FIELD_TYPE {
+                FieldType::Bn254 => rt.record_mut().add_precompile_event(
+                    syscall_code,
+                    syscall_event,
+                    PrecompileEvent::Bn254Fp(event),
+                ),
+                FieldType::Bls12381 => rt.record_mut().add_precompile_event(
+                    syscall_code,
+                    syscall_event,
+                    PrecompileEvent::Bls12381Fp(event),
+                ),
+            }
+        }
 
-        None
+        Ok(None)
     }
 
     fn num_extra_cycles(&self) -> u32 {
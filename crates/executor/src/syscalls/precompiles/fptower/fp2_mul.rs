@@ -1,12 +1,15 @@
 use std::marker::PhantomData;
 
-use num::BigUint;
-use sp1_curves::{params::NumWords, weierstrass::FpOpField};
+use sp1_curves::{
+    params::NumWords,
+    weierstrass::{fp::FpElement, FieldType, FpOpField},
+};
 use typenum::Unsigned;
 
 use crate::{
-    events::Fp2MulEvent,
+    events::{Fp2MulEvent, PrecompileEvent},
     syscalls::{Syscall, SyscallCode, SyscallContext},
+    ExecutionError,
 };
 
 pub struct Fp2MulSyscall<P> {
@@ -28,18 +31,13 @@ impl<P: FpOpField> Syscall for Fp2MulSyscall<P> {
         syscall_code: SyscallCode,
         arg1: u32,
         arg2: u32,
-    ) -> Option<u32> {
+    ) -> Result<Option<u32>, ExecutionError> {
         let clk = rt.clk;
+        let num_words = <P as NumWords>::WordsCurvePoint::USIZE;
         let x_ptr = arg1;
-        if x_ptr % 4 != 0 {
-            panic!();
-        }
+        rt.check_words(syscall_code as u32, x_ptr, num_words)?;
         let y_ptr = arg2;
-        if y_ptr % 4 != 0 {
-            panic!();
-        }
-
-        let num_words = <P as NumWords>::WordsCurvePoint::USIZE;
+        rt.check_words(syscall_code as u32, y_ptr, num_words)?;
 
         let x = rt.slice_unsafe(x_ptr, num_words);
         let (y_memory_records, y) = rt.mr_slice(y_ptr, num_words);
@@ -48,57 +46,52 @@ impl<P: FpOpField> Syscall for Fp2MulSyscall<P> {
         let (ac0, ac1) = x.split_at(x.len() / 2);
         let (bc0, bc1) = y.split_at(y.len() / 2);
 
-        let ac0 = &BigUint::from_slice(ac0);
-        let ac1 = &BigUint::from_slice(ac1);
-        let bc0 = &BigUint::from_slice(bc0);
-        let bc1 = &BigUint::from_slice(bc1);
-        let modulus = &BigUint::from_bytes_le(P::MODULUS);
+        let ac0 = P::Fp::from_words(ac0);
+        let ac1 = P::Fp::from_words(ac1);
+        let bc0 = P::Fp::from_words(bc0);
+        let bc1 = P::Fp::from_words(bc1);
 
-        #[allow(clippy::match_bool)]
-        let c0 = match (ac0 * bc0) % modulus < (ac1 * bc1) % modulus {
-            true => ((modulus + (ac0 * bc0) % modulus) - (ac1 * bc1) % modulus) % modulus,
-            false => ((ac0 * bc0) % modulus - (ac1 * bc1) % modulus) % modulus,
-        };
-        let c1 = ((ac0 * bc1) % modulus + (ac1 * bc0) % modulus) % modulus;
+        let c0 = ac0.mul(&bc0).sub(&ac1.mul(&bc1));
+        let c1 = ac0.mul(&bc1).add(&ac1.mul(&bc0));
 
-        let mut result = c0
-            .to_u32_digits()
-            .into_iter()
-            .chain(c1.to_u32_digits())
-            .collect::<Vec<u32>>();
-
-        result.resize(num_words, 0);
+        let mut result = vec![0; num_words];
+        let (result0, result1) = result.split_at_mut(num_words / 2);
+        c0.to_words(result0);
+        c1.to_words(result1);
         let x_memory_records = rt.mw_slice(x_ptr, &result);
 
-        let lookup_id = rt.syscall_lookup_id;
-        let shard = rt.current_shard();
-        let event = Fp2MulEvent {
-            lookup_id,
-            shard,
-            clk,
-            x_ptr,
-            x,
-            y_ptr,
-            y,
-            x_memory_records,
-            y_memory_records,
-            local_mem_access: rt.postprocess(),
-        };
-        // let syscall_event =
-        //     rt.rt.syscall_event(clk, syscall_code.syscall_id(), arg1, arg2, event.lookup_id);
-        // match P::FIELD_TYPE {
-        //     FieldType::Bn254 => rt.record_mut().add_precompile_event(
-        //         syscall_code,
-        //         syscall_event,
-        //         PrecompileEvent::Bn254Fp2Mul(event),
-        //     ),
-        //     FieldType::Bls12381 => rt.record_mut().add_precompile_event(
-        //         syscall_code,
-        //         syscall_event,
-        //         PrecompileEvent::Bls12381Fp2Mul(event),
-        //     ),
-        // };
-        None
+        if rt.emits_events() {
+            let lookup_id = rt.syscall_lookup_id;
+            let shard = rt.current_shard();
+            let event = Fp2MulEvent {
+                lookup_id,
+                shard,
+                clk,
+                x_ptr,
+                x,
+                y_ptr,
+                y,
+                x_memory_records,
+                y_memory_records,
+                local_mem_access: rt.postprocess(),
+            };
+            let syscall_event =
+                rt.rt
+                    .syscall_event(clk, syscall_code.syscall_id(), arg1, arg2, event.lookup_id);
+            match P::FIELD_TYPE {
+                FieldType::Bn254 => rt.record_mut().add_precompile_event(
+                    syscall_code,
+                    syscall_event,
+                    PrecompileEvent::Bn254Fp2Mul(event),
+                ),
+                FieldType::Bls12381 => rt.record_mut().add_precompile_event(
+                    syscall_code,
+                    syscall_event,
+                    PrecompileEvent::Bls12381Fp2Mul(event),
+                ),
+            }
+        }
+        Ok(None)
     }
 
     fn num_extra_cycles(&self) -> u32 {
@@ -1,6 +1,7 @@
 use crate::{
     events::{PrecompileEvent, ShaCompressEvent},
     syscalls::{Syscall, SyscallCode, SyscallContext},
+    ExecutionError,
 };
 
 pub const SHA_COMPRESS_K: [u32; 64] = [
@@ -29,25 +30,26 @@ impl Syscall for Sha256CompressSyscall {
         syscall_code: SyscallCode,
         arg1: u32,
         arg2: u32,
-    ) -> Option<u32> {
+    ) -> Result<Option<u32>, ExecutionError> {
         let w_ptr = arg1;
         let h_ptr = arg2;
-        assert_ne!(w_ptr, h_ptr);
+        if w_ptr == h_ptr {
+            return Err(ExecutionError::InvalidSyscallArgument(
+                syscall_code as u32,
+                h_ptr,
+            ));
+        }
+        rt.check_words(syscall_code as u32, w_ptr, 64)?;
+        rt.check_words(syscall_code as u32, h_ptr, 8)?;
 
         let start_clk = rt.clk;
-        let mut h_read_records = Vec::new();
-        let mut w_i_read_records = Vec::new();
-        let mut h_write_records = Vec::new();
 
         // Execute the "initialize" phase where we read in the h values.
-        let mut hx = [0u32; 8];
-        for i in 0..8 {
-            let (record, value) = rt.mr(h_ptr + i as u32 * 4);
-            h_read_records.push(record);
-            hx[i] = value;
-        }
+        let (h_read_records, hx) = rt.mr_array::<8>(h_ptr);
+
+        // All of w is read at the start clock, so it is read at once.
+        let (w_i_read_records, original_w) = rt.mr_array::<64>(w_ptr);
 
-        let mut original_w = Vec::new();
         // Execute the "compress" phase.
         let mut a = hx[0];
         let mut b = hx[1];
@@ -60,14 +62,11 @@ impl Syscall for Sha256CompressSyscall {
         for i in 0..64 {
             let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
             let ch = (e & f) ^ (!e & g);
-            let (record, w_i) = rt.mr(w_ptr + i * 4);
-            original_w.push(w_i);
-            w_i_read_records.push(record);
             let temp1 = h
                 .wrapping_add(s1)
                 .wrapping_add(ch)
-                .wrapping_add(SHA_COMPRESS_K[i as usize])
-                .wrapping_add(w_i);
+                .wrapping_add(SHA_COMPRESS_K[i])
+                .wrapping_add(original_w[i]);
             let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
             let maj = (a & b) ^ (a & c) ^ (b & c);
             let temp2 = s0.wrapping_add(maj);
@@ -87,31 +86,33 @@ impl Syscall for Sha256CompressSyscall {
 
         // Execute the "finalize" phase.
         let v = [a, b, c, d, e, f, g, h];
-        for i in 0..8 {
-            let record = rt.mw(h_ptr + i as u32 * 4, hx[i].wrapping_add(v[i]));
-            h_write_records.push(record);
-        }
+        let h_write_records =
+            rt.mw_array(h_ptr, &std::array::from_fn(|i| hx[i].wrapping_add(v[i])));
 
-        // Push the SHA extend event.
-        let lookup_id = rt.syscall_lookup_id;
-        let shard = rt.current_shard();
-        let event = PrecompileEvent::ShaCompress(ShaCompressEvent {
-            lookup_id,
-            shard,
-            clk: start_clk,
-            w_ptr,
-            h_ptr,
-            w: original_w,
-            h: hx,
-            h_read_records: h_read_records.try_into().unwrap(),
-            w_i_read_records,
-            h_write_records: h_write_records.try_into().unwrap(),
-            local_mem_access: rt.postprocess(),
-        });
-        // let syscall_event =
-        //     rt.rt.syscall_event(start_clk, syscall_code.syscall_id(), arg1, arg2, lookup_id);
-        // rt.record_mut().add_precompile_event(syscall_code, syscall_event, event);
+        // Push the SHA compress event.
+        if rt.emits_events() {
+            let lookup_id = rt.syscall_lookup_id;
+            let shard = rt.current_shard();
+            let event = PrecompileEvent::ShaCompress(ShaCompressEvent {
+                lookup_id,
+                shard,
+                clk: start_clk,
+                w_ptr,
+                h_ptr,
+                w: original_w.to_vec(),
+                h: hx,
+                h_read_records,
+                w_i_read_records: w_i_read_records.to_vec(),
+                h_write_records,
+                local_mem_access: rt.postprocess(),
+            });
+            let syscall_event =
+                rt.rt
+                    .syscall_event(start_clk, syscall_code.syscall_id(), arg1, arg2, lookup_id);
+            rt.record_mut()
+                .add_precompile_event(syscall_code, syscall_event, event);
+        }
 
-        None
+        Ok(None)
     }
 }
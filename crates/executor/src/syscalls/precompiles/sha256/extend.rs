@@ -1,6 +1,7 @@
 use crate::{
     events::{PrecompileEvent, ShaExtendEvent},
     syscalls::{Syscall, SyscallCode, SyscallContext},
+    ExecutionError,
 };
 
 pub(crate) struct Sha256ExtendSyscall;
@@ -16,10 +17,16 @@ impl Syscall for Sha256ExtendSyscall {
         syscall_code: SyscallCode,
         arg1: u32,
         arg2: u32,
-    ) -> Option<u32> {
+    ) -> Result<Option<u32>, ExecutionError> {
         let clk_init = rt.clk;
         let w_ptr = arg1;
-        assert!(arg2 == 0, "arg2 must be 0");
+        if arg2 != 0 {
+            return Err(ExecutionError::InvalidSyscallArgument(
+                syscall_code as u32,
+                arg2,
+            ));
+        }
+        rt.check_words(syscall_code as u32, w_ptr, 64)?;
 
         let w_ptr_init = w_ptr;
         let mut w_i_minus_15_reads = Vec::new();
@@ -64,24 +71,28 @@ impl Syscall for Sha256ExtendSyscall {
         }
 
         // Push the SHA extend event.
-        let lookup_id = rt.syscall_lookup_id;
-        let shard = rt.current_shard();
-        let event = PrecompileEvent::ShaExtend(ShaExtendEvent {
-            lookup_id,
-            shard,
-            clk: clk_init,
-            w_ptr: w_ptr_init,
-            w_i_minus_15_reads,
-            w_i_minus_2_reads,
-            w_i_minus_16_reads,
-            w_i_minus_7_reads,
-            w_i_writes,
-            local_mem_access: rt.postprocess(),
-        });
-        // let syscall_event =
-        //     rt.rt.syscall_event(clk_init, syscall_code.syscall_id(), arg1, arg2, lookup_id);
-        // rt.record_mut().add_precompile_event(syscall_code, syscall_event, event);
+        if rt.emits_events() {
+            let lookup_id = rt.syscall_lookup_id;
+            let shard = rt.current_shard();
+            let event = PrecompileEvent::ShaExtend(ShaExtendEvent {
+                lookup_id,
+                shard,
+                clk: clk_init,
+                w_ptr: w_ptr_init,
+                w_i_minus_15_reads,
+                w_i_minus_2_reads,
+                w_i_minus_16_reads,
+                w_i_minus_7_reads,
+                w_i_writes,
+                local_mem_access: rt.postprocess(),
+            });
+            let syscall_event =
+                rt.rt
+                    .syscall_event(clk_init, syscall_code.syscall_id(), arg1, arg2, lookup_id);
+            rt.record_mut()
+                .add_precompile_event(syscall_code, syscall_event, event);
+        }
 
-        None
+        Ok(None)
     }
 }
@@ -1,11 +1,10 @@
-use num::{BigUint, One, Zero};
-
 use sp1_curves::edwards::WORDS_FIELD_ELEMENT;
-use sp1_primitives::consts::{bytes_to_words_le, words_to_bytes_le_vec, WORD_SIZE};
+use sp1_primitives::consts::WORD_SIZE;
 
 use crate::{
     events::{PrecompileEvent, Uint256MulEvent},
     syscalls::{Syscall, SyscallCode, SyscallContext},
+    ExecutionError,
 };
 
 pub(crate) struct Uint256MulSyscall;
@@ -17,77 +16,162 @@ impl Syscall for Uint256MulSyscall {
         syscall_code: SyscallCode,
         arg1: u32,
         arg2: u32,
-    ) -> Option<u32> {
+    ) -> Result<Option<u32>, ExecutionError> {
         let clk = rt.clk;
 
         let x_ptr = arg1;
-        if x_ptr % 4 != 0 {
-            panic!();
-        }
+        rt.check_words(syscall_code as u32, x_ptr, WORDS_FIELD_ELEMENT)?;
+        // The modulus follows the y value.
         let y_ptr = arg2;
-        if y_ptr % 4 != 0 {
-            panic!();
-        }
+        rt.check_words(syscall_code as u32, y_ptr, 2 * WORDS_FIELD_ELEMENT)?;
 
-        // First read the words for the x value. We can read a slice_unsafe here because we write
-        // the computed result to x later.
-        let x = rt.slice_unsafe(x_ptr, WORDS_FIELD_ELEMENT);
+        // First read the words for the x value. We can read the words unsafely here because we
+        // write the computed result to x later.
+        let x = rt.array_unsafe::<WORDS_FIELD_ELEMENT>(x_ptr);
 
         // Read the y value.
-        let (y_memory_records, y) = rt.mr_slice(y_ptr, WORDS_FIELD_ELEMENT);
+        let (y_memory_records, y) = rt.mr_array::<WORDS_FIELD_ELEMENT>(y_ptr);
 
         // The modulus is stored after the y value. We increment the pointer by the number of words.
         let modulus_ptr = y_ptr + WORDS_FIELD_ELEMENT as u32 * WORD_SIZE as u32;
-        let (modulus_memory_records, modulus) = rt.mr_slice(modulus_ptr, WORDS_FIELD_ELEMENT);
-
-        // Get the BigUint values for x, y, and the modulus.
-        let uint256_x = BigUint::from_bytes_le(&words_to_bytes_le_vec(&x));
-        let uint256_y = BigUint::from_bytes_le(&words_to_bytes_le_vec(&y));
-        let uint256_modulus = BigUint::from_bytes_le(&words_to_bytes_le_vec(&modulus));
+        let (modulus_memory_records, modulus) = rt.mr_array::<WORDS_FIELD_ELEMENT>(modulus_ptr);
 
         // Perform the multiplication and take the result modulo the modulus.
-        let result: BigUint = if uint256_modulus.is_zero() {
-            let modulus = BigUint::one() << 256;
-            (uint256_x * uint256_y) % modulus
-        } else {
-            (uint256_x * uint256_y) % uint256_modulus
-        };
-
-        let mut result_bytes = result.to_bytes_le();
-        result_bytes.resize(32, 0u8); // Pad the result to 32 bytes.
-
-        // Convert the result to little endian u32 words.
-        let result = bytes_to_words_le::<8>(&result_bytes);
+        let result = mul_mod(&x, &y, &modulus);
 
         // Increment clk so that the write is not at the same cycle as the read.
         rt.clk += 1;
         // Write the result to x and keep track of the memory records.
-        let x_memory_records = rt.mw_slice(x_ptr, &result);
-
-        let lookup_id = rt.syscall_lookup_id;
-        let shard = rt.current_shard();
-        let event = PrecompileEvent::Uint256Mul(Uint256MulEvent {
-            lookup_id,
-            shard,
-            clk,
-            x_ptr,
-            x,
-            y_ptr,
-            y,
-            modulus,
-            x_memory_records,
-            y_memory_records,
-            modulus_memory_records,
-            local_mem_access: rt.postprocess(),
-        });
-        // let sycall_event =
-        //     rt.rt.syscall_event(clk, syscall_code.syscall_id(), arg1, arg2, lookup_id);
-        // rt.record_mut().add_precompile_event(syscall_code, sycall_event, event);
-
-        None
+        let x_memory_records = rt.mw_array(x_ptr, &result);
+
+        if rt.emits_events() {
+            let lookup_id = rt.syscall_lookup_id;
+            let shard = rt.current_shard();
+            let event = PrecompileEvent::Uint256Mul(Uint256MulEvent {
+                lookup_id,
+                shard,
+                clk,
+                x_ptr,
+                x: x.to_vec(),
+                y_ptr,
+                y: y.to_vec(),
+                modulus: modulus.to_vec(),
+                x_memory_records: x_memory_records.to_vec(),
+                y_memory_records: y_memory_records.to_vec(),
+                modulus_memory_records: modulus_memory_records.to_vec(),
+                local_mem_access: rt.postprocess(),
+            });
+            let syscall_event =
+                rt.rt
+                    .syscall_event(clk, syscall_code.syscall_id(), arg1, arg2, lookup_id);
+            rt.record_mut()
+                .add_precompile_event(syscall_code, syscall_event, event);
+        }
+
+        Ok(None)
     }
 
     fn num_extra_cycles(&self) -> u32 {
         1
     }
 }
+
+/// Compute `x * y mod modulus` on little-endian words, where a zero modulus stands for `2^256`.
+fn mul_mod(x: &[u32; 8], y: &[u32; 8], modulus: &[u32; 8]) -> [u32; 8] {
+    let to_limbs = |words: &[u32; 8]| -> [u64; 4] {
+        std::array::from_fn(|i| u64::from(words[2 * i]) | (u64::from(words[2 * i + 1]) << 32))
+    };
+    let (x, y, modulus) = (to_limbs(x), to_limbs(y), to_limbs(modulus));
+
+    // The 512-bit product, with schoolbook multiplication.
+    let mut product = [0u64; 8];
+    for (i, &x) in x.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in y.iter().enumerate() {
+            let t = u128::from(x) * u128::from(y) + u128::from(product[i + j]) + carry;
+            product[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        product[i + 4] = carry as u64;
+    }
+
+    let result = if modulus == [0; 4] {
+        [product[0], product[1], product[2], product[3]]
+    } else {
+        rem(product, &modulus)
+    };
+    std::array::from_fn(|i| (result[i / 2] >> (32 * (i % 2))) as u32)
+}
+
+/// Compute `u mod v` for a non-zero `v`, with Knuth's long division (TAOCP 4.3.1, algorithm D).
+fn rem(u: [u64; 8], v: &[u64; 4]) -> [u64; 4] {
+    let len = v.iter().rposition(|&limb| limb != 0).unwrap() + 1;
+
+    // Normalize so that the top limb of the divisor has its high bit set, which keeps the estimate
+    // of each quotient limb off by at most two.
+    let shift = v[len - 1].leading_zeros();
+    let shl = |limbs: &[u64], i: usize| {
+        let high = limbs.get(i).copied().unwrap_or(0);
+        let low = i
+            .checked_sub(1)
+            .and_then(|i| limbs.get(i))
+            .copied()
+            .unwrap_or(0);
+        if shift == 0 {
+            high
+        } else {
+            (high << shift) | (low >> (64 - shift))
+        }
+    };
+    let v: [u64; 4] = std::array::from_fn(|i| shl(&v[..len], i));
+    let mut u: [u64; 9] = std::array::from_fn(|i| shl(&u, i));
+
+    for j in (0..=8 - len).rev() {
+        let top = (u128::from(u[j + len]) << 64) | u128::from(u[j + len - 1]);
+        let mut qhat = top / u128::from(v[len - 1]);
+        let mut rhat = top % u128::from(v[len - 1]);
+        while qhat >> 64 != 0
+            || (len > 1
+                && qhat * u128::from(v[len - 2]) > (rhat << 64) | u128::from(u[j + len - 2]))
+        {
+            qhat -= 1;
+            rhat += u128::from(v[len - 1]);
+            if rhat >> 64 != 0 {
+                break;
+            }
+        }
+
+        // u[j..=j + len] -= qhat * v
+        let mut borrow: i128 = 0;
+        for i in 0..len {
+            let product = qhat * u128::from(v[i]);
+            let limb = i128::from(u[i + j]) - borrow - i128::from(product as u64);
+            u[i + j] = limb as u64;
+            borrow = (product >> 64) as i128 - (limb >> 64);
+        }
+        let limb = i128::from(u[j + len]) - borrow;
+        u[j + len] = limb as u64;
+
+        // The estimate was one too large, so add the divisor back.
+        if limb < 0 {
+            let mut carry = 0;
+            for i in 0..len {
+                let limb = u128::from(u[i + j]) + u128::from(v[i]) + carry;
+                u[i + j] = limb as u64;
+                carry = limb >> 64;
+            }
+            u[j + len] = u[j + len].wrapping_add(carry as u64);
+        }
+    }
+
+    // Undo the normalization of the remainder, which is in the low `len` limbs.
+    std::array::from_fn(|i| {
+        if i >= len {
+            0
+        } else if shift == 0 {
+            u[i]
+        } else {
+            (u[i] >> shift) | (u[i + 1] << (64 - shift))
+        }
+    })
+}
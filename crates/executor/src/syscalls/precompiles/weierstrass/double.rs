@@ -1,10 +1,11 @@
 use std::marker::PhantomData;
 
-use sp1_curves::EllipticCurve;
+use sp1_curves::{CurveType, EllipticCurve};
 
 use crate::{
-    events::create_ec_double_event,
+    events::{create_ec_double_event, PrecompileEvent},
     syscalls::{Syscall, SyscallCode, SyscallContext},
+    ExecutionError,
 };
 
 pub(crate) struct WeierstrassDoubleAssignSyscall<E: EllipticCurve> {
@@ -27,35 +28,42 @@ impl<E: EllipticCurve> Syscall for WeierstrassDoubleAssignSyscall<E> {
         syscall_code: SyscallCode,
         arg1: u32,
         arg2: u32,
-    ) -> Option<u32> {
-        let event = create_ec_double_event::<E>(rt, arg1, arg2);
-        // let syscall_event =
-        //     rt.rt.syscall_event(event.clk, syscall_code.syscall_id(), arg1, arg2, event.lookup_id);
-        // match E::CURVE_TYPE {
-        //     CurveType::Secp256k1 => {
-        //         rt.record_mut().add_precompile_event(
-        //             syscall_code,
-        //             syscall_event,
-        //             PrecompileEvent::Secp256k1Double(event),
-        //         );
-        //     }
-        //     CurveType::Bn254 => {
-        //         rt.record_mut().add_precompile_event(
-        //             syscall_code,
-        //             syscall_event,
-        //             PrecompileEvent::Bn254Double(event),
-        //         );
-        //     }
-        //     CurveType::Bls12381 => {
-        //         rt.record_mut().add_precompile_event(
-        //             syscall_code,
-        //             syscall_event,
-        //             PrecompileEvent::Bls12381Double(event),
-        //         );
-        //     }
-        //     _ => panic!("Unsupported curve"),
-        // }
-        None
+    ) -> Result<Option<u32>, ExecutionError> {
+        let Some(event) = create_ec_double_event::<E>(rt, syscall_code, arg1, arg2)? else {
+            return Ok(None);
+        };
+        let syscall_event = rt.rt.syscall_event(
+            event.clk,
+            syscall_code.syscall_id(),
+            arg1,
+            arg2,
+            event.lookup_id,
+        );
+        match E::CURVE_TYPE {
+            CurveType::Secp256k1 => {
+                rt.record_mut().add_precompile_event(
+                    syscall_code,
+                    syscall_event,
+                    PrecompileEvent::Secp256k1Double(event),
+                );
+            }
+            CurveType::Bn254 => {
+                rt.record_mut().add_precompile_event(
+                    syscall_code,
+                    syscall_event,
+                    PrecompileEvent::Bn254Double(event),
+                );
+            }
+            CurveType::Bls12381 => {
+                rt.record_mut().add_precompile_event(
+                    syscall_code,
+                    syscall_event,
+                    PrecompileEvent::Bls12381Double(event),
+                );
+            }
+            _ => panic!("Unsupported curve"),
+        }
+        Ok(None)
     }
 
     fn num_extra_cycles(&self) -> u32 {
@@ -1,57 +1,67 @@
 use hashbrown::HashMap;
 
-use crate::{state::ForkState, ExecutorMode};
+use crate::{state::ForkState, ExecutionError, ExecutorMode};
 
 use super::{Syscall, SyscallCode, SyscallContext};
 
 pub(crate) struct EnterUnconstrainedSyscall;
 
 impl Syscall for EnterUnconstrainedSyscall {
-    fn execute(&self, ctx: &mut SyscallContext, _: SyscallCode, _: u32, _: u32) -> Option<u32> {
+    fn execute(
+        &self,
+        ctx: &mut SyscallContext,
+        syscall_code: SyscallCode,
+        _: u32,
+        _: u32,
+    ) -> Result<Option<u32>, ExecutionError> {
+        // Unconstrained blocks can't be nested.
         if ctx.rt.unconstrained {
-            panic!("Unconstrained block is already active.");
+            return Err(ExecutionError::InvalidSyscallUsage(syscall_code as u64));
         }
         ctx.rt.unconstrained = true;
         ctx.rt.unconstrained_state = ForkState {
             global_clk: ctx.rt.state.global_clk,
             clk: ctx.rt.state.clk,
             pc: ctx.rt.state.pc,
+            registers: ctx.rt.state.registers,
+            register_timestamps: ctx.rt.state.register_timestamps,
             memory_diff: HashMap::default(),
-            // record: std::mem::take(&mut ctx.rt.record),
-            // op_record: std::mem::take(&mut ctx.rt.memory_accesses),
+            record: std::mem::take(&mut ctx.rt.record),
+            op_record: std::mem::take(&mut ctx.rt.memory_accesses),
             executor_mode: ctx.rt.executor_mode,
         };
         ctx.rt.executor_mode = ExecutorMode::Simple;
-        Some(1)
+        Ok(Some(1))
     }
 }
 
 pub(crate) struct ExitUnconstrainedSyscall;
 
 impl Syscall for ExitUnconstrainedSyscall {
-    fn execute(&self, ctx: &mut SyscallContext, _: SyscallCode, _: u32, _: u32) -> Option<u32> {
+    fn execute(
+        &self,
+        ctx: &mut SyscallContext,
+        _: SyscallCode,
+        _: u32,
+        _: u32,
+    ) -> Result<Option<u32>, ExecutionError> {
         // Reset the state of the runtime.
         if ctx.rt.unconstrained {
             ctx.rt.state.global_clk = ctx.rt.unconstrained_state.global_clk;
             ctx.rt.state.clk = ctx.rt.unconstrained_state.clk;
             ctx.rt.state.pc = ctx.rt.unconstrained_state.pc;
             ctx.next_pc = ctx.rt.state.pc.wrapping_add(4);
-            for (addr, value) in ctx.rt.unconstrained_state.memory_diff.drain() {
-                match value {
-                    Some(value) => {
-                        ctx.rt.state.memory.insert(addr, value);
-                    }
-                    None => {
-                        ctx.rt.state.memory.remove(&addr);
-                    }
-                }
+            ctx.rt.state.registers = ctx.rt.unconstrained_state.registers;
+            ctx.rt.state.register_timestamps = ctx.rt.unconstrained_state.register_timestamps;
+            for (addr, record) in ctx.rt.unconstrained_state.memory_diff.drain() {
+                ctx.rt.state.set_memory_record(addr, record);
             }
-            // ctx.rt.record = std::mem::take(&mut ctx.rt.unconstrained_state.record);
-            // ctx.rt.memory_accesses = std::mem::take(&mut ctx.rt.unconstrained_state.op_record);
+            ctx.rt.record = std::mem::take(&mut ctx.rt.unconstrained_state.record);
+            ctx.rt.memory_accesses = std::mem::take(&mut ctx.rt.unconstrained_state.op_record);
             ctx.rt.executor_mode = ctx.rt.unconstrained_state.executor_mode;
             ctx.rt.unconstrained = false;
         }
         ctx.rt.unconstrained_state = ForkState::default();
-        Some(0)
+        Ok(Some(0))
     }
 }
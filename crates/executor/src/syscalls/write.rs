@@ -1,6 +1,6 @@
 use sp1_primitives::consts::num_to_comma_separated;
 
-use crate::{Executor, Register};
+use crate::{ExecutionError, Executor, Register};
 
 use super::{Syscall, SyscallCode, SyscallContext};
 
@@ -33,33 +33,32 @@ impl Syscall for WriteSyscall {
         _: SyscallCode,
         arg1: u32,
         arg2: u32,
-    ) -> Option<u32> {
+    ) -> Result<Option<u32>, ExecutionError> {
         let a2 = Register::X12;
-        let rt = &mut ctx.rt;
         let fd = arg1;
         let write_buf = arg2;
-        let nbytes = rt.register(a2);
+        let nbytes = ctx.rt.register(a2);
+        ctx.check_bytes(write_buf, nbytes)?;
+        let rt = &mut ctx.rt;
         // Read nbytes from memory starting at write_buf.
         let bytes = (0..nbytes)
             .map(|i| rt.byte(write_buf + i))
             .collect::<Vec<u8>>();
         let slice = bytes.as_slice();
         if fd == 1 {
-            let s = core::str::from_utf8(slice).unwrap();
+            let s = &String::from_utf8_lossy(slice);
             match parse_cycle_tracker_command(s) {
                 Some(command) => handle_cycle_tracker_command(rt, command),
                 None => {
                     // If the string does not match any known command, print it to stdout.
                     let flush_s = update_io_buf(ctx, fd, s);
                     if !flush_s.is_empty() {
-                        flush_s
-                            .into_iter()
-                            .for_each(|_| {});
+                        flush_s.into_iter().for_each(|_| {});
                     }
                 }
             }
         } else if fd == 2 {
-            let s = core::str::from_utf8(slice).unwrap();
+            let s = &String::from_utf8_lossy(slice);
             let flush_s = update_io_buf(ctx, fd, s);
             if !flush_s.is_empty() {
                 flush_s
@@ -78,7 +77,7 @@ impl Syscall for WriteSyscall {
         } else {
             tracing::warn!("tried to write to unknown file descriptor {fd}");
         }
-        None
+        Ok(None)
     }
 }
 
@@ -88,7 +87,7 @@ enum CycleTrackerCommand {
     Start(String),
     End(String),
     ReportStart(String),
-    ReportEnd(()),
+    ReportEnd(String),
 }
 
 /// Parse a cycle tracker command from a string. If the string does not match any known command,
@@ -101,7 +100,7 @@ fn parse_cycle_tracker_command(s: &str) -> Option<CycleTrackerCommand> {
         "cycle-tracker-start" => Some(CycleTrackerCommand::Start(trimmed_name)),
         "cycle-tracker-end" => Some(CycleTrackerCommand::End(trimmed_name)),
         "cycle-tracker-report-start" => Some(CycleTrackerCommand::ReportStart(trimmed_name)),
-        "cycle-tracker-report-end" => Some(CycleTrackerCommand::ReportEnd(())),
+        "cycle-tracker-report-end" => Some(CycleTrackerCommand::ReportEnd(trimmed_name)),
         _ => None,
     }
 }
@@ -115,9 +114,12 @@ fn handle_cycle_tracker_command(rt: &mut Executor, command: CycleTrackerCommand)
         CycleTrackerCommand::End(name) => {
             end_cycle_tracker(rt, &name);
         }
-        CycleTrackerCommand::ReportEnd(_) => {
+        CycleTrackerCommand::ReportEnd(name) => {
             // Attempt to end the cycle tracker and accumulate the total cycles in the fn_name's
             // entry in the ExecutionReport.
+            if let Some(total_cycles) = end_cycle_tracker(rt, &name) {
+                *rt.report.cycle_tracker.entry(name).or_default() += total_cycles;
+            }
         }
     }
 }
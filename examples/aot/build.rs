@@ -0,0 +1,17 @@
+//! Compiles the program of the example to a Rust module, like `sp1-aot` does for an ELF.
+
+use std::{env, fs, path::Path};
+
+use sp1_core_executor::compile_to_rust;
+
+include!("src/program.rs");
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(
+        Path::new(&out_dir).join("blocks.rs"),
+        compile_to_rust(&program()),
+    )
+    .unwrap();
+    println!("cargo:rerun-if-changed=src/program.rs");
+}
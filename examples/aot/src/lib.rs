@@ -0,0 +1,26 @@
+//! An example of executing a program with blocks compiled ahead of time by `sp1-aot`.
+//!
+//! The build script compiles [`program`] to a Rust module, which is included as [`blocks`] and
+//! registered with [`Executor::set_aot_blocks`].
+
+use sp1_core_executor::{Executor, Program};
+
+mod program;
+
+pub use program::program;
+
+/// The blocks of [`program`], compiled ahead of time by the build script.
+///
+/// Generated modules must compile without warnings in the crates that include them.
+#[deny(warnings)]
+pub mod blocks {
+    include!(concat!(env!("OUT_DIR"), "/blocks.rs"));
+}
+
+/// Create an executor of `program` that runs its blocks compiled ahead of time.
+#[must_use]
+pub fn executor(program: Program) -> Executor<'static> {
+    let mut executor = Executor::new(program);
+    executor.set_aot_blocks(blocks::DIGEST, blocks::block);
+    executor
+}
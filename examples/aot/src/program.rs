@@ -0,0 +1,43 @@
+use sp1_core_executor::{syscalls::SyscallCode, Instruction, Opcode, Program};
+
+/// The program compiled ahead of time by the build script.
+///
+/// It sums a countdown into memory through a call, so that its blocks are entered from branches,
+/// jumps and returns. It then commits the sum as public values with an `ECALL`, which is left to
+/// the interpreter, and loads memory that it never initialized.
+#[must_use]
+pub fn program() -> Program {
+    let mut program = Program::new(
+        vec![
+            // The countdown, from the value at 0x10000.
+            Instruction::new(Opcode::ADD, 20, 0, 0x10000, false, true),
+            Instruction::new(Opcode::LW, 10, 20, 0, false, true),
+            // Add the counter to the sum at 0x10004 in a function.
+            Instruction::new(Opcode::JAL, 1, 36, 0, true, true),
+            Instruction::new(Opcode::ADD, 10, 10, u32::MAX, false, true),
+            Instruction::new(Opcode::BNE, 10, 0, 8u32.wrapping_neg(), false, true),
+            // Commit the sum.
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::WRITE as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 3, false, true),
+            Instruction::new(Opcode::ADD, 11, 20, 4, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            // Skip the function.
+            Instruction::new(Opcode::JAL, 0, 24, 0, true, true),
+            // The function, with a sign-extended byte load and a store.
+            Instruction::new(Opcode::LW, 21, 20, 4, false, true),
+            Instruction::new(Opcode::ADD, 21, 21, 10, false, false),
+            Instruction::new(Opcode::SW, 21, 20, 4, false, true),
+            Instruction::new(Opcode::LB, 22, 20, 8, false, true),
+            Instruction::new(Opcode::JALR, 0, 1, 0, false, true),
+            // Load uninitialized memory and the program counter.
+            Instruction::new(Opcode::LHU, 23, 20, 12, false, true),
+            Instruction::new(Opcode::AUIPC, 24, 0x1000, 0x1000, true, true),
+        ],
+        0,
+        0,
+    );
+    program.memory_image.insert(0x10000, 100);
+    program.memory_image.insert(0x10008, 0x80);
+    program
+}
@@ -0,0 +1,40 @@
+use sp1_aot_example::{blocks, executor, program};
+use sp1_core_executor::{Executor, Register};
+
+#[test]
+fn test_aot_matches_interpreter() {
+    let mut aot = executor(program());
+    aot.print_report = true;
+    aot.run_fast().unwrap();
+    let mut interpreted = Executor::new(program());
+    interpreted.print_report = true;
+    interpreted.run().unwrap();
+
+    assert_eq!(aot.registers(), interpreted.registers());
+    assert_eq!(aot.state.global_clk, interpreted.state.global_clk);
+    assert_eq!(aot.state.pc, interpreted.state.pc);
+    assert_eq!(aot.report.opcode_counts, interpreted.report.opcode_counts);
+    assert_eq!(
+        aot.state.public_values_stream,
+        interpreted.state.public_values_stream
+    );
+    assert_eq!(aot.word(0x10004), 5050);
+    assert_eq!(aot.state.public_values_stream, 5050u32.to_le_bytes());
+    assert_eq!(aot.register(Register::X22), 0xffff_ff80);
+    assert_eq!(aot.register(Register::X24), 0x1044);
+}
+
+#[test]
+fn test_aot_blocks() {
+    // Blocks start at the entry point, after each jump and branch and at their targets, and end
+    // before the `ECALL`, which is left to the interpreter.
+    let starts = (0..0x48)
+        .step_by(4)
+        .filter(|&pc| blocks::block(pc).is_some());
+    assert_eq!(
+        starts.collect::<Vec<_>>(),
+        [0x0, 0x8, 0xc, 0x14, 0x28, 0x2c, 0x40]
+    );
+    assert_eq!(blocks::block(0x14).map(|block| block.len), Some(4));
+    assert!(blocks::block(0x24).is_none());
+}